@@ -0,0 +1,122 @@
+use crate::gif::{self, GifOptions};
+use crate::{animated_image, ffmpeg_caps};
+
+// Formats that keep the RGBA input's alpha, and the encoder that does it
+fn alpha_encoder(format: &str) -> Option<&'static str> {
+    match format {
+        "webm" => Some("libvpx-vp9"),
+        "mov" => Some("prores_ks"),
+        "gif" => Some("gif"),
+        "png_sequence" => Some("png"),
+        _ => None,
+    }
+}
+
+/// Refuse alpha exports the format or the bundled ffmpeg can't honour, instead of
+/// silently flattening the background
+pub fn validate(format: &str) -> Result<(), String> {
+    // Every animated image format carries alpha; its own check covers the encoder
+    if animated_image::is_animated_image_format(format) {
+        return animated_image::validate(format);
+    }
+    // Written from the RGBA frames directly, no encoder involved
+    if format == "image_sequence" {
+        return Ok(());
+    }
+    let encoder = alpha_encoder(format).ok_or_else(|| {
+        format!(
+            "{} cannot carry transparency; use webm (VP9), mov (ProRes 4444), gif, an animated image or a PNG sequence",
+            format
+        )
+    })?;
+    if !ffmpeg_caps::has_encoder(encoder) {
+        return Err(format!("The bundled ffmpeg has no {} encoder for transparent {}", encoder, format));
+    }
+    Ok(())
+}
+
+/// Video encoding args that keep alpha; `scale_filter` is the usual output scale.
+/// Only meaningful for formats `validate` accepts.
+pub fn video_args(format: &str, scale_filter: String) -> Vec<String> {
+    match format {
+        "webm" => vec![
+            "-vf".to_string(),
+            scale_filter,
+            "-c:v".to_string(),
+            "libvpx-vp9".to_string(),
+            "-crf".to_string(),
+            "18".to_string(),
+            "-b:v".to_string(),
+            "0".to_string(),
+            // VP9 stores alpha as a second plane in BlockAdditional
+            "-pix_fmt".to_string(),
+            "yuva420p".to_string(),
+            "-deadline".to_string(),
+            "good".to_string(),
+            "-cpu-used".to_string(),
+            "2".to_string(),
+            "-row-mt".to_string(),
+            "1".to_string(),
+            "-threads".to_string(),
+            "0".to_string(),
+        ],
+        "mov" => vec![
+            "-vf".to_string(),
+            scale_filter,
+            "-c:v".to_string(),
+            "prores_ks".to_string(),
+            "-profile:v".to_string(),
+            "4".to_string(), // 4444
+            "-pix_fmt".to_string(),
+            "yuva444p10le".to_string(),
+            "-alpha_bits".to_string(),
+            "16".to_string(),
+            "-vendor".to_string(),
+            "apl0".to_string(), // Final Cut / Premiere treat it as Apple ProRes
+        ],
+        "gif" => gif::video_args(scale_filter, &GifOptions::default(), true),
+        _ => vec![
+            "-vf".to_string(),
+            scale_filter,
+            "-c:v".to_string(),
+            "png".to_string(),
+            "-pix_fmt".to_string(),
+            "rgba".to_string(),
+            "-f".to_string(),
+            "image2".to_string(),
+        ],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::value_of;
+
+    #[test]
+    fn formats_without_alpha_are_refused() {
+        for format in ["mp4", "mkv", "m4a"] {
+            let err = validate(format).unwrap_err();
+            assert!(err.starts_with(&format!("{} cannot carry transparency", format)), "{err}");
+        }
+        assert_eq!(validate("image_sequence"), Ok(()));
+    }
+
+    #[test]
+    fn args_keep_an_alpha_pixel_format() {
+        let pix_fmt = |format: &str| value_of(&video_args(format, "scale=2:2".into()), "-pix_fmt").map(str::to_string);
+        assert_eq!(pix_fmt("webm").as_deref(), Some("yuva420p"));
+        assert_eq!(pix_fmt("mov").as_deref(), Some("yuva444p10le"));
+        assert_eq!(pix_fmt("png_sequence").as_deref(), Some("rgba"));
+
+        let prores = video_args("mov", "scale=2:2".into());
+        assert_eq!(value_of(&prores, "-profile:v"), Some("4"));
+        assert_eq!(value_of(&prores, "-c:v"), Some("prores_ks"));
+    }
+
+    #[test]
+    fn gif_keeps_a_transparent_palette_slot() {
+        let args = video_args("gif", "scale=2:2".into());
+        assert!(value_of(&args, "-vf").unwrap().contains("reserve_transparent=1"));
+    }
+}
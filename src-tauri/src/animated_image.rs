@@ -0,0 +1,246 @@
+use serde::Deserialize;
+
+use crate::ffmpeg_caps;
+
+// libwebp quality for lossy output; for lossless it is the compression effort instead
+const WEBP_LOSSY_QUALITY: u32 = 80;
+const WEBP_LOSSLESS_EFFORT: u32 = 75;
+// Still-image AV1 quality - visually lossless on UI content at a fraction of the APNG size
+const AVIF_CRF: u32 = 30;
+
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct AnimationOptions {
+    // WebP only - APNG is always lossless, AVIF always lossy
+    #[serde(default)]
+    pub lossless: bool,
+    // 0-100, higher is better; WebP quality (effort when lossless), mapped onto CRF for AVIF
+    #[serde(default)]
+    pub quality: Option<u32>,
+    // Times to play the animation, 0 = forever
+    #[serde(default)]
+    pub loop_count: u32,
+}
+
+/// Animated image outputs of the streaming encoder
+pub fn is_animated_image_format(format: &str) -> bool {
+    matches!(format, "animated_webp" | "apng" | "avif")
+}
+
+// AVIF goes through the same AV1 encoders as the video export
+fn avif_encoder() -> Option<&'static str> {
+    ["libsvtav1", "libaom-av1"]
+        .into_iter()
+        .find(|name| ffmpeg_caps::has_encoder(name))
+}
+
+fn encoder_and_muxer(format: &str) -> Result<(&'static str, &'static str), String> {
+    match format {
+        "animated_webp" => Ok(("libwebp_anim", "webp")),
+        "apng" => Ok(("apng", "apng")),
+        "avif" => avif_encoder()
+            .map(|encoder| (encoder, "avif"))
+            .ok_or_else(|| "The bundled ffmpeg has no AV1 encoder for AVIF".to_string()),
+        _ => Err(format!("{} is not an animated image format", format)),
+    }
+}
+
+/// Check the bundled ffmpeg has both the encoder and the container for `format`
+pub fn validate(format: &str) -> Result<(), String> {
+    let (encoder, muxer) = encoder_and_muxer(format)?;
+    if !ffmpeg_caps::has_encoder(encoder) {
+        return Err(format!("The bundled ffmpeg has no {} encoder for {}", encoder, format));
+    }
+    if !ffmpeg_caps::has_muxer(muxer) {
+        return Err(format!("The bundled ffmpeg cannot write {} files", muxer));
+    }
+    Ok(())
+}
+
+/// Encoder name for size estimates; falls back to a plausible name when unavailable
+pub fn encoder(format: &str) -> &'static str {
+    encoder_and_muxer(format).map(|(encoder, _)| encoder).unwrap_or("libwebp_anim")
+}
+
+/// CRF the AVIF encoder runs at for a 0-100 `quality`
+fn avif_crf(quality: Option<u32>) -> u32 {
+    match quality {
+        // 100 -> crf 10, 0 -> crf 60
+        Some(q) => 60 - q.min(100) / 2,
+        None => AVIF_CRF,
+    }
+}
+
+/// Video encoding args for an animated image. `alpha` keeps the RGBA input's transparency.
+/// Only meaningful for formats `validate` accepts; colour tags are added by the caller.
+pub fn video_args(format: &str, scale_filter: String, options: &AnimationOptions, alpha: bool) -> Vec<String> {
+    match format {
+        "animated_webp" => {
+            let pix_fmt = match (options.lossless, alpha) {
+                // Lossless keeps full-resolution chroma; libwebp takes it as BGRA
+                (true, _) => "bgra",
+                (false, true) => "yuva420p",
+                (false, false) => "yuv420p",
+            };
+            let quality = options
+                .quality
+                .unwrap_or(if options.lossless { WEBP_LOSSLESS_EFFORT } else { WEBP_LOSSY_QUALITY })
+                .min(100);
+            vec![
+                "-vf".to_string(),
+                scale_filter,
+                "-c:v".to_string(),
+                "libwebp_anim".to_string(),
+                "-lossless".to_string(),
+                if options.lossless { "1" } else { "0" }.to_string(),
+                "-quality".to_string(),
+                quality.to_string(),
+                "-compression_level".to_string(),
+                "4".to_string(),
+                "-pix_fmt".to_string(),
+                pix_fmt.to_string(),
+                "-loop".to_string(),
+                options.loop_count.to_string(),
+                "-f".to_string(),
+                "webp".to_string(),
+            ]
+        }
+        "apng" => vec![
+            "-vf".to_string(),
+            scale_filter,
+            "-c:v".to_string(),
+            "apng".to_string(),
+            // Per-row filter choice - slowest but smallest
+            "-pred".to_string(),
+            "mixed".to_string(),
+            "-pix_fmt".to_string(),
+            if alpha { "rgba" } else { "rgb24" }.to_string(),
+            "-plays".to_string(),
+            options.loop_count.to_string(),
+            "-f".to_string(),
+            "apng".to_string(),
+        ],
+        _ => avif_args(encoder(format), scale_filter, options, alpha),
+    }
+}
+
+// AVIF args for a given AV1 encoder, split out so they don't depend on the bundled ffmpeg
+fn avif_args(encoder: &str, scale_filter: String, options: &AnimationOptions, alpha: bool) -> Vec<String> {
+    let mut args = Vec::new();
+    if alpha {
+        // AVIF stores alpha as a second, monochrome AV1 stream next to the colour one
+        args.extend([
+            "-filter_complex".to_string(),
+            format!("[0:v]{},format=yuva420p,alphaextract[alpha]", scale_filter),
+            "-map".to_string(),
+            "[alpha]".to_string(),
+        ]);
+    }
+    args.extend([
+        // Only the colour stream - the alpha one is already scaled in the complex graph
+        "-filter:v:0".to_string(),
+        scale_filter,
+        "-c:v".to_string(),
+        encoder.to_string(),
+        "-crf".to_string(),
+        avif_crf(options.quality).to_string(),
+    ]);
+    if encoder == "libsvtav1" {
+        args.extend(["-preset".to_string(), "8".to_string()]);
+    } else {
+        args.extend([
+            "-b:v".to_string(),
+            "0".to_string(),
+            "-cpu-used".to_string(),
+            "6".to_string(),
+            "-row-mt".to_string(),
+            "1".to_string(),
+        ]);
+    }
+    // Pixel format of the colour stream only; an unqualified -pix_fmt would turn the alpha
+    // plane into 4:2:0 colour too
+    args.extend(["-pix_fmt:v:0".to_string(), "yuv420p".to_string()]);
+    // The alpha stream is monochrome with libaom. SVT-AV1 has no monochrome input, so there
+    // ffmpeg pads the alpha plane with flat chroma instead
+    if alpha && encoder == "libaom-av1" {
+        args.extend(["-pix_fmt:v:1".to_string(), "gray".to_string()]);
+    }
+    args.extend([
+        "-loop".to_string(),
+        options.loop_count.to_string(),
+        "-f".to_string(),
+        "avif".to_string(),
+    ]);
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::value_of;
+
+    #[test]
+    fn avif_crf_maps_quality() {
+        assert_eq!(avif_crf(None), AVIF_CRF);
+        assert_eq!(avif_crf(Some(100)), 10);
+        assert_eq!(avif_crf(Some(0)), 60);
+        assert_eq!(avif_crf(Some(50)), 35);
+        // Out-of-range quality is clamped, not underflowed
+        assert_eq!(avif_crf(Some(250)), 10);
+    }
+
+    #[test]
+    fn avif_alpha_keeps_pix_fmt_off_the_alpha_stream() {
+        let options = AnimationOptions::default();
+        for encoder in ["libsvtav1", "libaom-av1"] {
+            let args = avif_args(encoder, "scale=640:360".into(), &options, true);
+            assert!(!args.iter().any(|arg| arg == "-pix_fmt"), "{encoder}: {args:?}");
+            assert_eq!(value_of(&args, "-pix_fmt:v:0"), Some("yuv420p"));
+            assert_eq!(value_of(&args, "-map"), Some("[alpha]"));
+            assert_eq!(
+                value_of(&args, "-filter_complex"),
+                Some("[0:v]scale=640:360,format=yuva420p,alphaextract[alpha]")
+            );
+        }
+        let aom = avif_args("libaom-av1", "scale=640:360".into(), &options, true);
+        assert_eq!(value_of(&aom, "-pix_fmt:v:1"), Some("gray"));
+    }
+
+    #[test]
+    fn avif_without_alpha_has_one_stream() {
+        let args = avif_args("libsvtav1", "scale=640:360".into(), &AnimationOptions::default(), false);
+        assert_eq!(value_of(&args, "-filter_complex"), None);
+        assert_eq!(value_of(&args, "-pix_fmt:v:1"), None);
+        assert_eq!(value_of(&args, "-preset"), Some("8"));
+        assert_eq!(value_of(&args, "-f"), Some("avif"));
+    }
+
+    #[test]
+    fn webp_pix_fmt_follows_lossless_and_alpha() {
+        let lossy = AnimationOptions::default();
+        let lossless = AnimationOptions {
+            lossless: true,
+            ..Default::default()
+        };
+        let pix_fmt = |options: &AnimationOptions, alpha| {
+            let args = video_args("animated_webp", "scale=2:2".into(), options, alpha);
+            value_of(&args, "-pix_fmt").map(str::to_string)
+        };
+        assert_eq!(pix_fmt(&lossy, false).as_deref(), Some("yuv420p"));
+        assert_eq!(pix_fmt(&lossy, true).as_deref(), Some("yuva420p"));
+        assert_eq!(pix_fmt(&lossless, true).as_deref(), Some("bgra"));
+        let args = video_args("animated_webp", "scale=2:2".into(), &lossless, false);
+        assert_eq!(value_of(&args, "-quality"), Some("75"));
+    }
+
+    #[test]
+    fn loop_count_is_passed_through() {
+        let options = AnimationOptions {
+            loop_count: 3,
+            ..Default::default()
+        };
+        let apng = video_args("apng", "scale=2:2".into(), &options, false);
+        assert_eq!(value_of(&apng, "-plays"), Some("3"));
+        let webp = video_args("animated_webp", "scale=2:2".into(), &options, false);
+        assert_eq!(value_of(&webp, "-loop"), Some("3"));
+    }
+}
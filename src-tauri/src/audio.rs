@@ -0,0 +1,866 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use crate::{path_scope, probe};
+
+// atempo only accepts 0.5..=2.0 per instance on older ffmpeg builds; larger factors are chained
+const ATEMPO_MIN: f64 = 0.5;
+const ATEMPO_MAX: f64 = 2.0;
+const MIN_PLAYBACK_RATE: f64 = 0.0625;
+const MAX_PLAYBACK_RATE: f64 = 16.0;
+// Peak ceiling of the final mix (-1 dBFS); amix no longer scales inputs down, so sums can clip
+const LIMITER_CEILING: f64 = 0.891;
+// EBU R128 stage: true-peak ceiling and the loudness range we allow before loudnorm
+// would have to compress (it falls back to dynamic mode when LRA exceeds the target)
+const LOUDNESS_TRUE_PEAK: f64 = -1.5;
+const LOUDNESS_MIN_LRA: f64 = 11.0;
+const LOUDNESS_MAX_LRA: f64 = 50.0;
+// Default output rate; loudnorm upsamples to 192 kHz internally and is resampled back to it
+const OUTPUT_SAMPLE_RATE: u32 = 48_000;
+// Upper bound on crossfaded repeats of a looping track (each is an asplit branch)
+const MAX_LOOP_COPIES: u64 = 256;
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct AudioTrack {
+    pub path: String,
+    // Where the clip starts on the timeline
+    pub delay_ms: u64,
+    // Part of the source file the clip uses; None = from the start / to the end
+    #[serde(default)]
+    pub source_in_ms: Option<u64>,
+    #[serde(default)]
+    pub source_out_ms: Option<u64>,
+    #[serde(default)]
+    pub playback_rate: Option<f64>,
+    #[serde(default)]
+    pub volume_db: Option<f64>,
+    #[serde(default)]
+    pub fade_in_ms: Option<u64>,
+    #[serde(default)]
+    pub fade_out_ms: Option<u64>,
+    #[serde(default)]
+    pub mute: bool,
+    #[serde(default)]
+    pub role: AudioRole,
+    // Repeat the clip until the end of the export
+    #[serde(default, rename = "loop")]
+    pub looped: bool,
+    // Overlap between repeats; 0 = hard, sample-exact loop
+    #[serde(default)]
+    pub loop_crossfade_ms: Option<u64>,
+    // Probed length of the source, needed to place the fade-out of untrimmed tracks
+    #[serde(skip)]
+    pub source_duration_ms: Option<u64>,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AudioRole {
+    #[default]
+    Music,
+    // Audio recorded with a video clip - what the music ducks under
+    Camera,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AudioCodec {
+    // The format's default: AAC-LC for mp4/mov/m4a, Opus for webm/opus, PCM for wav, FLAC for flac
+    #[default]
+    Auto,
+    Aac,
+    Opus,
+    // Uncompressed: 16-bit in mov, 24-bit in wav
+    Pcm,
+    Flac,
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct AudioOptions {
+    #[serde(default)]
+    pub codec: AudioCodec,
+    #[serde(default)]
+    pub bitrate_kbps: Option<u32>,
+    #[serde(default)]
+    pub sample_rate: Option<u32>,
+    // Forced output channel count, 1 or 2 (default stereo)
+    #[serde(default)]
+    pub channels: Option<u32>,
+    // Lower music tracks while camera audio is playing
+    #[serde(default)]
+    pub duck_music: bool,
+    // Integrated loudness target in LUFS (e.g. -14 YouTube/social, -16 App Store, -23 broadcast);
+    // None skips the two-pass loudnorm stage
+    #[serde(default)]
+    pub loudness_target: Option<f64>,
+    // Short fade at the very end of the export so a cut-off track doesn't click
+    #[serde(default)]
+    pub tail_fade_ms: Option<u64>,
+    // Add a silent track when there is nothing to mix (App Store previews reject video-only files)
+    #[serde(default)]
+    pub silent_track: bool,
+}
+
+/// Length the exported audio is conformed to: exactly `frames / fps`, counted in samples.
+/// The frame rate is kept as a fraction so NTSC rates (30000/1001) stay exact.
+#[derive(Clone, Copy, Debug)]
+pub struct Conform {
+    pub frames: u32,
+    pub fps_num: u32,
+    pub fps_den: u32,
+    pub sample_rate: u32,
+    pub tail_fade_ms: u64,
+}
+
+impl Conform {
+    pub fn new(format: &str, options: &AudioOptions, frames: u32, fps: u32) -> Self {
+        Self::with_frame_rate(format, options, frames, fps, 1)
+    }
+
+    /// `new` for fractional frame rates, e.g. 30000/1001 for 29.97 fps
+    pub fn with_frame_rate(format: &str, options: &AudioOptions, frames: u32, fps_num: u32, fps_den: u32) -> Self {
+        Conform {
+            frames,
+            fps_num: fps_num.max(1),
+            fps_den: fps_den.max(1),
+            sample_rate: output_sample_rate(format, options),
+            tail_fade_ms: options.tail_fade_ms.unwrap_or(0),
+        }
+    }
+
+    pub fn duration_ms(&self) -> f64 {
+        self.frames as f64 * 1000.0 * self.fps_den as f64 / self.fps_num as f64
+    }
+
+    // Rounded to the nearest sample; integer maths so long exports don't drift
+    fn samples(&self) -> u64 {
+        let numerator = self.frames as u64 * self.sample_rate as u64 * self.fps_den as u64;
+        let fps_num = self.fps_num as u64;
+        (numerator + fps_num / 2) / fps_num
+    }
+
+    // Resample to the encoder's rate so the sample count is exact, pad short mixes with
+    // silence, cut long ones, then fade the tail
+    fn filter(&self) -> String {
+        let mut filter = format!(
+            "aresample={},apad,atrim=end_sample={},asetpts=PTS-STARTPTS",
+            self.sample_rate,
+            self.samples()
+        );
+        let duration_ms = self.duration_ms().round() as u64;
+        let fade_ms = self.tail_fade_ms.min(duration_ms);
+        if fade_ms > 0 {
+            filter.push_str(&format!(
+                ",afade=t=out:st={}:d={}",
+                ms_to_secs(duration_ms - fade_ms),
+                ms_to_secs(fade_ms)
+            ));
+        }
+        filter
+    }
+}
+
+/// Decoded length of a file's first audio stream vs. the length it was conformed to
+#[derive(Serialize, Clone, Debug)]
+pub struct AudioConformance {
+    pub expected_ms: f64,
+    pub audio_ms: f64,
+    pub container_ms: Option<u64>,
+    pub within_tolerance: bool,
+}
+
+/// First-pass loudnorm measurement of the mixed audio, plus the target it was normalized to
+#[derive(Serialize, Clone, Debug)]
+pub struct LoudnessMeasurement {
+    pub target_i: f64,
+    pub input_i: f64,
+    pub input_tp: f64,
+    pub input_lra: f64,
+    pub input_thresh: f64,
+    pub target_offset: f64,
+}
+
+impl LoudnessMeasurement {
+    fn target_lra(&self) -> f64 {
+        self.input_lra.ceil().clamp(LOUDNESS_MIN_LRA, LOUDNESS_MAX_LRA)
+    }
+
+    // Second pass: feeding the measured values back lets loudnorm apply a single linear gain
+    fn normalize_filter(&self) -> String {
+        format!(
+            "loudnorm=I={:.1}:TP={:.1}:LRA={:.1}:measured_I={:.2}:measured_TP={:.2}:measured_LRA={:.2}:measured_thresh={:.2}:offset={:.2}:linear=true:print_format=none,aresample={}",
+            self.target_i,
+            LOUDNESS_TRUE_PEAK,
+            self.target_lra(),
+            self.input_i,
+            self.input_tp,
+            self.input_lra,
+            self.input_thresh,
+            self.target_offset,
+            OUTPUT_SAMPLE_RATE
+        )
+    }
+}
+
+impl AudioTrack {
+    fn rate(&self) -> f64 {
+        match self.playback_rate {
+            Some(rate) if rate.is_finite() && rate > 0.0 => rate.clamp(MIN_PLAYBACK_RATE, MAX_PLAYBACK_RATE),
+            _ => 1.0,
+        }
+    }
+
+    fn is_trimmed(&self) -> bool {
+        self.source_in_ms.unwrap_or(0) > 0 || self.source_out_ms.is_some()
+    }
+
+    fn gain_db(&self) -> f64 {
+        self.volume_db.filter(|db| db.is_finite()).unwrap_or(0.0)
+    }
+
+    /// Length of the track on the timeline after trimming and retiming, when known
+    pub fn timeline_duration_ms(&self) -> Option<u64> {
+        let source_in = self.source_in_ms.unwrap_or(0);
+        let source_out = self.source_out_ms.or(self.source_duration_ms)?;
+        Some((source_out.saturating_sub(source_in) as f64 / self.rate()).round() as u64)
+    }
+
+    // True when the source can be mapped straight through without a filter graph
+    fn is_passthrough(&self) -> bool {
+        self.delay_ms == 0
+            && !self.is_trimmed()
+            && (self.rate() - 1.0).abs() < f64::EPSILON
+            && self.gain_db() == 0.0
+            && self.fade_in_ms.unwrap_or(0) == 0
+            && self.fade_out_ms.unwrap_or(0) == 0
+            && !self.looped
+    }
+}
+
+fn ms_to_secs(ms: u64) -> String {
+    format!("{:.3}", ms as f64 / 1000.0)
+}
+
+/// Drop muted tracks and tracks ffmpeg can't use, normalize paths and fill in source lengths
+pub fn prepare_tracks(app: &tauri::AppHandle, tracks: Vec<AudioTrack>) -> Vec<AudioTrack> {
+    let mut valid = Vec::new();
+    for mut t in tracks {
+        if t.mute {
+            continue;
+        }
+        let trimmed = t.path.strip_prefix("file://").unwrap_or(&t.path).to_string();
+        // Audio paths go straight to ffmpeg as inputs - no options, protocols or out-of-scope files
+        if let Err(e) = path_scope::ensure_plain_file_path(&trimmed)
+            .and_then(|_| path_scope::ensure_in_scope(app, Path::new(&trimmed)))
+        {
+            log::warn!("[Audio] Audio path rejected, skipping: {} ({})", trimmed, e);
+            continue;
+        }
+        // A clip without an audio stream would make the [N:a] mapping fail the whole encode
+        match probe::probe(Path::new(&trimmed)) {
+            Ok(info) if info.has_audio() => {
+                t.path = trimmed;
+                t.source_duration_ms = info.duration_ms;
+                valid.push(t);
+            }
+            Ok(_) => {
+                log::info!("[Audio] No audio stream, skipping: {}", trimmed);
+            }
+            Err(e) => {
+                log::warn!("[Audio] Audio path not usable, skipping: {} ({})", trimmed, e);
+            }
+        }
+    }
+    valid
+}
+
+// Split a playback rate into atempo stages that each stay within atempo's range
+fn atempo_chain(rate: f64) -> Vec<String> {
+    let mut remaining = rate;
+    let mut stages = Vec::new();
+    while remaining > ATEMPO_MAX {
+        stages.push(format!("atempo={}", ATEMPO_MAX));
+        remaining /= ATEMPO_MAX;
+    }
+    while remaining < ATEMPO_MIN {
+        stages.push(format!("atempo={}", ATEMPO_MIN));
+        remaining /= ATEMPO_MIN;
+    }
+    if (remaining - 1.0).abs() > 1e-6 {
+        stages.push(format!("atempo={:.6}", remaining));
+    }
+    stages
+}
+
+// Repeat the (trimmed, retimed) clip in `[from]` until it is `needed_ms` long. Hard loops use
+// aloop on a fixed-rate copy; crossfaded loops chain acrossfade over asplit copies.
+fn loop_graph(input: usize, from: &str, segment_ms: u64, needed_ms: u64, crossfade_ms: u64, out: &str) -> Vec<String> {
+    let crossfade_ms = crossfade_ms.min(segment_ms / 2);
+    if crossfade_ms == 0 {
+        let size = segment_ms * OUTPUT_SAMPLE_RATE as u64 / 1000;
+        return vec![format!(
+            "[{}]aresample={},aloop=loop=-1:size={}:start=0,atrim=end={},asetpts=PTS-STARTPTS[{}]",
+            from,
+            OUTPUT_SAMPLE_RATE,
+            size,
+            ms_to_secs(needed_ms),
+            out
+        )];
+    }
+
+    // Each junction overlaps by the crossfade, so every copy after the first adds segment - crossfade
+    let copies = (needed_ms.saturating_sub(crossfade_ms)).div_ceil(segment_ms - crossfade_ms).max(2);
+    if copies > MAX_LOOP_COPIES {
+        // Too many branches for one graph; a hard loop still fills the whole export
+        log::warn!(
+            "[Audio] Looping a {} ms clip over {} ms needs {} crossfaded copies (max {}); looping without crossfades",
+            segment_ms,
+            needed_ms,
+            copies,
+            MAX_LOOP_COPIES
+        );
+        return loop_graph(input, from, segment_ms, needed_ms, 0, out);
+    }
+    let copy_labels: Vec<String> = (0..copies).map(|k| format!("[l{}_{}]", input, k)).collect();
+    let mut graph = vec![format!("[{}]asplit={}{}", from, copies, copy_labels.concat())];
+    let mut current = copy_labels[0].clone();
+    for (k, next) in copy_labels.iter().enumerate().skip(1) {
+        let joined = format!("[lx{}_{}]", input, k);
+        graph.push(format!(
+            "{}{}acrossfade=d={}:c1=qsin:c2=qsin{}",
+            current,
+            next,
+            ms_to_secs(crossfade_ms),
+            joined
+        ));
+        current = joined;
+    }
+    graph.push(format!(
+        "{}atrim=end={},asetpts=PTS-STARTPTS[{}]",
+        current,
+        ms_to_secs(needed_ms),
+        out
+    ));
+    graph
+}
+
+/// Per-input chain: cut the clip's source range, reset timestamps, retime to the clip's
+/// speed, loop it if asked, apply gain and fades, then shift it to its timeline position.
+/// `end_ms` is the export length looping tracks fill up to.
+fn track_chain(input: usize, track: &AudioTrack, end_ms: Option<u64>) -> String {
+    let mut filters = Vec::new();
+    if track.is_trimmed() {
+        let mut trim = format!("atrim=start={}", ms_to_secs(track.source_in_ms.unwrap_or(0)));
+        if let Some(out_ms) = track.source_out_ms {
+            trim.push_str(&format!(":end={}", ms_to_secs(out_ms.max(track.source_in_ms.unwrap_or(0)))));
+        }
+        filters.push(trim);
+        filters.push("asetpts=PTS-STARTPTS".to_string());
+    }
+    filters.extend(atempo_chain(track.rate()));
+
+    let mut graph = Vec::new();
+    let mut source = format!("{}:a", input);
+    let mut length_ms = track.timeline_duration_ms();
+    if track.looped {
+        let needed_ms = end_ms.map(|end| end.saturating_sub(track.delay_ms));
+        match (length_ms, needed_ms) {
+            (Some(segment_ms), Some(needed_ms)) if segment_ms > 0 && needed_ms > segment_ms => {
+                let clip = format!("c{}", input);
+                let looped = format!("r{}", input);
+                if filters.is_empty() {
+                    filters.push("anull".to_string());
+                }
+                graph.push(format!("[{}]{}[{}]", source, filters.join(","), clip));
+                graph.extend(loop_graph(
+                    input,
+                    &clip,
+                    segment_ms,
+                    needed_ms,
+                    track.loop_crossfade_ms.unwrap_or(0),
+                    &looped,
+                ));
+                filters.clear();
+                source = looped;
+                length_ms = Some(needed_ms);
+            }
+            (None, _) | (_, None) => log::warn!("[Audio] Unknown length for {}, not looping", track.path),
+            _ => {}
+        }
+    }
+
+    if track.gain_db() != 0.0 {
+        filters.push(format!("volume={:.2}dB", track.gain_db()));
+    }
+    // Fades are placed in clip time, so they go before the delay
+    let fade_in_ms = track.fade_in_ms.unwrap_or(0);
+    if fade_in_ms > 0 {
+        filters.push(format!("afade=t=in:st=0:d={}", ms_to_secs(fade_in_ms)));
+    }
+    let fade_out_ms = track.fade_out_ms.unwrap_or(0);
+    if fade_out_ms > 0 {
+        match length_ms {
+            Some(length_ms) => {
+                let fade_out_ms = fade_out_ms.min(length_ms);
+                filters.push(format!(
+                    "afade=t=out:st={}:d={}",
+                    ms_to_secs(length_ms - fade_out_ms),
+                    ms_to_secs(fade_out_ms)
+                ));
+            }
+            None => log::warn!("[Audio] Unknown length for {}, skipping fade-out", track.path),
+        }
+    }
+    // all=1 delays every channel, whatever the source layout
+    filters.push(format!("adelay=delays={}:all=1", track.delay_ms));
+    graph.push(format!("[{}]{}[a{}]", source, filters.join(","), input));
+    graph.join(";")
+}
+
+// amix without normalize=0 divides every input by the input count, so a second track
+// made the first one quieter; gains are now explicit and the limiter catches overs
+fn amix(labels: &[String], duration: &str, out: &str) -> String {
+    format!(
+        "{}amix=inputs={}:duration={}:normalize=0[{}]",
+        labels.concat(),
+        labels.len(),
+        duration,
+        out
+    )
+}
+
+// Full mix graph from the per-track chains to `[out]`, conformed to the export length if given
+fn mix_graph(
+    tracks: &[AudioTrack],
+    first_input: usize,
+    options: &AudioOptions,
+    conform: Option<&Conform>,
+    out: &str,
+) -> Vec<String> {
+    let end_ms = conform.map(|c| c.duration_ms().round() as u64);
+    let mut graph = Vec::new();
+    let mut music = Vec::new();
+    let mut camera = Vec::new();
+    for (i, track) in tracks.iter().enumerate() {
+        graph.push(track_chain(first_input + i, track, end_ms));
+        let label = format!("[a{}]", first_input + i);
+        match track.role {
+            AudioRole::Music => music.push(label),
+            AudioRole::Camera => camera.push(label),
+        }
+    }
+
+    if options.duck_music && !music.is_empty() && !camera.is_empty() {
+        // Camera audio drives the compressor on the music bus, then both buses are summed
+        graph.push(amix(&music, "longest", "music"));
+        graph.push(amix(&camera, "longest", "camera"));
+        graph.push("[camera]asplit=2[camera_mix][camera_tail]".to_string());
+        // apad keeps the key input alive so the music isn't cut when camera audio ends
+        graph.push("[camera_tail]apad[camera_key]".to_string());
+        graph.push(
+            "[music][camera_key]sidechaincompress=threshold=0.03:ratio=8:attack=50:release=600:makeup=1[ducked]"
+                .to_string(),
+        );
+        graph.push(amix(&["[ducked]".to_string(), "[camera_mix]".to_string()], "longest", "mix"));
+    } else {
+        let labels: Vec<String> = (0..tracks.len()).map(|i| format!("[a{}]", first_input + i)).collect();
+        // "longest" keeps every track; the export length is enforced by the conform stage
+        graph.push(amix(&labels, "longest", "mix"));
+    }
+    let conform_filter = conform.map(|c| format!(",{}", c.filter())).unwrap_or_default();
+    graph.push(format!(
+        "[mix]alimiter=limit={}:attack=5:release=50:level=disabled{}[{}]",
+        LIMITER_CEILING, conform_filter, out
+    ));
+    graph
+}
+
+/// `-filter_complex`/`-map` args that mix `tracks`, whose ffmpeg inputs start at `first_input`.
+/// Muted tracks are expected to be filtered out before their inputs are added.
+pub fn mix_args(
+    tracks: &[AudioTrack],
+    first_input: usize,
+    options: &AudioOptions,
+    loudness: Option<&LoudnessMeasurement>,
+    conform: Option<&Conform>,
+) -> Vec<String> {
+    if tracks.is_empty() {
+        return Vec::new();
+    }
+    if tracks.len() == 1 && tracks[0].is_passthrough() && loudness.is_none() && conform.is_none() {
+        // Single untouched track - just map it
+        return vec!["-map".to_string(), format!("{}:a:0", first_input)];
+    }
+
+    let mut graph = mix_graph(tracks, first_input, options, conform, "mixed");
+    match (loudness, conform) {
+        // loudnorm resamples, so the length is enforced again after it
+        (Some(measurement), Some(conform)) => graph.push(format!(
+            "[mixed]{},{}[aout]",
+            measurement.normalize_filter(),
+            conform.filter()
+        )),
+        (Some(measurement), None) => graph.push(format!("[mixed]{}[aout]", measurement.normalize_filter())),
+        (None, _) => graph.push("[mixed]anull[aout]".to_string()),
+    }
+
+    vec![
+        "-filter_complex".to_string(),
+        graph.join(";"),
+        "-map".to_string(),
+        "[aout]".to_string(),
+    ]
+}
+
+// Rate the encoder will run at - Opus is always 48 kHz
+fn output_sample_rate(format: &str, options: &AudioOptions) -> u32 {
+    if format == "webm" || format == "opus" {
+        48_000
+    } else {
+        options.sample_rate.unwrap_or(OUTPUT_SAMPLE_RATE)
+    }
+}
+
+/// Input args for a silent stereo source at the encoder's rate (infinite; cut by the conform stage)
+pub fn silence_input_args(conform: &Conform) -> Vec<String> {
+    vec![
+        "-f".to_string(),
+        "lavfi".to_string(),
+        "-i".to_string(),
+        format!("anullsrc=channel_layout=stereo:sample_rate={}", conform.sample_rate),
+    ]
+}
+
+/// Map the silent input, trimmed to exactly the video length
+pub fn silence_map_args(input: usize, conform: &Conform) -> Vec<String> {
+    vec![
+        "-filter_complex".to_string(),
+        format!("[{}:a]{}[aout]", input, conform.filter()),
+        "-map".to_string(),
+        "[aout]".to_string(),
+    ]
+}
+
+/// Audio-only formats `export_audio_mix` can write
+pub fn is_audio_only_format(format: &str) -> bool {
+    matches!(format, "wav" | "flac" | "m4a" | "opus")
+}
+
+/// Whether the container can carry an audio stream at all
+pub fn format_supports_audio(format: &str) -> bool {
+    matches!(format, "mp4" | "mov" | "mkv" | "webm")
+}
+
+/// Output-side audio codec args for `format`; errors on combinations the container can't hold
+pub fn encode_args(format: &str, options: &AudioOptions) -> Result<Vec<String>, String> {
+    let codec = match (format, options.codec) {
+        ("mp4" | "mov" | "mkv" | "m4a", AudioCodec::Auto | AudioCodec::Aac) => AudioCodec::Aac,
+        ("mov" | "mkv" | "wav", AudioCodec::Pcm) | ("wav", AudioCodec::Auto) => AudioCodec::Pcm,
+        ("webm" | "mkv" | "opus", AudioCodec::Auto | AudioCodec::Opus) => AudioCodec::Opus,
+        ("flac" | "mkv", AudioCodec::Auto | AudioCodec::Flac) => AudioCodec::Flac,
+        (_, codec) => return Err(format!("{:?} audio is not supported in {}", codec, format)),
+    };
+    let channels = options.channels.unwrap_or(2).clamp(1, 2);
+    let sample_rate = options.sample_rate.unwrap_or(OUTPUT_SAMPLE_RATE);
+    if sample_rate != 44_100 && sample_rate != 48_000 {
+        return Err(format!("Unsupported audio sample rate: {}", sample_rate));
+    }
+
+    let mut args = match codec {
+        AudioCodec::Aac => vec![
+            "-c:a".to_string(),
+            "aac".to_string(),
+            "-profile:a".to_string(),
+            "aac_low".to_string(),
+            "-b:a".to_string(),
+            format!("{}k", options.bitrate_kbps.unwrap_or(192).clamp(64, 512)),
+            "-ar".to_string(),
+            sample_rate.to_string(),
+        ],
+        AudioCodec::Opus => vec![
+            "-c:a".to_string(),
+            "libopus".to_string(),
+            "-b:a".to_string(),
+            format!("{}k", options.bitrate_kbps.unwrap_or(160).clamp(32, 512)),
+            "-vbr".to_string(),
+            "on".to_string(),
+            // Opus always runs at 48 kHz; other rates would just be resampled by the encoder
+            "-ar".to_string(),
+            "48000".to_string(),
+        ],
+        AudioCodec::Flac => vec![
+            "-c:a".to_string(),
+            "flac".to_string(),
+            "-ar".to_string(),
+            sample_rate.to_string(),
+        ],
+        _ => vec![
+            "-c:a".to_string(),
+            // Stems for a sound designer get 24-bit; mov keeps the widely supported 16-bit
+            if format == "wav" { "pcm_s24le" } else { "pcm_s16le" }.to_string(),
+            "-ar".to_string(),
+            sample_rate.to_string(),
+        ],
+    };
+    // Mono or 5.1 sources would otherwise keep their own layout; -ac picks the default
+    // layout for the count (mono / stereo)
+    args.extend(["-ac".to_string(), channels.to_string()]);
+    Ok(args)
+}
+
+// loudnorm prints its JSON summary as the last {...} block on stderr, values as strings
+fn parse_loudnorm_json(stderr: &str) -> Option<HashMap<String, String>> {
+    let start = stderr.rfind('{')?;
+    let end = stderr[start..].find('}')? + start;
+    serde_json::from_str(&stderr[start..=end]).ok()
+}
+
+/// Pass one of loudness normalization: run the same mix graph into loudnorm's analyzer.
+/// Returns None for silent mixes, where there is nothing to normalize.
+pub fn measure_loudness(
+    tracks: &[AudioTrack],
+    options: &AudioOptions,
+    conform: Option<&Conform>,
+    target_i: f64,
+) -> Result<Option<LoudnessMeasurement>, String> {
+    if tracks.is_empty() {
+        return Ok(None);
+    }
+    let target_i = target_i.clamp(-70.0, -5.0);
+    let mut graph = mix_graph(tracks, 0, options, conform, "mixed");
+    graph.push(format!(
+        "[mixed]loudnorm=I={:.1}:TP={:.1}:LRA={:.1}:print_format=json[measured]",
+        target_i, LOUDNESS_TRUE_PEAK, LOUDNESS_MIN_LRA
+    ));
+
+    let ffmpeg_path = crate::get_ffmpeg_path()?;
+    let mut command = Command::new(&ffmpeg_path);
+    command.args(["-hide_banner", "-nostdin"]);
+    for track in tracks {
+        command.arg("-i").arg(&track.path);
+    }
+    let output = command
+        .args(["-filter_complex", &graph.join(";"), "-map", "[measured]", "-f", "null", "-"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .output()
+        .map_err(|e| format!("Failed to run ffmpeg: {e}"))?;
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !output.status.success() {
+        return Err(format!("Loudness measurement failed: {}", stderr.lines().last().unwrap_or("")));
+    }
+    let values = parse_loudnorm_json(&stderr).ok_or("Loudness measurement produced no summary")?;
+    let value = |key: &str| -> Result<f64, String> {
+        values
+            .get(key)
+            .and_then(|v| v.trim().parse::<f64>().ok())
+            .ok_or_else(|| format!("Loudness measurement missing {}", key))
+    };
+
+    let measurement = LoudnessMeasurement {
+        target_i,
+        input_i: value("input_i")?,
+        input_tp: value("input_tp")?,
+        input_lra: value("input_lra")?,
+        input_thresh: value("input_thresh")?,
+        target_offset: value("target_offset")?,
+    };
+    // "-inf" integrated loudness = digital silence
+    Ok(measurement.input_i.is_finite().then_some(measurement))
+}
+
+/// Decode the first audio stream of an exported file and check that its length matches the
+/// conformed length within one codec frame (AAC frames are 1024 samples, Opus 20 ms)
+pub fn verify_conformance(path: &Path, conform: &Conform) -> Result<AudioConformance, String> {
+    let ffmpeg_path = crate::get_ffmpeg_path()?;
+    let mut child = Command::new(&ffmpeg_path)
+        .args(["-hide_banner", "-nostdin", "-loglevel", "error", "-i"])
+        .arg(path)
+        .args(["-map", "0:a:0", "-f", "null", "-progress", "pipe:1", "-"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Failed to spawn ffmpeg: {e}"))?;
+
+    // The last out_time_us report is the end of the decoded audio
+    let mut audio_us: Option<u64> = None;
+    if let Some(stdout) = child.stdout.take() {
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            if let Some(us) = line.strip_prefix("out_time_us=").and_then(|v| v.trim().parse().ok()) {
+                audio_us = Some(us);
+            }
+        }
+    }
+    let status = child.wait().map_err(|e| format!("Failed to wait for ffmpeg: {e}"))?;
+    if !status.success() {
+        return Err(format!("ffmpeg failed to decode exported audio: {}", status));
+    }
+    let audio_ms = audio_us.ok_or("Exported file has no decodable audio")? as f64 / 1000.0;
+
+    let expected_ms = conform.duration_ms();
+    let tolerance_ms = (1024.0 * 1000.0 / conform.sample_rate as f64).max(20.0) + 1.0;
+    Ok(AudioConformance {
+        expected_ms,
+        audio_ms,
+        container_ms: probe::probe(path).ok().and_then(|info| info.duration_ms),
+        within_tolerance: (audio_ms - expected_ms).abs() <= tolerance_ms,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(sample_rate: Option<u32>, tail_fade_ms: Option<u64>) -> AudioOptions {
+        AudioOptions {
+            sample_rate,
+            tail_fade_ms,
+            ..Default::default()
+        }
+    }
+
+    fn track(path: &str, extra: serde_json::Value) -> AudioTrack {
+        let mut value = serde_json::json!({ "path": path, "delay_ms": 0 });
+        value.as_object_mut().unwrap().extend(extra.as_object().unwrap().clone());
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn conform_samples_at_integer_rates() {
+        let conform = Conform::new("mp4", &options(None, None), 90, 30);
+        assert_eq!(conform.sample_rate, 48_000);
+        assert_eq!(conform.samples(), 144_000);
+        assert_eq!(conform.duration_ms(), 3000.0);
+
+        let cd = Conform::new("mp4", &options(Some(44_100), None), 1, 60);
+        assert_eq!(cd.samples(), 735);
+        // Opus only runs at 48 kHz, whatever was asked for
+        let opus = Conform::new("webm", &options(Some(44_100), None), 1, 60);
+        assert_eq!(opus.sample_rate, 48_000);
+        assert_eq!(opus.samples(), 800);
+    }
+
+    #[test]
+    fn conform_samples_at_ntsc_rates() {
+        // 300 frames at 29.97 fps = 10.01 s
+        let ntsc_48k = Conform::with_frame_rate("mov", &options(None, None), 300, 30_000, 1001);
+        assert_eq!(ntsc_48k.samples(), 480_480);
+        assert!((ntsc_48k.duration_ms() - 10_010.0).abs() < 1e-9);
+        let ntsc_44k = Conform::with_frame_rate("mov", &options(Some(44_100), None), 300, 30_000, 1001);
+        assert_eq!(ntsc_44k.samples(), 441_441);
+
+        // 59.94 fps: one frame is 735.735 samples at 44.1 kHz and 800.8 at 48 kHz
+        let one_44k = Conform::with_frame_rate("mp4", &options(Some(44_100), None), 1, 60_000, 1001);
+        assert_eq!(one_44k.samples(), 736);
+        let one_48k = Conform::with_frame_rate("mp4", &options(None, None), 1, 60_000, 1001);
+        assert_eq!(one_48k.samples(), 801);
+        // Rounding happens once over the whole length, not per frame
+        let long = Conform::with_frame_rate("mp4", &options(Some(44_100), None), 108_000, 60_000, 1001);
+        assert_eq!(long.samples(), 79_459_380);
+    }
+
+    #[test]
+    fn zero_fps_is_clamped() {
+        let conform = Conform::new("mp4", &options(None, None), 10, 0);
+        assert_eq!(conform.fps_num, 1);
+        assert_eq!(conform.samples(), 480_000);
+    }
+
+    #[test]
+    fn conform_filter_string() {
+        let conform = Conform::new("mp4", &options(None, None), 90, 30);
+        assert_eq!(
+            conform.filter(),
+            "aresample=48000,apad,atrim=end_sample=144000,asetpts=PTS-STARTPTS"
+        );
+        let faded = Conform::new("mp4", &options(Some(44_100), Some(500)), 90, 30);
+        assert_eq!(
+            faded.filter(),
+            "aresample=44100,apad,atrim=end_sample=132300,asetpts=PTS-STARTPTS,afade=t=out:st=2.500:d=0.500"
+        );
+        // A fade longer than the export covers all of it
+        let short = Conform::new("mp4", &options(None, Some(10_000)), 30, 30);
+        assert!(short.filter().ends_with(",afade=t=out:st=0.000:d=1.000"));
+    }
+
+    #[test]
+    fn mix_args_without_tracks() {
+        let conform = Conform::new("mp4", &options(None, None), 90, 30);
+        assert!(mix_args(&[], 1, &AudioOptions::default(), None, Some(&conform)).is_empty());
+    }
+
+    #[test]
+    fn mix_args_single_track() {
+        let tracks = [track("/music.wav", serde_json::json!({}))];
+        // Untouched and unconformed: mapped as-is
+        assert_eq!(
+            mix_args(&tracks, 1, &AudioOptions::default(), None, None),
+            vec!["-map".to_string(), "1:a:0".to_string()]
+        );
+
+        let conform = Conform::new("mp4", &options(None, None), 90, 30);
+        let args = mix_args(&tracks, 1, &AudioOptions::default(), None, Some(&conform));
+        assert_eq!(args[0], "-filter_complex");
+        assert_eq!(&args[2..], ["-map", "[aout]"]);
+        let graph = &args[1];
+        assert!(graph.starts_with("[1:a]"), "{graph}");
+        assert!(graph.contains("amix=inputs=1:duration=longest:normalize=0[mix]"), "{graph}");
+        assert!(graph.contains(&conform.filter()), "{graph}");
+        assert!(graph.ends_with("[mixed]anull[aout]"), "{graph}");
+    }
+
+    #[test]
+    fn mix_args_several_tracks() {
+        let tracks = [
+            track("/music.wav", serde_json::json!({})),
+            track("/voice.wav", serde_json::json!({ "delay_ms": 1500, "role": "camera" })),
+            track("/sting.wav", serde_json::json!({ "delay_ms": 4000, "volume_db": -6.0 })),
+        ];
+        let args = mix_args(&tracks, 2, &AudioOptions::default(), None, None);
+        let graph = &args[1];
+        for input in 2..5 {
+            assert!(graph.contains(&format!("[{}:a]", input)), "{graph}");
+        }
+        assert!(graph.contains("adelay=delays=1500:all=1"), "{graph}");
+        assert!(graph.contains("[a2][a3][a4]amix=inputs=3:duration=longest:normalize=0[mix]"), "{graph}");
+        assert!(!graph.contains("sidechaincompress"), "{graph}");
+
+        let ducked = mix_args(
+            &tracks,
+            2,
+            &AudioOptions {
+                duck_music: true,
+                ..Default::default()
+            },
+            None,
+            None,
+        );
+        assert!(ducked[1].contains("[a2][a4]amix=inputs=2"), "{}", ducked[1]);
+        assert!(ducked[1].contains("sidechaincompress"), "{}", ducked[1]);
+    }
+
+    #[test]
+    fn crossfaded_loops_split_into_enough_copies() {
+        // 10 s clip, 1 s overlap, 30 s needed: 10 + 9 + 9 + 9 >= 30
+        let graph = loop_graph(0, "t0", 10_000, 30_000, 1_000, "out");
+        assert_eq!(graph[0], "[t0]asplit=4[l0_0][l0_1][l0_2][l0_3]");
+        assert_eq!(graph.len(), 5);
+        assert_eq!(graph[4], "[lx0_3]atrim=end=30.000,asetpts=PTS-STARTPTS[out]");
+    }
+
+    #[test]
+    fn loops_past_the_copy_limit_fall_back_to_a_hard_loop() {
+        // 100 ms clip over ten minutes would need thousands of crossfaded copies
+        let graph = loop_graph(1, "t1", 100, 600_000, 20, "out");
+        assert_eq!(
+            graph,
+            ["[t1]aresample=48000,aloop=loop=-1:size=4800:start=0,atrim=end=600.000,asetpts=PTS-STARTPTS[out]"]
+        );
+    }
+}
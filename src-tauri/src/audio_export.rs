@@ -0,0 +1,209 @@
+use serde::Serialize;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use tauri::Emitter;
+
+use crate::audio::{self, AudioConformance, AudioOptions, AudioTrack, Conform, LoudnessMeasurement};
+use crate::output::{self, CollisionPolicy};
+
+#[derive(Serialize, Clone, Debug)]
+pub struct AudioMixExport {
+    pub path: String,
+    pub loudness: Option<LoudnessMeasurement>,
+    pub conformance: Option<AudioConformance>,
+}
+
+#[derive(Serialize, Clone)]
+struct AudioMixProgressPayload {
+    output: String,
+    progress: f32,
+}
+
+// Check an export request and return the length to conform to: millisecond "frames" at
+// 1000 fps give exactly `duration_ms`
+fn export_conform(
+    tracks: &[AudioTrack],
+    duration_ms: u64,
+    format: &str,
+    options: &AudioOptions,
+) -> Result<Conform, String> {
+    if !audio::is_audio_only_format(format) {
+        return Err(format!("Unsupported audio export format: {}", format));
+    }
+    if tracks.is_empty() {
+        return Err("No audible tracks to export".into());
+    }
+    if duration_ms == 0 {
+        return Err("Duration must be greater than 0".into());
+    }
+    // Codec/format mismatches fail here, before the loudness pass
+    audio::encode_args(format, options)?;
+    Ok(Conform::new(format, options, duration_ms.min(u32::MAX as u64) as u32, 1000))
+}
+
+// ffmpeg arguments (after the binary) mixing `tracks` into `output`
+fn export_args(
+    tracks: &[AudioTrack],
+    format: &str,
+    options: &AudioOptions,
+    loudness: Option<&LoudnessMeasurement>,
+    conform: &Conform,
+    output: &Path,
+) -> Result<Vec<String>, String> {
+    let mut args: Vec<String> = ["-hide_banner", "-nostdin", "-y", "-loglevel", "error", "-progress", "pipe:1"]
+        .iter()
+        .map(|arg| arg.to_string())
+        .collect();
+    for track in tracks {
+        args.push("-i".to_string());
+        args.push(track.path.clone());
+    }
+    args.extend(audio::mix_args(tracks, 0, options, loudness, Some(conform)));
+    args.extend(audio::encode_args(format, options)?);
+    args.push("-vn".to_string());
+    if format == "m4a" {
+        args.extend(["-movflags".to_string(), "+faststart".to_string()]);
+    }
+    args.push(output.to_string_lossy().to_string());
+    Ok(args)
+}
+
+/// Render the timeline mix of `tracks` to an audio-only file of exactly `duration_ms`.
+/// Uses the same per-track chains, mix and loudness stages as the video export.
+pub fn export(
+    app: &tauri::AppHandle,
+    tracks: &[AudioTrack],
+    duration_ms: u64,
+    format: &str,
+    requested: &Path,
+    options: &AudioOptions,
+    policy: CollisionPolicy,
+) -> Result<AudioMixExport, String> {
+    let conform = export_conform(tracks, duration_ms, format, options)?;
+    output::resolve_output_path(requested, policy)?;
+    let temp = output::temp_output_path(requested);
+
+    let loudness = match options.loudness_target {
+        Some(target) => audio::measure_loudness(tracks, options, Some(&conform), target).unwrap_or_else(|e| {
+            log::warn!("[AudioExport] {}, exporting without loudness normalization", e);
+            None
+        }),
+        None => None,
+    };
+
+    let ffmpeg_path = crate::get_ffmpeg_path()?;
+    let mut command = Command::new(&ffmpeg_path);
+    command
+        .args(export_args(tracks, format, options, loudness.as_ref(), &conform, &temp)?)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+
+    log::info!("[AudioExport] Mixing: {:?}", command);
+    let mut child = command.spawn().map_err(|e| format!("Failed to spawn ffmpeg: {e}"))?;
+
+    // Drain stderr on the side so a chatty ffmpeg can't block on a full pipe
+    let stderr = child.stderr.take();
+    let stderr_thread = std::thread::spawn(move || {
+        stderr
+            .map(|s| BufReader::new(s).lines().map_while(Result::ok).last())
+            .unwrap_or_default()
+    });
+
+    let output_str = requested.to_string_lossy().to_string();
+    let total_us = duration_ms as f32 * 1000.0;
+    if let Some(stdout) = child.stdout.take() {
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            if let Some(us) = line.strip_prefix("out_time_us=").and_then(|v| v.trim().parse::<f32>().ok()) {
+                let _ = app.emit(
+                    "audio-mix-progress",
+                    AudioMixProgressPayload {
+                        output: output_str.clone(),
+                        progress: (us / total_us).clamp(0.0, 1.0),
+                    },
+                );
+            }
+        }
+    }
+
+    let status = child.wait().map_err(|e| format!("Failed to wait for ffmpeg: {e}"))?;
+    let last_error = stderr_thread.join().unwrap_or_default();
+    if !status.success() {
+        output::discard_output(&temp);
+        return Err(format!(
+            "Audio export failed: {}",
+            last_error.unwrap_or_else(|| status.to_string())
+        ));
+    }
+
+    let final_path = output::commit_output(&temp, requested, policy).inspect_err(|_| output::discard_output(&temp))?;
+    let conformance = audio::verify_conformance(&final_path, &conform)
+        .inspect_err(|e| log::warn!("[AudioExport] Length check failed: {}", e))
+        .ok();
+
+    Ok(AudioMixExport {
+        path: final_path.to_string_lossy().to_string(),
+        loudness,
+        conformance,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::AudioCodec;
+    use crate::test_util::value_of;
+
+    fn track(path: &str, delay_ms: u64) -> AudioTrack {
+        serde_json::from_value(serde_json::json!({ "path": path, "delay_ms": delay_ms })).unwrap()
+    }
+
+    fn args_for(format: &str, options: &AudioOptions) -> Vec<String> {
+        let tracks = [track("/music.wav", 0), track("/voice.wav", 1500)];
+        let conform = export_conform(&tracks, 5000, format, options).unwrap();
+        export_args(&tracks, format, options, None, &conform, Path::new("/out/mix.partial")).unwrap()
+    }
+
+    #[test]
+    fn each_format_gets_its_codec() {
+        for (format, codec) in [("wav", "pcm_s24le"), ("flac", "flac"), ("m4a", "aac"), ("opus", "libopus")] {
+            let args = args_for(format, &AudioOptions::default());
+            assert_eq!(value_of(&args, "-c:a"), Some(codec), "{format}");
+            // Every track is an input, mixed and conformed into one stream, no video
+            assert_eq!(args.iter().filter(|arg| *arg == "-i").count(), 2, "{format}");
+            assert_eq!(value_of(&args, "-i"), Some("/voice.wav"));
+            assert_eq!(value_of(&args, "-map"), Some("[aout]"), "{format}");
+            assert!(args.contains(&"-vn".to_string()), "{format}");
+            assert_eq!(args.last().map(String::as_str), Some("/out/mix.partial"));
+            assert_eq!(value_of(&args, "-movflags"), (format == "m4a").then_some("+faststart"), "{format}");
+        }
+    }
+
+    #[test]
+    fn mix_is_conformed_to_the_requested_length() {
+        let args = args_for("wav", &AudioOptions::default());
+        let graph = value_of(&args, "-filter_complex").unwrap();
+        // 5000 ms at 48 kHz
+        assert!(graph.contains("240000"), "{graph}");
+        assert!(graph.contains("adelay=delays=1500:all=1"), "{graph}");
+    }
+
+    #[test]
+    fn bad_requests_are_rejected() {
+        let tracks = [track("/music.wav", 0)];
+        let options = AudioOptions::default();
+        assert!(export_conform(&tracks, 5000, "mp3", &options)
+            .unwrap_err()
+            .contains("Unsupported audio export format"));
+        assert!(export_conform(&tracks, 5000, "mp4", &options).is_err());
+        assert_eq!(export_conform(&[], 5000, "wav", &options).unwrap_err(), "No audible tracks to export");
+        assert_eq!(export_conform(&tracks, 0, "wav", &options).unwrap_err(), "Duration must be greater than 0");
+
+        let aac_in_flac = AudioOptions {
+            codec: AudioCodec::Aac,
+            ..Default::default()
+        };
+        assert!(export_conform(&tracks, 5000, "flac", &aac_in_flac).is_err());
+    }
+}
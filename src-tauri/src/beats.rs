@@ -0,0 +1,381 @@
+use rustfft::num_complex::Complex;
+use rustfft::FftPlanner;
+use serde::{Deserialize, Serialize};
+use std::io::{BufReader, Read};
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use crate::media_cache;
+
+// Mono 22.05 kHz keeps everything up to ~11 kHz, enough for kicks, snares and hats
+const SAMPLE_RATE: u32 = 22_050;
+const FRAME_SIZE: usize = 1024;
+const HOP_SIZE: usize = 256; // ~11.6 ms onset resolution
+const MIN_BPM: f64 = 60.0;
+const MAX_BPM: f64 = 200.0;
+// Tempo prior: most music we get sits around 120 BPM; the weight falls off per octave
+const PRIOR_BPM: f64 = 120.0;
+const PRIOR_OCTAVE_WIDTH: f64 = 1.0;
+// How strongly beat tracking sticks to the estimated period (Ellis 2007 "tightness")
+const TIGHTNESS: f64 = 100.0;
+const MIN_ONSET_GAP_MS: f64 = 50.0;
+// Onset peaks this many standard deviations above the mean count as "strong" (drops, hits)
+const STRONG_ONSET_SIGMA: f64 = 2.0;
+const CACHE_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Onset {
+    pub time_ms: u64,
+    // Normalized onset strength, 0..1
+    pub strength: f32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct BeatAnalysis {
+    pub key: String,
+    pub version: u32,
+    pub bpm: f64,
+    pub beats_ms: Vec<u64>,
+    pub onsets: Vec<Onset>,
+    pub strong_onsets_ms: Vec<u64>,
+    pub duration_ms: u64,
+}
+
+fn decode_mono(source: &Path) -> Result<Vec<f32>, String> {
+    let ffmpeg_path = crate::get_ffmpeg_path()?;
+    let mut child = Command::new(&ffmpeg_path)
+        .args(["-hide_banner", "-nostdin", "-loglevel", "error", "-i"])
+        .arg(source)
+        .args(["-vn", "-ac", "1", "-ar"])
+        .arg(SAMPLE_RATE.to_string())
+        .args(["-f", "f32le", "pipe:1"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Failed to spawn ffmpeg: {e}"))?;
+
+    let stdout = child.stdout.take().ok_or("Failed to get ffmpeg stdout")?;
+    let mut bytes = Vec::new();
+    BufReader::with_capacity(256 * 1024, stdout)
+        .read_to_end(&mut bytes)
+        .map_err(|e| format!("Failed to read ffmpeg output: {e}"))?;
+    let status = child.wait().map_err(|e| format!("Failed to wait for ffmpeg: {e}"))?;
+    if !status.success() {
+        return Err(format!("ffmpeg failed to decode audio: {}", status));
+    }
+    Ok(bytes
+        .chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect())
+}
+
+/// Spectral flux onset envelope: per hop, the summed increase of log-compressed magnitudes
+fn onset_envelope(samples: &[f32]) -> Vec<f64> {
+    if samples.len() < FRAME_SIZE {
+        return Vec::new();
+    }
+    let fft = FftPlanner::<f32>::new().plan_fft_forward(FRAME_SIZE);
+    let window: Vec<f32> = (0..FRAME_SIZE)
+        .map(|i| 0.5 - 0.5 * (2.0 * std::f32::consts::PI * i as f32 / FRAME_SIZE as f32).cos())
+        .collect();
+
+    let bins = FRAME_SIZE / 2 + 1;
+    let mut previous = vec![0f32; bins];
+    let mut buffer = vec![Complex::new(0f32, 0f32); FRAME_SIZE];
+    let mut envelope = Vec::with_capacity(samples.len() / HOP_SIZE);
+
+    for start in (0..=samples.len() - FRAME_SIZE).step_by(HOP_SIZE) {
+        for (i, value) in buffer.iter_mut().enumerate() {
+            *value = Complex::new(samples[start + i] * window[i], 0.0);
+        }
+        fft.process(&mut buffer);
+
+        let mut flux = 0f64;
+        for (bin, prev) in previous.iter_mut().enumerate() {
+            // log(1 + C|X|) evens out loud and quiet passages
+            let magnitude = (1.0 + 100.0 * buffer[bin].norm()).ln();
+            flux += (magnitude - *prev).max(0.0) as f64;
+            *prev = magnitude;
+        }
+        envelope.push(flux);
+    }
+    if let Some(first) = envelope.first_mut() {
+        // The first frame "rises" from silence
+        *first = 0.0;
+    }
+    envelope
+}
+
+fn mean_std(values: &[f64]) -> (f64, f64) {
+    if values.is_empty() {
+        return (0.0, 0.0);
+    }
+    let mean = values.iter().sum::<f64>() / values.len() as f64;
+    let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / values.len() as f64;
+    (mean, variance.sqrt())
+}
+
+// Remove the slowly varying part so sustained loud sections don't read as onsets
+fn detrend(envelope: &[f64], radius: usize) -> Vec<f64> {
+    let mut prefix = vec![0f64; envelope.len() + 1];
+    for (i, v) in envelope.iter().enumerate() {
+        prefix[i + 1] = prefix[i] + v;
+    }
+    (0..envelope.len())
+        .map(|i| {
+            let lo = i.saturating_sub(radius);
+            let hi = (i + radius + 1).min(envelope.len());
+            let local_mean = (prefix[hi] - prefix[lo]) / (hi - lo) as f64;
+            (envelope[i] - local_mean).max(0.0)
+        })
+        .collect()
+}
+
+/// Peak picking with an adaptive threshold and a minimum gap between onsets
+fn pick_onsets(envelope: &[f64], frames_per_sec: f64) -> Vec<(usize, f64)> {
+    let (mean, std) = mean_std(envelope);
+    let threshold = mean + 0.5 * std;
+    let min_gap = (MIN_ONSET_GAP_MS / 1000.0 * frames_per_sec).ceil() as usize;
+    let neighbourhood = 3;
+
+    let mut onsets: Vec<(usize, f64)> = Vec::new();
+    for i in 0..envelope.len() {
+        let value = envelope[i];
+        if value <= threshold {
+            continue;
+        }
+        let lo = i.saturating_sub(neighbourhood);
+        let hi = (i + neighbourhood + 1).min(envelope.len());
+        if envelope[lo..hi].iter().any(|&v| v > value) {
+            continue;
+        }
+        match onsets.last_mut() {
+            Some(last) if i - last.0 < min_gap => {
+                if value > last.1 {
+                    *last = (i, value);
+                }
+            }
+            _ => onsets.push((i, value)),
+        }
+    }
+    onsets
+}
+
+/// Tempo from the autocorrelation of the onset envelope, weighted by a log-Gaussian prior
+/// around 120 BPM so half/double tempo candidates don't win on noise
+fn estimate_period(envelope: &[f64], frames_per_sec: f64) -> Option<f64> {
+    let min_lag = (60.0 / MAX_BPM * frames_per_sec).floor().max(1.0) as usize;
+    let max_lag = (60.0 / MIN_BPM * frames_per_sec).ceil() as usize;
+    if envelope.len() <= max_lag + 1 {
+        return None;
+    }
+
+    let score = |lag: usize| -> f64 {
+        let acf: f64 = envelope[lag..].iter().zip(envelope).map(|(a, b)| a * b).sum::<f64>()
+            / (envelope.len() - lag) as f64;
+        let bpm = 60.0 * frames_per_sec / lag as f64;
+        let octaves = (bpm / PRIOR_BPM).log2() / PRIOR_OCTAVE_WIDTH;
+        acf * (-0.5 * octaves * octaves).exp()
+    };
+    let scores: Vec<f64> = (min_lag..=max_lag).map(score).collect();
+    let (best, best_score) = scores
+        .iter()
+        .enumerate()
+        .max_by(|a, b| a.1.total_cmp(b.1))?;
+    if *best_score <= 0.0 {
+        return None;
+    }
+
+    // Parabolic interpolation between neighbouring lags for sub-frame precision
+    let lag = (min_lag + best) as f64;
+    if best > 0 && best + 1 < scores.len() {
+        let (a, b, c) = (scores[best - 1], scores[best], scores[best + 1]);
+        let denom = a - 2.0 * b + c;
+        if denom.abs() > f64::EPSILON {
+            return Some(lag + 0.5 * (a - c) / denom);
+        }
+    }
+    Some(lag)
+}
+
+/// Dynamic-programming beat tracker: beats land on strong onsets while keeping their spacing
+/// close to the estimated period
+fn track_beats(envelope: &[f64], period: f64) -> Vec<usize> {
+    let n = envelope.len();
+    if n == 0 || period < 1.0 {
+        return Vec::new();
+    }
+    let mut score = envelope.to_vec();
+    let mut backlink: Vec<Option<usize>> = vec![None; n];
+    let min_back = (period / 2.0).round().max(1.0) as usize;
+    let max_back = (period * 2.0).round() as usize;
+
+    for t in 0..n {
+        let lo = t.saturating_sub(max_back);
+        let hi = t.saturating_sub(min_back);
+        if t < min_back {
+            continue;
+        }
+        let best = (lo..=hi)
+            .map(|prev| {
+                let ratio = ((t - prev) as f64 / period).ln();
+                (prev, score[prev] - TIGHTNESS * ratio * ratio)
+            })
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+        if let Some((prev, prev_score)) = best {
+            if prev_score > 0.0 {
+                score[t] = envelope[t] + prev_score;
+                backlink[t] = Some(prev);
+            }
+        }
+    }
+
+    // Start from the best-scoring frame within the last period, then follow the links back
+    let tail = n.saturating_sub(period.round() as usize);
+    let mut current = (tail..n).max_by(|&a, &b| score[a].total_cmp(&score[b]));
+    let mut beats = Vec::new();
+    while let Some(t) = current {
+        beats.push(t);
+        current = backlink[t];
+    }
+    beats.reverse();
+    beats
+}
+
+fn frame_to_ms(frame: usize, frames_per_sec: f64) -> u64 {
+    // Centre of the analysis window
+    ((frame as f64 + FRAME_SIZE as f64 / HOP_SIZE as f64 / 2.0) / frames_per_sec * 1000.0).round() as u64
+}
+
+fn analyze_samples(key: String, samples: &[f32]) -> Result<BeatAnalysis, String> {
+    let frames_per_sec = SAMPLE_RATE as f64 / HOP_SIZE as f64;
+    let raw = onset_envelope(samples);
+    if raw.is_empty() {
+        return Err("Audio is too short to analyze".into());
+    }
+    let envelope = detrend(&raw, (0.1 * frames_per_sec) as usize);
+    let peak = envelope.iter().cloned().fold(0.0, f64::max);
+    if peak <= 0.0 {
+        return Err("Audio is silent".into());
+    }
+
+    let picked = pick_onsets(&envelope, frames_per_sec);
+    let strengths: Vec<f64> = picked.iter().map(|(_, v)| *v).collect();
+    let (mean, std) = mean_std(&strengths);
+    let strong_threshold = mean + STRONG_ONSET_SIGMA * std;
+
+    let onsets: Vec<Onset> = picked
+        .iter()
+        .map(|&(frame, value)| Onset {
+            time_ms: frame_to_ms(frame, frames_per_sec),
+            strength: (value / peak) as f32,
+        })
+        .collect();
+    let strong_onsets_ms = picked
+        .iter()
+        .filter(|(_, value)| *value >= strong_threshold)
+        .map(|&(frame, _)| frame_to_ms(frame, frames_per_sec))
+        .collect();
+
+    let (bpm, beats_ms) = match estimate_period(&envelope, frames_per_sec) {
+        Some(period) => (
+            60.0 * frames_per_sec / period,
+            track_beats(&envelope, period)
+                .into_iter()
+                .map(|frame| frame_to_ms(frame, frames_per_sec))
+                .collect(),
+        ),
+        None => (0.0, Vec::new()),
+    };
+
+    Ok(BeatAnalysis {
+        key,
+        version: CACHE_VERSION,
+        bpm: (bpm * 10.0).round() / 10.0,
+        beats_ms,
+        onsets,
+        strong_onsets_ms,
+        duration_ms: samples.len() as u64 * 1000 / SAMPLE_RATE as u64,
+    })
+}
+
+/// Beat grid, tempo and onsets for an audio (or video) file, cached on disk by content hash
+pub fn analyze(app: &tauri::AppHandle, source: &Path) -> Result<BeatAnalysis, String> {
+    let key = media_cache::content_key(source)?;
+    let cache_path = media_cache::cache_dir(app, "beats")?.join(format!("{}.json", key));
+
+    if let Ok(bytes) = std::fs::read(&cache_path) {
+        match serde_json::from_slice::<BeatAnalysis>(&bytes) {
+            Ok(analysis) if analysis.version == CACHE_VERSION => return Ok(analysis),
+            _ => log::warn!("[Beats] Ignoring stale cache entry {}", cache_path.display()),
+        }
+    }
+
+    let samples = decode_mono(source)?;
+    let analysis = analyze_samples(key, &samples)?;
+    if let Ok(json) = serde_json::to_vec(&analysis) {
+        if let Err(e) = media_cache::write_atomic(&cache_path, &json) {
+            log::warn!("[Beats] {}", e);
+        }
+    }
+    Ok(analysis)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Short decaying noise bursts on every beat, louder on every fourth
+    fn click_track(bpm: f64, seconds: f64) -> Vec<f32> {
+        let total = (seconds * SAMPLE_RATE as f64) as usize;
+        let beat = 60.0 / bpm * SAMPLE_RATE as f64;
+        let click = SAMPLE_RATE as usize / 50;
+        let mut noise = 1u32;
+        let mut samples = vec![0.0f32; total];
+        let mut k = 0;
+        while (k as f64 * beat) < total as f64 {
+            let start = (k as f64 * beat) as usize;
+            let gain = if k % 4 == 0 { 0.9 } else { 0.4 };
+            for (i, sample) in samples.iter_mut().skip(start).take(click).enumerate() {
+                noise = noise.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                let white = (noise >> 8) as f32 / (1 << 24) as f32 * 2.0 - 1.0;
+                *sample = white * gain * (1.0 - i as f32 / click as f32);
+            }
+            k += 1;
+        }
+        samples
+    }
+
+    #[test]
+    fn finds_the_tempo_of_a_click_track() {
+        let analysis = analyze_samples("k".into(), &click_track(120.0, 12.0)).unwrap();
+        assert!((analysis.bpm - 120.0).abs() < 2.0, "bpm {}", analysis.bpm);
+        assert_eq!(analysis.duration_ms, 12_000);
+
+        // Beats land on the clicks, half a second apart
+        assert!(analysis.beats_ms.len() >= 20, "{:?}", analysis.beats_ms);
+        for pair in analysis.beats_ms.windows(2) {
+            let gap = pair[1] as i64 - pair[0] as i64;
+            assert!((gap - 500).abs() <= 30, "gap {gap} in {:?}", analysis.beats_ms);
+        }
+        assert!(analysis.onsets.iter().all(|o| (0.0..=1.0).contains(&o.strength)));
+    }
+
+    #[test]
+    fn silence_and_tiny_inputs_are_errors() {
+        assert!(analyze_samples("k".into(), &[0.0; 100]).is_err());
+        assert_eq!(
+            analyze_samples("k".into(), &vec![0.0; SAMPLE_RATE as usize]).unwrap_err(),
+            "Audio is silent"
+        );
+    }
+
+    #[test]
+    fn frame_times_use_the_window_centre() {
+        let frames_per_sec = SAMPLE_RATE as f64 / HOP_SIZE as f64;
+        // Two hops in: the 1024-sample window is centred 2 hops after its start
+        assert_eq!(frame_to_ms(0, frames_per_sec), 23);
+        assert_eq!(frame_to_ms(86, frames_per_sec), 1022);
+    }
+}
@@ -0,0 +1,392 @@
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use crate::animated_image::AnimationOptions;
+use crate::temp_workspace;
+
+// Swatch patches of the self-test frame, SWATCH_SIZE pixels square side by side
+const SWATCH_SIZE: u32 = 32;
+const SWATCHES: [(&str, [u8; 3]); 8] = [
+    ("red", [255, 0, 0]),
+    ("green", [0, 255, 0]),
+    ("blue", [0, 0, 255]),
+    ("white", [255, 255, 255]),
+    ("black", [0, 0, 0]),
+    ("grey", [128, 128, 128]),
+    ("orange", [255, 99, 26]),
+    ("violet", [99, 91, 255]),
+];
+// 8-bit 4:2:0 rounding plus encoder noise on flat patches stays well under this, while a
+// BT.601/BT.709 matrix mix-up moves saturated red and green by 20 or more
+const MAX_ERROR: u8 = 8;
+const SELF_TEST_FORMATS: [&str; 4] = ["mp4", "webm", "apng", "gif"];
+
+/// Colour space of the captured frames, and what the export is tagged as
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ColorSpace {
+    #[default]
+    Srgb,
+    // Wide-gamut canvas capture; same transfer curve as sRGB, P3 primaries
+    DisplayP3,
+}
+
+impl ColorSpace {
+    fn primaries(self) -> &'static str {
+        match self {
+            ColorSpace::Srgb => "bt709",
+            ColorSpace::DisplayP3 => "smpte432",
+        }
+    }
+}
+
+/// Refuse Display P3 for outputs that have nowhere to record it - viewers would show the
+/// P3 values as sRGB and shift every colour
+pub fn validate(format: &str, space: ColorSpace) -> Result<(), String> {
+    match (format, space) {
+        ("gif" | "animated_webp" | "image_sequence", ColorSpace::DisplayP3) => Err(format!(
+            "{} cannot be tagged as Display P3; use mp4, mov, webm, APNG, AVIF or a PNG sequence",
+            format
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Outputs whose encoder takes the RGB frames as they are; everything else goes to YUV
+pub fn is_rgb_output(format: &str, animation_options: &AnimationOptions) -> bool {
+    match format {
+        "gif" | "apng" | "png_sequence" => true,
+        "animated_webp" => animation_options.lossless,
+        _ => false,
+    }
+}
+
+/// Resize filter for an output. YUV outputs are converted right here with the BT.709 matrix into
+/// limited range; left to the converter ffmpeg inserts, swscale would use BT.601.
+pub fn scale_filter(width: u32, height: u32, rgb: bool) -> String {
+    if rgb {
+        format!("scale={}:{}:flags=bicubic", width, height)
+    } else {
+        format!("scale={}:{}:flags=bicubic:out_color_matrix=bt709:out_range=tv", width, height)
+    }
+}
+
+/// Input options for the raw canvas frames: full-range RGB with the sRGB curve
+pub fn input_args(space: ColorSpace) -> Vec<String> {
+    vec![
+        "-color_primaries".to_string(),
+        space.primaries().to_string(),
+        "-color_trc".to_string(),
+        "iec61966-2-1".to_string(),
+        "-colorspace".to_string(),
+        "rgb".to_string(),
+        "-color_range".to_string(),
+        "pc".to_string(),
+    ]
+}
+
+/// Output tags matching `scale_filter`. RGB outputs keep the sRGB curve (PNG writes it as
+/// cHRM/gAMA); YUV outputs use the BT.709 curve video players expect from screen content.
+pub fn output_args(space: ColorSpace, rgb: bool) -> Vec<String> {
+    let (trc, matrix, range) = if rgb {
+        ("iec61966-2-1", "rgb", "pc")
+    } else {
+        ("bt709", "bt709", "tv")
+    };
+    vec![
+        "-color_primaries".to_string(),
+        space.primaries().to_string(),
+        "-color_trc".to_string(),
+        trc.to_string(),
+        "-colorspace".to_string(),
+        matrix.to_string(),
+        "-color_range".to_string(),
+        range.to_string(),
+    ]
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct SwatchCheck {
+    pub name: String,
+    pub expected: [u8; 3],
+    pub actual: [u8; 3],
+    pub error: u8,
+}
+
+/// Round trip of the swatch frame through one output format
+#[derive(Serialize, Clone, Debug)]
+pub struct ColorCheck {
+    pub format: String,
+    pub passed: bool,
+    pub max_error: u8,
+    pub swatches: Vec<SwatchCheck>,
+    // Set when the format could not be encoded or decoded at all
+    pub error: Option<String>,
+}
+
+fn extension(format: &str) -> &str {
+    match format {
+        "apng" => "png",
+        other => other,
+    }
+}
+
+fn swatch_frame() -> Vec<u8> {
+    let width = SWATCH_SIZE * SWATCHES.len() as u32;
+    let mut frame = Vec::with_capacity((width * SWATCH_SIZE * 4) as usize);
+    for _ in 0..SWATCH_SIZE {
+        for x in 0..width {
+            let [r, g, b] = SWATCHES[(x / SWATCH_SIZE) as usize].1;
+            frame.extend([r, g, b, 255]);
+        }
+    }
+    frame
+}
+
+// Encode the swatch frame to `target` with the export's own args, then decode it back to RGB
+// the way a player would - trusting the tags in the file
+fn round_trip(ffmpeg_path: &Path, format: &str, space: ColorSpace, target: &Path) -> Result<Vec<u8>, String> {
+    let width = SWATCH_SIZE * SWATCHES.len() as u32;
+    let session = crate::SessionInput {
+        input_width: width,
+        input_height: SWATCH_SIZE,
+        fps: 30,
+        total_frames: 3,
+        // Software encoders only, so the result doesn't depend on the GPU
+        use_hw: Some(false),
+        color_space: space,
+        ..Default::default()
+    };
+    let spec = crate::OutputSpec {
+        format: Some(format.to_string()),
+        ..Default::default()
+    };
+    let args = crate::get_streaming_encoder_args(&session, &spec, &crate::OutputAudio::default())?;
+    let mut child = Command::new(ffmpeg_path)
+        .args(["-hide_banner", "-loglevel", "error"])
+        .args(&args)
+        .arg(target)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to spawn ffmpeg: {e}"))?;
+    {
+        let mut stdin = child.stdin.take().ok_or("Failed to get ffmpeg stdin")?;
+        let frame = swatch_frame();
+        // A few frames so the encoders settle past their first keyframe
+        for _ in 0..3 {
+            stdin
+                .write_all(&frame)
+                .map_err(|e| format!("Failed to write to ffmpeg stdin: {e}"))?;
+        }
+    }
+    let output = child
+        .wait_with_output()
+        .map_err(|e| format!("Failed to wait for ffmpeg: {e}"))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("Encode failed: {}", stderr.lines().last().unwrap_or("unknown error")));
+    }
+
+    let output = Command::new(ffmpeg_path)
+        .args(["-hide_banner", "-nostdin", "-loglevel", "error", "-i"])
+        .arg(target)
+        .args(["-frames:v", "1", "-f", "rawvideo", "-pix_fmt", "rgb24", "pipe:1"])
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| format!("Failed to spawn ffmpeg: {e}"))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("Decode failed: {}", stderr.lines().last().unwrap_or("unknown error")));
+    }
+    let expected_len = (width * SWATCH_SIZE * 3) as usize;
+    if output.stdout.len() != expected_len {
+        return Err(format!("Decoded {} bytes, expected {}", output.stdout.len(), expected_len));
+    }
+    Ok(output.stdout)
+}
+
+fn check_format(ffmpeg_path: &Path, format: &str, space: ColorSpace, dir: &Path) -> ColorCheck {
+    let target = dir.join(format!("swatches_{}.{}", format, extension(format)));
+    let decoded = match round_trip(ffmpeg_path, format, space, &target) {
+        Ok(decoded) => decoded,
+        Err(e) => {
+            return ColorCheck {
+                format: format.to_string(),
+                passed: false,
+                max_error: 0,
+                swatches: Vec::new(),
+                error: Some(e),
+            }
+        }
+    };
+
+    let width = SWATCH_SIZE * SWATCHES.len() as u32;
+    // Sample patch centres, away from chroma bleeding across patch edges
+    let swatches: Vec<SwatchCheck> = SWATCHES
+        .iter()
+        .enumerate()
+        .map(|(i, (name, expected))| {
+            let x = i as u32 * SWATCH_SIZE + SWATCH_SIZE / 2;
+            let offset = ((SWATCH_SIZE / 2 * width + x) * 3) as usize;
+            let actual = [decoded[offset], decoded[offset + 1], decoded[offset + 2]];
+            let error = expected
+                .iter()
+                .zip(actual)
+                .map(|(e, a)| e.abs_diff(a))
+                .max()
+                .unwrap_or(0);
+            SwatchCheck {
+                name: name.to_string(),
+                expected: *expected,
+                actual,
+                error,
+            }
+        })
+        .collect();
+    let max_error = swatches.iter().map(|s| s.error).max().unwrap_or(0);
+    ColorCheck {
+        format: format.to_string(),
+        passed: max_error <= MAX_ERROR,
+        max_error,
+        swatches,
+        error: None,
+    }
+}
+
+/// Push known swatches through the export args of each format and decode them back, to catch
+/// matrix, range and tagging mistakes. Formats that can't carry `space` are left out.
+pub fn self_test(space: ColorSpace) -> Result<Vec<ColorCheck>, String> {
+    let ffmpeg_path = crate::get_ffmpeg_path()?;
+    let workspace = temp_workspace::create(Some("color_test"))?;
+    let dir = Path::new(&workspace.path);
+
+    let checks = SELF_TEST_FORMATS
+        .iter()
+        .filter(|format| validate(format, space).is_ok())
+        .map(|format| {
+            let check = check_format(&ffmpeg_path, format, space, dir);
+            log::info!("[Color] {} self-test: passed={} max_error={}", format, check.passed, check.max_error);
+            check
+        })
+        .collect();
+
+    if let Err(e) = temp_workspace::release(&workspace.job_id) {
+        log::warn!("[Color] {}", e);
+    }
+    Ok(checks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::value_of;
+
+    fn tags(args: &[String]) -> [Option<&str>; 4] {
+        [
+            value_of(args, "-color_primaries"),
+            value_of(args, "-color_trc"),
+            value_of(args, "-colorspace"),
+            value_of(args, "-color_range"),
+        ]
+    }
+
+    #[test]
+    fn scale_filter_converts_only_yuv_outputs() {
+        assert_eq!(scale_filter(1920, 1080, true), "scale=1920:1080:flags=bicubic");
+        assert_eq!(
+            scale_filter(1920, 1080, false),
+            "scale=1920:1080:flags=bicubic:out_color_matrix=bt709:out_range=tv"
+        );
+    }
+
+    #[test]
+    fn rgb_outputs() {
+        let lossy = AnimationOptions::default();
+        let lossless = AnimationOptions {
+            lossless: true,
+            ..Default::default()
+        };
+        for format in ["gif", "apng", "png_sequence"] {
+            assert!(is_rgb_output(format, &lossy), "{format}");
+        }
+        for format in ["mp4", "mov", "mkv", "webm", "avif", "animated_webp"] {
+            assert!(!is_rgb_output(format, &lossy), "{format}");
+        }
+        assert!(is_rgb_output("animated_webp", &lossless));
+    }
+
+    #[test]
+    fn output_tags_per_format() {
+        let options = AnimationOptions::default();
+        let cases = [
+            ("mp4", ColorSpace::Srgb, ["bt709", "bt709", "bt709", "tv"]),
+            ("mp4", ColorSpace::DisplayP3, ["smpte432", "bt709", "bt709", "tv"]),
+            ("webm", ColorSpace::Srgb, ["bt709", "bt709", "bt709", "tv"]),
+            ("webm", ColorSpace::DisplayP3, ["smpte432", "bt709", "bt709", "tv"]),
+            ("avif", ColorSpace::Srgb, ["bt709", "bt709", "bt709", "tv"]),
+            ("avif", ColorSpace::DisplayP3, ["smpte432", "bt709", "bt709", "tv"]),
+            ("gif", ColorSpace::Srgb, ["bt709", "iec61966-2-1", "rgb", "pc"]),
+            ("apng", ColorSpace::DisplayP3, ["smpte432", "iec61966-2-1", "rgb", "pc"]),
+        ];
+        for (format, space, expected) in cases {
+            assert!(validate(format, space).is_ok(), "{format} {space:?}");
+            let args = output_args(space, is_rgb_output(format, &options));
+            assert_eq!(tags(&args), expected.map(Some), "{format} {space:?}");
+        }
+    }
+
+    #[test]
+    fn p3_is_refused_where_it_cannot_be_tagged() {
+        for format in ["gif", "animated_webp", "image_sequence"] {
+            assert!(validate(format, ColorSpace::DisplayP3).is_err(), "{format}");
+            assert!(validate(format, ColorSpace::Srgb).is_ok(), "{format}");
+        }
+    }
+
+    #[test]
+    fn input_is_tagged_as_full_range_rgb() {
+        let args = input_args(ColorSpace::DisplayP3);
+        assert_eq!(tags(&args), [Some("smpte432"), Some("iec61966-2-1"), Some("rgb"), Some("pc")]);
+    }
+
+    #[test]
+    fn streaming_args_convert_and_tag_together() {
+        // Software H.264 and VP9 need no encoder lookup, so the full arg list is checked
+        for (format, space) in [("mp4", ColorSpace::Srgb), ("webm", ColorSpace::DisplayP3)] {
+            let session = crate::SessionInput {
+                input_width: 640,
+                input_height: 360,
+                fps: 30,
+                total_frames: 30,
+                use_hw: Some(false),
+                color_space: space,
+                ..Default::default()
+            };
+            let spec = crate::OutputSpec {
+                format: Some(format.to_string()),
+                ..Default::default()
+            };
+            let args = crate::get_streaming_encoder_args(&session, &spec, &crate::OutputAudio::default()).unwrap();
+            let input_end = args.iter().position(|arg| arg == "pipe:0").unwrap();
+            let (input, output) = args.split_at(input_end);
+            assert_eq!(tags(input), [Some(space.primaries()), Some("iec61966-2-1"), Some("rgb"), Some("pc")]);
+            assert_eq!(tags(output), [Some(space.primaries()), Some("bt709"), Some("bt709"), Some("tv")]);
+            assert_eq!(value_of(output, "-vf"), Some(scale_filter(640, 360, false).as_str()));
+        }
+    }
+
+    // Needs the bundled ffmpeg: cargo test -- --ignored
+    #[test]
+    #[ignore]
+    fn swatches_survive_the_round_trip() {
+        for space in [ColorSpace::Srgb, ColorSpace::DisplayP3] {
+            for check in self_test(space).unwrap() {
+                assert!(check.passed, "{:?}: {:?}", space, check);
+            }
+        }
+    }
+}
@@ -0,0 +1,427 @@
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+// Keep this much free on top of the estimate - estimates are rough and the OS needs room too
+const SAFETY_MARGIN_BYTES: u64 = 256 * 1024 * 1024;
+// Warn (but still allow the export) when the estimate eats more than this share of free space
+const WARN_RATIO: f64 = 0.8;
+
+// Assumed bitrate for any muxed audio (AAC/Opus at typical export settings)
+const AUDIO_BITS_PER_SECOND: u64 = 192_000;
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ExportEstimate {
+    pub output_bytes: u64,
+    pub temp_bytes: u64,
+    pub output_free_bytes: Option<u64>,
+    pub temp_free_bytes: Option<u64>,
+    pub sufficient: bool,
+    pub warning: Option<String>,
+}
+
+// Rough bits-per-pixel for each encoder at its reference quality setting.
+// Returns (bits per pixel, reference CRF/CQ, quality step where size doubles).
+fn codec_profile(codec: &str) -> (f64, f64, f64) {
+    match codec {
+        "libx264" => (0.10, 20.0, 6.0),
+        "h264_nvenc" => (0.12, 19.0, 6.0),
+        // VideoToolbox uses -q:v (0-100, higher = better) so the step is inverted
+        "h264_videotoolbox" => (0.16, 75.0, -15.0),
+        "libvpx-vp9" => (0.08, 18.0, 8.0),
+        "libx265" => (0.06, 24.0, 6.0),
+        "libsvtav1" | "libaom-av1" => (0.05, 32.0, 8.0),
+        // Paletted + LZW - roughly half a byte per pixel on UI content
+        "gif" => (4.0, 0.0, 0.0),
+        // Uncompressed RGBA PNG frames typically land around 60% of raw size
+        "png" => (19.2, 0.0, 0.0),
+        "png16" => (38.4, 0.0, 0.0),
+        // Uncompressed RGBA TIFF
+        "tiff" => (32.0, 0.0, 0.0),
+        "webp_lossless" => (12.0, 0.0, 0.0),
+        // ProRes 4444 is intra-only at a near-fixed rate (~330 Mbps at 1080p30)
+        "prores_ks" => (5.3, 0.0, 0.0),
+        // Lossy animated WebP at quality 80; lossless lands closer to APNG
+        "libwebp_anim" => (0.4, 0.0, 0.0),
+        // Deflate with per-frame deltas - between GIF and raw PNG frames
+        "apng" => (8.0, 0.0, 0.0),
+        _ => (0.12, 20.0, 6.0),
+    }
+}
+
+/// Video codec and quality value ffmpeg will be invoked with for a streaming export.
+/// `encoder` is an explicit HEVC/AV1 pick from `video_codec::resolve`.
+pub fn streaming_codec(
+    format: &str,
+    use_hw: bool,
+    alpha: bool,
+    encoder: Option<&'static str>,
+) -> (&'static str, Option<f64>) {
+    let is_macos = std::env::consts::OS == "macos";
+    let is_windows = std::env::consts::OS == "windows";
+    if let Some(encoder) = encoder {
+        return (encoder, Some(crate::video_codec::default_quality(encoder)));
+    }
+    match format {
+        "webm" => ("libvpx-vp9", Some(18.0)),
+        "gif" => ("gif", None),
+        "png_sequence" => ("png", None),
+        "animated_webp" | "apng" => (crate::animated_image::encoder(format), None),
+        "avif" => {
+            let encoder = crate::animated_image::encoder(format);
+            (encoder, Some(crate::video_codec::default_quality(encoder)))
+        }
+        "mov" if alpha => ("prores_ks", None),
+        _ if use_hw && is_macos => ("h264_videotoolbox", Some(75.0)),
+        _ if use_hw && is_windows => ("h264_nvenc", Some(19.0)),
+        _ => ("libx264", Some(20.0)),
+    }
+}
+
+/// What an export size estimate is based on
+#[derive(Clone, Copy, Debug)]
+pub struct EstimateInput<'a> {
+    pub codec: &'a str,
+    // CRF/CQ/q:v value passed to the encoder
+    pub quality: Option<f64>,
+    // Wins over `quality` when set
+    pub bitrate_kbps: Option<u64>,
+    pub width: u32,
+    pub height: u32,
+    pub fps: u32,
+    pub total_frames: u32,
+    pub has_audio: bool,
+    // Rate a GIF is resampled to (`gif::effective_fps`); unused by other codecs
+    pub gif_fps: u32,
+}
+
+/// Estimate encoded output size in bytes.
+pub fn estimate_output_bytes(input: &EstimateInput) -> u64 {
+    let EstimateInput {
+        codec,
+        quality,
+        bitrate_kbps,
+        width,
+        height,
+        fps,
+        total_frames,
+        has_audio,
+        gif_fps,
+    } = *input;
+    let fps = fps.max(1);
+    let duration_secs = total_frames as f64 / fps as f64;
+
+    let video_bits = if let Some(kbps) = bitrate_kbps {
+        kbps as f64 * 1000.0 * duration_secs
+    } else {
+        let (bpp, reference, step) = codec_profile(codec);
+        let scale = match quality {
+            Some(q) if step != 0.0 => 2f64.powf((reference - q) / step),
+            _ => 1.0,
+        };
+        let frames = if codec == "gif" {
+            (duration_secs * gif_fps as f64).ceil()
+        } else {
+            total_frames as f64
+        };
+        width as f64 * height as f64 * frames * bpp * scale
+    };
+
+    let audio_bits = if has_audio && codec != "gif" {
+        AUDIO_BITS_PER_SECOND as f64 * duration_secs
+    } else {
+        0.0
+    };
+
+    ((video_bits + audio_bits) / 8.0).ceil() as u64
+}
+
+/// Estimate temp usage of a frame-dir export (one PNG per frame)
+pub fn estimate_frames_dir_bytes(width: u32, height: u32, total_frames: u32) -> u64 {
+    estimate_output_bytes(&EstimateInput {
+        codec: "png",
+        quality: None,
+        bitrate_kbps: None,
+        width,
+        height,
+        fps: 1,
+        total_frames,
+        has_audio: false,
+        gif_fps: 1,
+    })
+}
+
+// statvfs needs an existing path - walk up until we hit one (output dirs may not exist yet)
+fn existing_ancestor(path: &Path) -> Option<PathBuf> {
+    let mut current = Some(path);
+    while let Some(p) = current {
+        if p.exists() {
+            return Some(p.to_path_buf());
+        }
+        current = p.parent();
+    }
+    None
+}
+
+#[cfg(unix)]
+fn free_space_at(path: &Path) -> Option<u64> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+    #[allow(clippy::unnecessary_cast)]
+    Some(stat.f_bavail as u64 * stat.f_frsize as u64)
+}
+
+#[cfg(windows)]
+fn free_space_at(path: &Path) -> Option<u64> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::Win32::Storage::FileSystem::GetDiskFreeSpaceExW;
+
+    let wide: Vec<u16> = path.as_os_str().encode_wide().chain(std::iter::once(0)).collect();
+    let mut available: u64 = 0;
+    let ok = unsafe {
+        GetDiskFreeSpaceExW(wide.as_ptr(), &mut available, std::ptr::null_mut(), std::ptr::null_mut())
+    };
+    if ok == 0 {
+        None
+    } else {
+        Some(available)
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn free_space_at(_path: &Path) -> Option<u64> {
+    None
+}
+
+// Device number of the filesystem holding `path`
+#[cfg(unix)]
+fn volume_id(path: &Path) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    std::fs::metadata(path).ok().map(|meta| meta.dev())
+}
+
+// Serial number of the volume holding `path`, looked up through its mount point
+#[cfg(windows)]
+fn volume_id(path: &Path) -> Option<u64> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::Win32::Storage::FileSystem::{GetVolumeInformationW, GetVolumePathNameW};
+
+    let wide: Vec<u16> = path.as_os_str().encode_wide().chain(std::iter::once(0)).collect();
+    let mut mount = [0u16; 1024];
+    if unsafe { GetVolumePathNameW(wide.as_ptr(), mount.as_mut_ptr(), mount.len() as u32) } == 0 {
+        return None;
+    }
+    let mut serial: u32 = 0;
+    let ok = unsafe {
+        GetVolumeInformationW(
+            mount.as_ptr(),
+            std::ptr::null_mut(),
+            0,
+            &mut serial,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            0,
+        )
+    };
+    if ok == 0 {
+        None
+    } else {
+        Some(serial as u64)
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn volume_id(_path: &Path) -> Option<u64> {
+    None
+}
+
+/// Whether two paths (existing or not) end up on the same volume; unknown counts as different
+pub fn same_volume(a: &Path, b: &Path) -> bool {
+    let id = |path: &Path| volume_id(&existing_ancestor(path)?);
+    matches!((id(a), id(b)), (Some(x), Some(y)) if x == y)
+}
+
+/// Free bytes available to the current user on the volume holding `path`
+pub fn available_space(path: &Path) -> Option<u64> {
+    free_space_at(&existing_ancestor(path)?)
+}
+
+fn format_bytes(bytes: u64) -> String {
+    let mb = bytes as f64 / (1024.0 * 1024.0);
+    if mb >= 1024.0 {
+        format!("{:.1} GB", mb / 1024.0)
+    } else {
+        format!("{:.0} MB", mb)
+    }
+}
+
+/// Check estimated usage against free space on the output and temp volumes.
+/// Unknown free space (unsupported platform / statvfs failure) never blocks an export.
+pub fn check_space(output_path: &Path, output_bytes: u64, temp_bytes: u64) -> ExportEstimate {
+    let output_free = available_space(output_path);
+    let temp_dir = crate::temp_workspace::root();
+    let temp_free = if temp_bytes > 0 {
+        available_space(&temp_dir)
+    } else {
+        None
+    };
+
+    // Output and temp on the same volume have to share its free space
+    let shared = temp_bytes > 0 && same_volume(output_path, &temp_dir);
+
+    let mut sufficient = true;
+    let mut warning = None;
+
+    let mut check = |label: &str, needed: u64, free: Option<u64>| {
+        let Some(free) = free else { return };
+        if needed + SAFETY_MARGIN_BYTES > free {
+            sufficient = false;
+            warning = Some(format!(
+                "Not enough disk space on {} volume: need ~{}, {} free",
+                label,
+                format_bytes(needed + SAFETY_MARGIN_BYTES),
+                format_bytes(free)
+            ));
+        } else if warning.is_none() && needed as f64 > free as f64 * WARN_RATIO {
+            warning = Some(format!(
+                "Low disk space on {} volume: export needs ~{} of {} free",
+                label,
+                format_bytes(needed),
+                format_bytes(free)
+            ));
+        }
+    };
+
+    if shared {
+        check("output", output_bytes + temp_bytes, output_free);
+    } else {
+        check("output", output_bytes, output_free);
+        if temp_bytes > 0 {
+            check("temp", temp_bytes, temp_free);
+        }
+    }
+
+    ExportEstimate {
+        output_bytes,
+        temp_bytes,
+        output_free_bytes: output_free,
+        temp_free_bytes: temp_free,
+        sufficient,
+        warning,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(codec: &str) -> EstimateInput<'_> {
+        EstimateInput {
+            codec,
+            quality: None,
+            bitrate_kbps: None,
+            width: 1920,
+            height: 1080,
+            fps: 30,
+            total_frames: 300,
+            has_audio: false,
+            gif_fps: 15,
+        }
+    }
+
+    #[test]
+    fn reference_quality_uses_codec_bits_per_pixel() {
+        let bytes = estimate_output_bytes(&EstimateInput {
+            quality: Some(20.0),
+            ..input("libx264")
+        });
+        let expected = (1920.0 * 1080.0 * 300.0 * 0.10 / 8.0_f64).ceil() as u64;
+        assert_eq!(bytes, expected);
+    }
+
+    #[test]
+    fn quality_step_doubles_size() {
+        let reference = estimate_output_bytes(&EstimateInput {
+            quality: Some(20.0),
+            ..input("libx264")
+        });
+        let better = estimate_output_bytes(&EstimateInput {
+            quality: Some(14.0),
+            ..input("libx264")
+        });
+        assert_eq!(better, reference * 2);
+        // VideoToolbox q:v runs the other way - higher is bigger
+        let vt_reference = estimate_output_bytes(&EstimateInput {
+            quality: Some(75.0),
+            ..input("h264_videotoolbox")
+        });
+        let vt_better = estimate_output_bytes(&EstimateInput {
+            quality: Some(90.0),
+            ..input("h264_videotoolbox")
+        });
+        assert_eq!(vt_better, vt_reference * 2);
+    }
+
+    #[test]
+    fn bitrate_wins_over_quality() {
+        let bytes = estimate_output_bytes(&EstimateInput {
+            quality: Some(10.0),
+            bitrate_kbps: Some(8000),
+            ..input("libx264")
+        });
+        // 10 s at 8 Mbps
+        assert_eq!(bytes, 10_000_000);
+    }
+
+    #[test]
+    fn audio_is_added_except_for_gif() {
+        let silent = estimate_output_bytes(&input("libvpx-vp9"));
+        let with_audio = estimate_output_bytes(&EstimateInput {
+            has_audio: true,
+            ..input("libvpx-vp9")
+        });
+        assert_eq!(with_audio - silent, AUDIO_BITS_PER_SECOND * 10 / 8);
+
+        let gif = estimate_output_bytes(&input("gif"));
+        let gif_audio = estimate_output_bytes(&EstimateInput {
+            has_audio: true,
+            ..input("gif")
+        });
+        assert_eq!(gif, gif_audio);
+    }
+
+    #[test]
+    fn gif_counts_frames_at_the_gif_fps() {
+        let bytes = estimate_output_bytes(&input("gif"));
+        let expected = (1920.0 * 1080.0 * 150.0 * 4.0 / 8.0_f64).ceil() as u64;
+        assert_eq!(bytes, expected);
+
+        // 10 s at 10 fps is 100 frames, whatever the export rate
+        let bytes = estimate_output_bytes(&EstimateInput {
+            gif_fps: 10,
+            ..input("gif")
+        });
+        let expected = (1920.0 * 1080.0 * 100.0 * 4.0 / 8.0_f64).ceil() as u64;
+        assert_eq!(bytes, expected);
+    }
+
+    #[test]
+    fn zero_fps_does_not_divide_by_zero() {
+        let bytes = estimate_output_bytes(&EstimateInput { fps: 0, ..input("png") });
+        assert!(bytes > 0);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn same_volume_follows_device() {
+        let dir = std::env::temp_dir();
+        assert!(same_volume(&dir, &dir.join("not_created_yet").join("clip.mp4")));
+        assert!(!same_volume(&dir, Path::new("/proc")));
+    }
+}
@@ -0,0 +1,139 @@
+use std::collections::HashSet;
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+
+// `ffmpeg -filters` output, parsed once per run - the bundled binary doesn't change underneath us
+static FILTERS: OnceLock<HashSet<String>> = OnceLock::new();
+static ENCODERS: OnceLock<HashSet<String>> = OnceLock::new();
+static MUXERS: OnceLock<HashSet<String>> = OnceLock::new();
+
+// Filter lines look like " T.C zscale            V->V       Apply resizing, colorspace ...";
+// the legend above them has no "->" column, which is how it's told apart
+fn parse_filters(output: &str) -> HashSet<String> {
+    output
+        .lines()
+        .filter_map(|l| {
+            let mut cols = l.split_whitespace();
+            let _flags = cols.next()?;
+            let name = cols.next()?;
+            cols.next()?.contains("->").then(|| name.to_string())
+        })
+        .collect()
+}
+
+// Encoder lines look like " V....D libx264              libx264 H.264 ..."; legend lines
+// (" V..... = Video") have "=" where the name would be
+fn parse_encoders(output: &str) -> HashSet<String> {
+    output
+        .lines()
+        .filter_map(|l| {
+            let mut cols = l.split_whitespace();
+            let flags = cols.next()?;
+            let name = cols.next()?;
+            (flags.len() == 6 && flags.starts_with(['V', 'A', 'S']) && name != "=").then(|| name.to_string())
+        })
+        .collect()
+}
+
+// Muxer lines look like "  E avif            AVIF"; legend lines (" E. = Muxing supported")
+// and the "--" separator don't have a bare E flag column
+fn parse_muxers(output: &str) -> HashSet<String> {
+    output
+        .lines()
+        .filter_map(|l| {
+            let mut cols = l.split_whitespace();
+            let flags = cols.next()?;
+            let name = cols.next()?;
+            (matches!(flags, "E" | "DE") && name != "=").then(|| name.to_string())
+        })
+        .collect()
+}
+
+fn query(flag: &str, parse: fn(&str) -> HashSet<String>) -> HashSet<String> {
+    let Ok(ffmpeg_path) = crate::get_ffmpeg_path() else { return HashSet::new() };
+    Command::new(ffmpeg_path)
+        .args(["-hide_banner", flag])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .map(|out| parse(&String::from_utf8_lossy(&out.stdout)))
+        .unwrap_or_default()
+}
+
+/// Whether the bundled ffmpeg was built with a given filter (e.g. `zscale` needs libzimg)
+pub fn has_filter(name: &str) -> bool {
+    FILTERS.get_or_init(|| query("-filters", parse_filters)).contains(name)
+}
+
+/// Whether the bundled ffmpeg can encode with a given encoder (e.g. `prores_ks`, `libx265`)
+pub fn has_encoder(name: &str) -> bool {
+    ENCODERS.get_or_init(|| query("-encoders", parse_encoders)).contains(name)
+}
+
+/// Whether the bundled ffmpeg can write a given container (e.g. `avif` needs ffmpeg 6+)
+pub fn has_muxer(name: &str) -> bool {
+    MUXERS.get_or_init(|| query("-muxers", parse_muxers)).contains(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filters_skip_the_legend() {
+        let output = "\
+Filters:
+  T.. = Timeline support
+  .S. = Slice threading
+  ..C = Command support
+  A = Audio input/output
+  | = Source or sink filter
+ ... abench            A->A       Benchmark part of a filtergraph.
+ TSC colorspace        V->V       Convert between colorspaces.
+ .S. zscale            V->V       Apply resizing, colorspace and bit depth conversion.
+ ... anullsrc          |->A       Null audio source, return empty audio frames.
+";
+        let filters = parse_filters(output);
+        assert_eq!(filters.len(), 4);
+        assert!(filters.contains("zscale") && filters.contains("anullsrc"));
+        assert!(!filters.contains("="));
+    }
+
+    #[test]
+    fn encoders_skip_the_legend() {
+        let output = "\
+Encoders:
+ V..... = Video
+ A..... = Audio
+ S..... = Subtitle
+ .F.... = Frame-level multithreading
+ ------
+ V....D libx264              libx264 H.264 / AVC / MPEG-4 AVC / MPEG-4 part 10 (codec h264)
+ V..... h264_videotoolbox    VideoToolbox H.264 Encoder (codec h264)
+ A....D aac                  AAC (Advanced Audio Coding)
+ S..... mov_text             3GPP Timed Text subtitle
+";
+        let encoders = parse_encoders(output);
+        let mut names: Vec<_> = encoders.iter().map(String::as_str).collect();
+        names.sort();
+        assert_eq!(names, ["aac", "h264_videotoolbox", "libx264", "mov_text"]);
+    }
+
+    #[test]
+    fn muxers_skip_the_legend_and_demuxers() {
+        let output = "\
+ Formats:
+ D. = Demuxing supported
+ .E = Muxing supported
+ --
+  E avif            AVIF
+ D  aac             raw ADTS AAC (Advanced Audio Coding)
+ DE matroska        Matroska
+  E mp4             MP4 (MPEG-4 Part 14)
+";
+        let muxers = parse_muxers(output);
+        let mut names: Vec<_> = muxers.iter().map(String::as_str).collect();
+        names.sort();
+        assert_eq!(names, ["avif", "matroska", "mp4"]);
+    }
+}
@@ -0,0 +1,252 @@
+use serde::Serialize;
+use std::collections::HashSet;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use tauri::Emitter;
+
+use crate::{media_cache, probe};
+
+const MAX_THUMBNAILS: u32 = 120;
+const MAX_HEIGHT: u32 = 360;
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a];
+
+#[derive(Serialize, Clone, Debug)]
+pub struct Filmstrip {
+    pub key: String,
+    pub count: u32,
+    pub cached: bool,
+    // Thumbnail paths in timeline order; empty until generated when `cached` is false
+    pub frames: Vec<String>,
+}
+
+#[derive(Serialize, Clone)]
+struct FilmstripFramePayload {
+    key: String,
+    source: String,
+    index: u32,
+    count: u32,
+    path: String,
+}
+
+#[derive(Serialize, Clone)]
+struct FilmstripDonePayload {
+    key: String,
+    source: String,
+    error: Option<String>,
+}
+
+// Filmstrip keys currently being generated - avoids duplicate ffmpeg runs when the
+// timeline asks again before the first request has finished
+lazy_static::lazy_static! {
+    static ref IN_PROGRESS: Mutex<HashSet<String>> = Mutex::new(HashSet::new());
+}
+
+fn thumb_path(dir: &Path, index: u32) -> PathBuf {
+    dir.join(format!("thumb_{:04}.png", index))
+}
+
+fn cached_frames(dir: &Path, count: u32) -> Option<Vec<String>> {
+    (0..count)
+        .map(|i| {
+            let path = thumb_path(dir, i);
+            path.exists().then(|| path.to_string_lossy().to_string())
+        })
+        .collect()
+}
+
+/// Return cached thumbnails right away, or start generating them in the background.
+/// Each finished thumbnail is emitted as `filmstrip-frame`, completion as `filmstrip-done`.
+pub fn generate(app: &tauri::AppHandle, source: &Path, count: u32, height: u32) -> Result<Filmstrip, String> {
+    let count = count.clamp(1, MAX_THUMBNAILS);
+    let height = (height.clamp(16, MAX_HEIGHT) / 2) * 2;
+
+    let content_key = media_cache::content_key(source)?;
+    let key = format!("{}_{}x{}", content_key, count, height);
+    let dir = media_cache::cache_dir(app, "filmstrips")?.join(&key);
+
+    if let Some(frames) = cached_frames(&dir, count) {
+        return Ok(Filmstrip { key, count, cached: true, frames });
+    }
+
+    let already_running = !IN_PROGRESS
+        .lock()
+        .map_err(|e| format!("Failed to lock filmstrip jobs: {e}"))?
+        .insert(key.clone());
+    if !already_running {
+        std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create filmstrip dir: {e}"))?;
+
+        let app = app.clone();
+        let source = source.to_path_buf();
+        let job_key = key.clone();
+        std::thread::spawn(move || {
+            let result = extract_thumbnails(&app, &source, &dir, &job_key, count, height);
+            if let Ok(mut jobs) = IN_PROGRESS.lock() {
+                jobs.remove(&job_key);
+            }
+            if let Err(e) = &result {
+                log::warn!("[Filmstrip] {} failed: {}", source.display(), e);
+            }
+            let _ = app.emit(
+                "filmstrip-done",
+                FilmstripDonePayload {
+                    key: job_key,
+                    source: source.to_string_lossy().to_string(),
+                    error: result.err(),
+                },
+            );
+        });
+    }
+
+    Ok(Filmstrip { key, count, cached: false, frames: Vec::new() })
+}
+
+// Read one PNG off an image2pipe stream; None at a clean end of stream
+fn read_png(reader: &mut impl Read) -> std::io::Result<Option<Vec<u8>>> {
+    let mut png = vec![0u8; PNG_SIGNATURE.len()];
+    match reader.read_exact(&mut png) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+    if png != PNG_SIGNATURE {
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "not a PNG stream"));
+    }
+    // Chunks are length, type, data, CRC; the image ends with IEND
+    loop {
+        let mut header = [0u8; 8];
+        reader.read_exact(&mut header)?;
+        let length = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+        png.extend_from_slice(&header);
+        let start = png.len();
+        png.resize(start + length + 4, 0);
+        reader.read_exact(&mut png[start..])?;
+        if &header[4..] == b"IEND" {
+            return Ok(Some(png));
+        }
+    }
+}
+
+fn extract_thumbnails(
+    app: &tauri::AppHandle,
+    source: &Path,
+    dir: &Path,
+    key: &str,
+    count: u32,
+    height: u32,
+) -> Result<(), String> {
+    let info = probe::probe(source)?;
+    if info.streams.iter().all(|s| s.kind != "video") {
+        return Err("No video stream".into());
+    }
+    let duration_secs = info.duration_ms.unwrap_or(0) as f64 / 1000.0;
+    if duration_secs <= 0.0 {
+        return Err("Media has no duration".into());
+    }
+    let ffmpeg_path = crate::get_ffmpeg_path()?;
+    let source_str = source.to_string_lossy().to_string();
+
+    // One decode for the whole strip: start half a slot in so each frame sits in the middle
+    // of its slot, then keep `count` frames per duration
+    let mut child = Command::new(&ffmpeg_path)
+        .args(["-hide_banner", "-nostdin", "-loglevel", "error", "-ss"])
+        .arg(format!("{:.3}", duration_secs / count as f64 / 2.0))
+        .arg("-i")
+        .arg(source)
+        .args(["-an", "-vf"])
+        .arg(format!("fps={}/{:.3},scale=-2:{}:flags=bicubic", count, duration_secs, height))
+        .args(["-frames:v", &count.to_string(), "-c:v", "png", "-f", "image2pipe", "pipe:1"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Failed to run ffmpeg: {e}"))?;
+    let mut stdout = child.stdout.take().ok_or("Failed to read ffmpeg output")?;
+
+    let emit = |index: u32, target: &Path| {
+        let _ = app.emit(
+            "filmstrip-frame",
+            FilmstripFramePayload {
+                key: key.to_string(),
+                source: source_str.clone(),
+                index,
+                count,
+                path: target.to_string_lossy().to_string(),
+            },
+        );
+    };
+
+    let mut written = 0;
+    let mut last_png = Vec::new();
+    while written < count {
+        let png = match read_png(&mut stdout) {
+            Ok(Some(png)) => png,
+            Ok(None) => break,
+            Err(e) => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!("Failed to read thumbnail {}: {e}", written));
+            }
+        };
+        let target = thumb_path(dir, written);
+        media_cache::write_atomic(&target, &png)?;
+        emit(written, &target);
+        last_png = png;
+        written += 1;
+    }
+
+    let status = child.wait().map_err(|e| format!("Failed to wait for ffmpeg: {e}"))?;
+    if written == 0 {
+        return Err(format!("ffmpeg produced no thumbnails ({})", status));
+    }
+    // The video stream can end a little before the container duration; repeat its last frame
+    for index in written..count {
+        let target = thumb_path(dir, index);
+        media_cache::write_atomic(&target, &last_png)?;
+        emit(index, &target);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(kind: &[u8; 4], data: &[u8]) -> Vec<u8> {
+        let mut bytes = (data.len() as u32).to_be_bytes().to_vec();
+        bytes.extend_from_slice(kind);
+        bytes.extend_from_slice(data);
+        // CRC isn't checked when splitting
+        bytes.extend_from_slice(&[0; 4]);
+        bytes
+    }
+
+    fn png(body: &[u8]) -> Vec<u8> {
+        let mut bytes = PNG_SIGNATURE.to_vec();
+        bytes.extend(chunk(b"IHDR", &[0; 13]));
+        bytes.extend(chunk(b"IDAT", body));
+        bytes.extend(chunk(b"IEND", &[]));
+        bytes
+    }
+
+    #[test]
+    fn splits_a_png_stream() {
+        let first = png(b"first");
+        let second = png(&[0x49, 0x45, 0x4e, 0x44]); // "IEND" inside the data is not an end
+        let stream = [first.clone(), second.clone()].concat();
+        let mut reader = stream.as_slice();
+        assert_eq!(read_png(&mut reader).unwrap(), Some(first));
+        assert_eq!(read_png(&mut reader).unwrap(), Some(second));
+        assert_eq!(read_png(&mut reader).unwrap(), None);
+    }
+
+    #[test]
+    fn truncated_or_foreign_streams_fail() {
+        let whole = png(b"frame");
+        let mut truncated = &whole[..whole.len() - 6];
+        assert!(read_png(&mut truncated).is_err());
+        let mut jpeg: &[u8] = &[0xff, 0xd8, 0xff, 0xe0, 0, 0x10, b'J', b'F', b'I', b'F'];
+        assert!(read_png(&mut jpeg).is_err());
+    }
+}
@@ -0,0 +1,226 @@
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{sync_channel, Receiver};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+// Frames decoded ahead of the current request for sequential (export) access
+const LOOKAHEAD_FRAMES: usize = 8;
+// Jumps further ahead than this restart the decoder with a seek instead of decoding through
+const MAX_SKIP_FRAMES: u32 = 90;
+
+// A running `ffmpeg ... -f rawvideo -pix_fmt rgba pipe:1` positioned at `next_index`
+struct Decoder {
+    process: Child,
+    frames: Receiver<Vec<u8>>,
+    next_index: u32,
+    width: u32,
+    height: u32,
+}
+
+impl Drop for Decoder {
+    fn drop(&mut self) {
+        let _ = self.process.kill();
+        let _ = self.process.wait();
+    }
+}
+
+struct FrameClip {
+    path: PathBuf,
+    fps: u32,
+    decoder: Option<Decoder>,
+    // Last frame handed out - served again for repeats and for times past the clip end
+    last: Option<LastFrame>,
+}
+
+// (index, width, height, rgba)
+type LastFrame = (u32, u32, u32, Arc<Vec<u8>>);
+
+// Open clips keyed by clip id; each clip has its own lock so decoding one doesn't block others
+lazy_static::lazy_static! {
+    static ref CLIPS: Mutex<HashMap<String, Arc<Mutex<FrameClip>>>> = Mutex::new(HashMap::new());
+}
+
+fn spawn_decoder(path: &PathBuf, fps: u32, start_index: u32, width: u32, height: u32) -> Result<Decoder, String> {
+    let start_secs = start_index as f64 / fps as f64;
+    let ffmpeg_path = crate::get_ffmpeg_path()?;
+    // Input-side -ss is frame-accurate when decoding (not stream copying); the fps filter
+    // resamples to the export rate so frame N of the output is exactly N / fps seconds in
+    let mut process = Command::new(&ffmpeg_path)
+        .args(["-hide_banner", "-nostdin", "-loglevel", "error", "-ss"])
+        .arg(format!("{:.6}", start_secs))
+        .arg("-i")
+        .arg(path)
+        .args(["-an", "-vf"])
+        .arg(format!("fps={},scale={}:{}:flags=bicubic", fps, width, height))
+        .args(["-f", "rawvideo", "-pix_fmt", "rgba", "pipe:1"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Failed to spawn ffmpeg: {e}"))?;
+
+    let mut stdout = process.stdout.take().ok_or("Failed to get ffmpeg stdout")?;
+    let frame_size = (width * height * 4) as usize;
+    let (tx, rx) = sync_channel(LOOKAHEAD_FRAMES);
+
+    // Reader thread keeps up to LOOKAHEAD_FRAMES decoded frames queued; exits on EOF or
+    // once the receiver is dropped (decoder restarted or clip closed)
+    thread::spawn(move || {
+        let mut reader = std::io::BufReader::with_capacity(frame_size.min(8 * 1024 * 1024), &mut stdout);
+        loop {
+            let mut frame = vec![0u8; frame_size];
+            if reader.read_exact(&mut frame).is_err() || tx.send(frame).is_err() {
+                break;
+            }
+        }
+    });
+
+    Ok(Decoder {
+        process,
+        frames: rx,
+        next_index: start_index,
+        width,
+        height,
+    })
+}
+
+// Frame of the export-rate stream at `time_ms`; rounded so e.g. 33.333ms at 30fps is frame 1, not 0
+fn frame_index(time_ms: f64, fps: u32) -> u32 {
+    (time_ms.max(0.0) * fps as f64 / 1000.0).round() as u32
+}
+
+// Whether a decoder positioned at `next_index` can decode through to `index`: same size, and a
+// forward jump short enough that decoding is cheaper than restarting with a seek
+fn can_reuse(next_index: u32, decoder_size: (u32, u32), index: u32, size: (u32, u32)) -> bool {
+    decoder_size == size && index >= next_index && index - next_index <= MAX_SKIP_FRAMES
+}
+
+// Frame to hold once the decoder hits EOF: the last one served, if it has the requested size -
+// like a <video> element clamped at its end
+fn held_frame(last: Option<&LastFrame>, index: u32, width: u32, height: u32) -> Result<Arc<Vec<u8>>, String> {
+    match last {
+        Some((_, w, h, data)) if *w == width && *h == height => Ok(data.clone()),
+        _ => Err(format!("No frame at index {} (past end of clip)", index)),
+    }
+}
+
+impl FrameClip {
+    fn frame(&mut self, index: u32, width: u32, height: u32) -> Result<Arc<Vec<u8>>, String> {
+        if let Some((last_index, w, h, data)) = &self.last {
+            if *last_index == index && *w == width && *h == height {
+                return Ok(data.clone());
+            }
+        }
+
+        let reusable = self
+            .decoder
+            .as_ref()
+            .is_some_and(|d| can_reuse(d.next_index, (d.width, d.height), index, (width, height)));
+        if !reusable {
+            self.decoder = Some(spawn_decoder(&self.path, self.fps, index, width, height)?);
+        }
+
+        let decoder = self.decoder.as_mut().ok_or("Decoder not running")?;
+        while decoder.next_index <= index {
+            match decoder.frames.recv() {
+                Ok(data) => {
+                    let current = decoder.next_index;
+                    decoder.next_index += 1;
+                    if current == index {
+                        let data = Arc::new(data);
+                        self.last = Some((index, width, height, data.clone()));
+                        return Ok(data);
+                    }
+                }
+                Err(_) => {
+                    // EOF: the clip is shorter than the requested time
+                    self.decoder = None;
+                    return held_frame(self.last.as_ref(), index, width, height);
+                }
+            }
+        }
+        Err(format!("Frame {} not decoded", index))
+    }
+}
+
+/// Register a clip for frame serving at the export frame rate
+pub fn open(clip_id: &str, path: PathBuf, fps: u32) -> Result<(), String> {
+    if fps == 0 {
+        return Err("fps must be greater than 0".into());
+    }
+    let clip = FrameClip {
+        path,
+        fps,
+        decoder: None,
+        last: None,
+    };
+    CLIPS
+        .lock()
+        .map_err(|e| format!("Failed to lock frame clips: {e}"))?
+        .insert(clip_id.to_string(), Arc::new(Mutex::new(clip)));
+    Ok(())
+}
+
+/// Exact RGBA frame (width * height * 4 bytes) of a clip at `time_ms` from the clip start
+pub fn get_frame(clip_id: &str, time_ms: f64, width: u32, height: u32) -> Result<Arc<Vec<u8>>, String> {
+    if width == 0 || height == 0 {
+        return Err("Frame size must be greater than 0".into());
+    }
+    let clip = CLIPS
+        .lock()
+        .map_err(|e| format!("Failed to lock frame clips: {e}"))?
+        .get(clip_id)
+        .cloned()
+        .ok_or_else(|| format!("Frame clip not found: {}", clip_id))?;
+
+    let mut clip = clip.lock().map_err(|e| format!("Failed to lock frame clip: {e}"))?;
+    let index = frame_index(time_ms, clip.fps);
+    clip.frame(index, width, height)
+}
+
+/// Stop the clip's decoder and forget it
+pub fn close(clip_id: &str) {
+    if let Ok(mut clips) = CLIPS.lock() {
+        clips.remove(clip_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn times_round_to_the_nearest_frame() {
+        assert_eq!(frame_index(0.0, 30), 0);
+        assert_eq!(frame_index(1000.0 / 30.0, 30), 1);
+        // Float error just below a frame boundary still lands on it
+        assert_eq!(frame_index(33.3333, 30), 1);
+        assert_eq!(frame_index(16.0, 30), 0);
+        assert_eq!(frame_index(17.0, 30), 1);
+        assert_eq!(frame_index(1000.0, 60), 60);
+        assert_eq!(frame_index(-5.0, 30), 0);
+    }
+
+    #[test]
+    fn decoder_is_reused_only_for_short_forward_steps() {
+        let size = (640, 360);
+        assert!(can_reuse(10, size, 10, size));
+        assert!(can_reuse(10, size, 10 + MAX_SKIP_FRAMES, size));
+        assert!(!can_reuse(10, size, 11 + MAX_SKIP_FRAMES, size));
+        // Going back needs a seek
+        assert!(!can_reuse(10, size, 9, size));
+        // So does a size change, even at the next index
+        assert!(!can_reuse(10, size, 10, (1280, 720)));
+    }
+
+    #[test]
+    fn eof_holds_the_last_frame_of_the_same_size() {
+        let last: LastFrame = (41, 2, 2, Arc::new(vec![7; 16]));
+        assert_eq!(*held_frame(Some(&last), 50, 2, 2).unwrap(), vec![7; 16]);
+        assert!(held_frame(Some(&last), 50, 4, 4).is_err());
+        assert!(held_frame(None, 0, 2, 2).unwrap_err().contains("past end of clip"));
+    }
+}
@@ -0,0 +1,368 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+const DEFAULT_FPS: u32 = 15;
+const DEFAULT_COLORS: u32 = 256;
+// Size-budget mode never goes below these, whatever the budget
+const MIN_FPS: u32 = 6;
+const MIN_COLORS: u32 = 32;
+const MIN_SCALE: f64 = 0.4;
+const MAX_ATTEMPTS: usize = 10;
+
+/// Name of the lossless master inside the job folder in size-budget mode
+pub const MASTER_FILE: &str = "master.mkv";
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum GifDither {
+    Bayer,
+    Heckbert,
+    #[default]
+    FloydSteinberg,
+    Sierra2,
+    #[serde(rename = "sierra2_4a")]
+    Sierra24a,
+    None,
+}
+
+impl GifDither {
+    fn as_ffmpeg(self) -> &'static str {
+        match self {
+            GifDither::Bayer => "bayer",
+            GifDither::Heckbert => "heckbert",
+            GifDither::FloydSteinberg => "floyd_steinberg",
+            GifDither::Sierra2 => "sierra2",
+            GifDither::Sierra24a => "sierra2_4a",
+            GifDither::None => "none",
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum GifPalette {
+    // One palette for the whole clip, weighted towards what changes between frames
+    #[default]
+    Global,
+    // A fresh palette per frame - better colour on cuts and gradients, bigger files
+    PerFrame,
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct GifOptions {
+    #[serde(default)]
+    pub fps: Option<u32>,
+    // Palette size, 2-256
+    #[serde(default)]
+    pub max_colors: Option<u32>,
+    #[serde(default)]
+    pub dither: GifDither,
+    // 0-5; 0 turns dithering off, for bayer it also sets the pattern scale (5 = strongest)
+    #[serde(default)]
+    pub dither_strength: Option<u32>,
+    #[serde(default)]
+    pub palette: GifPalette,
+    // Times to play the animation, 0 = forever
+    #[serde(default)]
+    pub loop_count: u32,
+    // Size budget in bytes (e.g. 10 MB for GitHub); fps, colours and scale are reduced until it fits
+    #[serde(default)]
+    pub max_bytes: Option<u64>,
+}
+
+/// One encode of the size-budget search
+#[derive(Serialize, Clone, Debug)]
+pub struct GifAttempt {
+    pub fps: u32,
+    pub max_colors: u32,
+    pub scale: f64,
+    pub bytes: u64,
+    pub fits: bool,
+}
+
+/// Frame rate a GIF export is resampled to for the requested `fps`
+pub fn effective_fps(fps: Option<u32>) -> u32 {
+    fps.unwrap_or(DEFAULT_FPS).clamp(1, 50)
+}
+
+// The knobs the size-budget search turns
+#[derive(Clone, Copy)]
+struct Settings {
+    fps: u32,
+    max_colors: u32,
+    scale: f64,
+}
+
+impl Settings {
+    fn from_options(options: &GifOptions) -> Self {
+        Settings {
+            fps: effective_fps(options.fps),
+            max_colors: options.max_colors.unwrap_or(DEFAULT_COLORS).clamp(2, 256),
+            scale: 1.0,
+        }
+    }
+
+    // Cut one knob, rotating fps -> colours -> scale and skipping knobs already at their floor.
+    // Returns false once everything is at the floor.
+    fn reduce(&mut self, step: usize) -> bool {
+        for i in 0..3 {
+            match (step + i) % 3 {
+                0 if self.fps > MIN_FPS => {
+                    self.fps = (self.fps * 4 / 5).max(MIN_FPS);
+                    return true;
+                }
+                1 if self.max_colors > MIN_COLORS => {
+                    self.max_colors = (self.max_colors / 2).max(MIN_COLORS);
+                    return true;
+                }
+                2 if self.scale > MIN_SCALE => {
+                    self.scale = (self.scale * 0.85).max(MIN_SCALE);
+                    return true;
+                }
+                _ => {}
+            }
+        }
+        false
+    }
+}
+
+fn filter(scale_filter: &str, settings: Settings, options: &GifOptions, alpha: bool) -> String {
+    let stats_mode = match options.palette {
+        GifPalette::Global => "diff",
+        GifPalette::PerFrame => "single",
+    };
+    let mut palettegen = format!("palettegen=max_colors={}:stats_mode={}", settings.max_colors, stats_mode);
+    let strength = options.dither_strength.map(|s| s.min(5));
+    let dither = match strength {
+        Some(0) => GifDither::None,
+        _ => options.dither,
+    };
+    let mut paletteuse = format!("paletteuse=dither={}", dither.as_ffmpeg());
+    if let (GifDither::Bayer, Some(strength)) = (dither, strength) {
+        // bayer_scale 0 is the most visible pattern
+        paletteuse.push_str(&format!(":bayer_scale={}", 5 - strength));
+    }
+    if options.palette == GifPalette::PerFrame {
+        paletteuse.push_str(":new=1");
+    }
+    if alpha {
+        // One palette slot is kept for transparency; pixels under the threshold use it
+        palettegen.push_str(":reserve_transparent=1");
+        paletteuse.push_str(":alpha_threshold=128");
+    }
+    format!(
+        "{},fps={},split[s0][s1];[s0]{}[p];[s1][p]{}",
+        scale_filter, settings.fps, palettegen, paletteuse
+    )
+}
+
+// GIF loop extension counts repeats after the first play; -1 writes none (play once)
+fn loop_arg(loop_count: u32) -> String {
+    match loop_count {
+        0 => 0,
+        1 => -1,
+        plays => plays as i64 - 1,
+    }
+    .to_string()
+}
+
+/// Video encoding args for GIF output; `alpha` keeps transparency
+pub fn video_args(scale_filter: String, options: &GifOptions, alpha: bool) -> Vec<String> {
+    let mut args = vec![
+        "-vf".to_string(),
+        filter(&scale_filter, Settings::from_options(options), options, alpha),
+    ];
+    if alpha {
+        // transdiff would mark unchanged pixels transparent, which real transparency breaks
+        args.extend(["-gifflags".to_string(), "-offsetting-transdiff".to_string()]);
+    }
+    args.extend(["-loop".to_string(), loop_arg(options.loop_count)]);
+    args
+}
+
+/// Lossless intermediate the size-budget search re-encodes from. FFV1 keeps alpha.
+pub fn master_args(scale_filter: String) -> Vec<String> {
+    vec![
+        "-vf".to_string(),
+        scale_filter,
+        "-c:v".to_string(),
+        "ffv1".to_string(),
+        "-pix_fmt".to_string(),
+        "bgra".to_string(),
+        "-f".to_string(),
+        "matroska".to_string(),
+    ]
+}
+
+fn encode_attempt(
+    ffmpeg_path: &Path,
+    master: &Path,
+    target: &Path,
+    settings: Settings,
+    options: &GifOptions,
+    alpha: bool,
+) -> Result<u64, String> {
+    let scale_filter = if settings.scale < 1.0 {
+        format!("scale=trunc(iw*{:.3}):-1:flags=lanczos", settings.scale)
+    } else {
+        "null".to_string()
+    };
+    let mut command = Command::new(ffmpeg_path);
+    command
+        .args(["-hide_banner", "-nostdin", "-y", "-loglevel", "error", "-i"])
+        .arg(master)
+        .args(["-vf", &filter(&scale_filter, settings, options, alpha)]);
+    if alpha {
+        command.args(["-gifflags", "-offsetting-transdiff"]);
+    }
+    let output = command
+        .args(["-loop", &loop_arg(options.loop_count), "-f", "gif"])
+        .arg(target)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .output()
+        .map_err(|e| format!("Failed to spawn ffmpeg: {e}"))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("GIF encode failed: {}", stderr.lines().last().unwrap_or("unknown error")));
+    }
+    std::fs::metadata(target)
+        .map(|m| m.len())
+        .map_err(|e| format!("Failed to read GIF size: {e}"))
+}
+
+/// Encode `master` to `target` as a GIF under `options.max_bytes`, lowering fps, palette size
+/// and scale in turn until it fits. `on_attempt` sees every encode; all attempts are returned.
+pub fn fit_to_budget(
+    master: &Path,
+    target: &Path,
+    options: &GifOptions,
+    alpha: bool,
+    mut on_attempt: impl FnMut(&GifAttempt),
+) -> Result<Vec<GifAttempt>, String> {
+    let budget = options.max_bytes.ok_or("No GIF size budget set")?;
+    let ffmpeg_path = crate::get_ffmpeg_path()?;
+    let mut settings = Settings::from_options(options);
+    let mut attempts = Vec::new();
+
+    for step in 0..MAX_ATTEMPTS {
+        let bytes = encode_attempt(&ffmpeg_path, master, target, settings, options, alpha)?;
+        let attempt = GifAttempt {
+            fps: settings.fps,
+            max_colors: settings.max_colors,
+            scale: settings.scale,
+            bytes,
+            fits: bytes <= budget,
+        };
+        log::info!("[Gif] Attempt {}: {:?}", step + 1, attempt);
+        on_attempt(&attempt);
+        attempts.push(attempt);
+        if bytes <= budget || !settings.reduce(step) {
+            break;
+        }
+    }
+
+    match attempts.last() {
+        Some(last) if last.fits => Ok(attempts),
+        Some(last) => Err(format!(
+            "GIF is still {} KB after {} attempts (fps {}, {} colours, {:.0}% scale), over the {} KB budget",
+            last.bytes / 1024,
+            attempts.len(),
+            last.fps,
+            last.max_colors,
+            last.scale * 100.0,
+            budget / 1024
+        )),
+        None => Err("GIF encode produced no attempts".into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loop_arg_counts_repeats_after_the_first_play() {
+        assert_eq!(loop_arg(0), "0");
+        assert_eq!(loop_arg(1), "-1");
+        assert_eq!(loop_arg(2), "1");
+        assert_eq!(loop_arg(10), "9");
+    }
+
+    #[test]
+    fn reduce_rotates_knobs() {
+        let mut settings = Settings::from_options(&GifOptions::default());
+        assert!(settings.reduce(0));
+        assert_eq!((settings.fps, settings.max_colors, settings.scale), (12, 256, 1.0));
+        assert!(settings.reduce(1));
+        assert_eq!(settings.max_colors, 128);
+        assert!(settings.reduce(2));
+        assert_eq!(settings.scale, 0.85);
+    }
+
+    #[test]
+    fn reduce_skips_knobs_at_their_floor_and_stops() {
+        let mut settings = Settings::from_options(&GifOptions {
+            fps: Some(MIN_FPS),
+            ..Default::default()
+        });
+        // fps is already at the floor, so step 0 cuts colours instead
+        assert!(settings.reduce(0));
+        assert_eq!((settings.fps, settings.max_colors), (MIN_FPS, 128));
+
+        let mut steps = 0;
+        while settings.reduce(steps) {
+            steps += 1;
+            assert!(steps < 100, "reduce never reached the floor");
+        }
+        assert_eq!((settings.fps, settings.max_colors, settings.scale), (MIN_FPS, MIN_COLORS, MIN_SCALE));
+    }
+
+    #[test]
+    fn options_are_clamped() {
+        let settings = Settings::from_options(&GifOptions {
+            fps: Some(120),
+            max_colors: Some(1),
+            ..Default::default()
+        });
+        assert_eq!((settings.fps, settings.max_colors), (50, 2));
+    }
+
+    #[test]
+    fn filter_graph_follows_options() {
+        let options = GifOptions::default();
+        let settings = Settings::from_options(&options);
+        assert_eq!(
+            filter("scale=480:-2", settings, &options, false),
+            "scale=480:-2,fps=15,split[s0][s1];[s0]palettegen=max_colors=256:stats_mode=diff[p];[s1][p]paletteuse=dither=floyd_steinberg"
+        );
+
+        let bayer = GifOptions {
+            dither: GifDither::Bayer,
+            dither_strength: Some(4),
+            palette: GifPalette::PerFrame,
+            ..Default::default()
+        };
+        let graph = filter("scale=480:-2", settings, &bayer, true);
+        assert!(graph.contains("palettegen=max_colors=256:stats_mode=single:reserve_transparent=1"), "{graph}");
+        assert!(graph.ends_with("paletteuse=dither=bayer:bayer_scale=1:new=1:alpha_threshold=128"), "{graph}");
+
+        // Strength 0 turns dithering off whatever the mode
+        let off = GifOptions {
+            dither_strength: Some(0),
+            ..Default::default()
+        };
+        assert!(filter("scale=2:2", settings, &off, false).ends_with("paletteuse=dither=none"));
+    }
+
+    #[test]
+    fn alpha_disables_transdiff() {
+        let args = video_args("scale=2:2".into(), &GifOptions::default(), true);
+        assert!(args.windows(2).any(|w| w == ["-gifflags", "-offsetting-transdiff"]));
+        let opaque = video_args("scale=2:2".into(), &GifOptions::default(), false);
+        assert!(!opaque.iter().any(|arg| arg == "-gifflags"));
+        assert_eq!(opaque[opaque.len() - 2..], ["-loop", "0"]);
+    }
+}
@@ -1,42 +1,240 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::PathBuf;
 use std::process::{Child, Command, Stdio};
 use std::sync::{Arc, Mutex};
-use std::sync::mpsc::{sync_channel, SyncSender, Receiver};
+use std::sync::mpsc::{sync_channel, SyncSender};
 use std::thread;
 use std::io::Write;
-use tauri::Emitter;
+use tauri::{Emitter, Manager};
+use tauri_plugin_dialog::DialogExt;
 use tauri_plugin_shell::process::CommandEvent;
 use tauri_plugin_shell::ShellExt;
 
+mod alpha;
+mod animated_image;
+mod audio;
+mod audio_export;
+mod beats;
+mod color;
+mod disk_space;
+mod ffmpeg_caps;
+mod filmstrip;
+mod frame_server;
+mod gif;
+mod media_cache;
+mod media_import;
+mod output;
+mod path_scope;
+mod probe;
+mod sequence;
+mod temp_workspace;
+#[cfg(test)]
+mod test_util;
+mod video_codec;
+mod waveform;
+
+use animated_image::AnimationOptions;
+use audio::{AudioConformance, AudioOptions, AudioTrack, Conform, LoudnessMeasurement};
+use color::ColorSpace;
+use gif::{GifAttempt, GifOptions};
+use sequence::{FramePattern, FrameSize, SequenceOptions};
+use output::CollisionPolicy;
+use video_codec::VideoCodec;
+
 #[derive(Serialize, Clone)]
 struct ProgressPayload {
     progress: f32,
     message: String,
 }
 
-// Streaming encoder state - holds channel sender and worker thread handle
+// One output of a streaming session - an ffmpeg process (or the sequence writer) fed from the frame stream
 struct StreamingEncoder {
     process: Option<Child>, // Wrapped in Option to take ownership during finish
     sender: Option<SyncSender<Option<Vec<u8>>>>, // Send None to signal EOF
     worker_thread: Option<thread::JoinHandle<Result<(), String>>>,
+    format: String,
+    total_frames: u32,
+    current_frame: Arc<Mutex<u32>>, // Frames handed to this output
+    error: Option<String>, // Why this output stopped taking frames, if it did
+    last_error: Arc<Mutex<Option<String>>>, // Last ffmpeg/worker error for diagnostics
+    log_path: PathBuf,
+    output_path: PathBuf, // Path the user asked for
+    temp_output_path: PathBuf, // Hidden sibling ffmpeg writes into until finish
+    collision_policy: CollisionPolicy,
+    loudness: Option<LoudnessMeasurement>, // First-pass measurement when normalizing
+    audio_conform: Option<Conform>, // Set when the output has audio
+    finalize: Finalize,
+}
+
+// Streaming session - one captured frame stream fanned out to every output
+struct StreamingSession {
     input_width: u32,
     input_height: u32,
     total_frames: u32,
     current_frame: Arc<Mutex<u32>>, // Shared counter for progress
-    last_error: Arc<Mutex<Option<String>>>, // Last ffmpeg/worker error for diagnostics
-    log_path: PathBuf,
+    outputs: Vec<StreamingEncoder>,
+}
+
+// How finish turns what the session wrote into the output
+enum Finalize {
+    // Written in place at temp_output_path (a file, or a folder for image sequences)
+    Direct,
+    // Frames in a job folder, zipped into the output
+    Zip(temp_workspace::TempWorkspace, FramePattern),
+    // Lossless master in a job folder, re-encoded into a GIF that fits the size budget
+    GifBudget(temp_workspace::TempWorkspace, GifOptions, bool),
+}
+
+impl Finalize {
+    fn work_dir(&self) -> Option<&temp_workspace::TempWorkspace> {
+        match self {
+            Finalize::Direct => None,
+            Finalize::Zip(workspace, _) | Finalize::GifBudget(workspace, _, _) => Some(workspace),
+        }
+    }
+}
+
+/// One output of `start_streaming_encode`. On outputs after the first, unset sizes and option
+/// blocks fall back to the first output's; alpha and the video codec are per format, so they
+/// default to off / auto. Unset sizes on the first output mean the capture size.
+#[derive(Deserialize, Clone, Debug, Default)]
+struct OutputSpec {
+    output_path: String,
+    #[serde(default)]
+    format: Option<String>,
+    #[serde(default)]
+    width: Option<u32>,
+    #[serde(default)]
+    height: Option<u32>,
+    #[serde(default)]
+    alpha: Option<bool>,
+    #[serde(default)]
+    video_codec: Option<VideoCodec>,
+    #[serde(default)]
+    animation_options: Option<AnimationOptions>,
+    #[serde(default)]
+    gif_options: Option<GifOptions>,
+    #[serde(default)]
+    sequence_options: Option<SequenceOptions>,
+}
+
+impl OutputSpec {
+    fn with_defaults(self, primary: &OutputSpec) -> OutputSpec {
+        OutputSpec {
+            width: self.width.or(primary.width),
+            height: self.height.or(primary.height),
+            animation_options: self.animation_options.or_else(|| primary.animation_options.clone()),
+            gif_options: self.gif_options.or_else(|| primary.gif_options.clone()),
+            sequence_options: self.sequence_options.or_else(|| primary.sequence_options.clone()),
+            ..self
+        }
+    }
+
+    fn format(&self) -> &str {
+        self.format.as_deref().unwrap_or("mp4")
+    }
+
+    // Encoded size; the capture size when unset
+    fn size(&self, session: &SessionInput) -> (u32, u32) {
+        (
+            self.width.unwrap_or(session.input_width),
+            self.height.unwrap_or(session.input_height),
+        )
+    }
+
+    fn alpha(&self) -> bool {
+        self.alpha.unwrap_or(false)
+    }
+
+    // Explicit HEVC/AV1 pick, or None for the format's default encoder
+    fn encoder(&self) -> Result<Option<&'static str>, String> {
+        resolve_video_encoder(self.format(), self.alpha(), self.video_codec.unwrap_or_default())
+    }
+}
+
+/// What every output of a session shares: the captured frames and the audio mix
+#[derive(Deserialize, Clone, Debug, Default)]
+struct SessionInput {
+    // Size of the RGBA frames `send_frame` receives
+    input_width: u32,
+    input_height: u32,
+    fps: u32,
+    total_frames: u32,
+    #[serde(default)]
+    use_hw: Option<bool>,
+    // Probed and filtered by `audio::prepare_tracks` once the session starts
+    #[serde(default)]
+    audio_tracks: Vec<AudioTrack>,
+    #[serde(default)]
+    audio_options: AudioOptions,
+    #[serde(default)]
+    collision_policy: CollisionPolicy,
+    // What the canvas captured in
+    #[serde(default)]
+    color_space: ColorSpace,
+}
+
+impl SessionInput {
+    // Hardware encoders unless the caller opts out
+    fn use_hw(&self) -> bool {
+        self.use_hw.unwrap_or(true)
+    }
+}
+
+/// Audio an output muxes in: its share of the session tracks plus what was measured for it
+#[derive(Clone, Copy, Default)]
+struct OutputAudio<'a> {
+    tracks: &'a [AudioTrack],
+    loudness: Option<&'a LoudnessMeasurement>,
+    conform: Option<&'a Conform>,
+}
+
+/// One output of `finish_streaming_encode`
+#[derive(Serialize, Clone, Debug)]
+struct OutputResult {
+    format: String,
+    path: Option<String>, // None when this output failed
+    error: Option<String>,
+    frames: u32,
+    loudness: Option<LoudnessMeasurement>,
+    audio_conformance: Option<AudioConformance>,
+    gif_attempts: Option<Vec<GifAttempt>>,
+    // Problems with a file that was still written, e.g. audio not matching the video length
+    warnings: Vec<String>,
+}
+
+/// What `finish_streaming_encode` produced - doubles as the render report. The top-level
+/// fields describe the first output that succeeded; `outputs` lists every output in order.
+#[derive(Serialize, Clone, Debug)]
+struct StreamingEncodeResult {
+    path: String,
+    frames: u32,
+    loudness: Option<LoudnessMeasurement>,
+    audio_conformance: Option<AudioConformance>,
+    gif_attempts: Option<Vec<GifAttempt>>,
+    // Warnings of every output, for the UI to show once the export is done
+    warnings: Vec<String>,
+    outputs: Vec<OutputResult>,
+}
+
+/// Progress of one output while a session streams
+#[derive(Serialize, Clone, Debug)]
+struct OutputStatus {
+    format: String,
+    path: String,
+    frames: u32,
+    progress: f32,
+    error: Option<String>,
 }
 
 // Global encoder registry for managing multiple concurrent encoders
 lazy_static::lazy_static! {
-    static ref ENCODERS: Arc<Mutex<HashMap<String, StreamingEncoder>>> = Arc::new(Mutex::new(HashMap::new()));
+    static ref ENCODERS: Arc<Mutex<HashMap<String, StreamingSession>>> = Arc::new(Mutex::new(HashMap::new()));
 }
 
 fn parse_frame(line: &str) -> Option<u32> {
     line.strip_prefix("frame=")?
-        .trim()
         .split_whitespace()
         .next()?
         .parse::<u32>()
@@ -50,21 +248,50 @@ fn parse_out_time(line: &str) -> Option<f32> {
 }
 
 #[tauri::command]
-async fn copy_file(src: String, dest: String) -> Result<(), String> {
+async fn copy_file(app: tauri::AppHandle, src: String, dest: String) -> Result<(), String> {
+    let src = path_scope::ensure_in_scope(&app, std::path::Path::new(&src))?;
+    let dest = path_scope::ensure_in_scope(&app, std::path::Path::new(&dest))?;
+    if !src.is_file() {
+        return Err(format!("Source is not a file: {}", src.display()));
+    }
+    if src == dest {
+        return Err("Source and destination are the same file".into());
+    }
     std::fs::copy(&src, &dest).map_err(|e| format!("Failed to copy file: {e}"))?;
     Ok(())
 }
 
-fn get_encoder_args(format: &str, width: u32, height: u32, use_hw: bool) -> Vec<String> {
-    // Scale filter - bicubic offers good quality/speed balance
+fn get_encoder_args(
+    format: &str,
+    width: u32,
+    height: u32,
+    use_hw: bool,
+    alpha: bool,
+    encoder: Option<&str>,
+) -> Vec<String> {
+    // Scale filter - bicubic offers good quality/speed balance; it also does the YUV conversion
     let scale_w = if width % 2 == 0 { width } else { width + 1 };
     let scale_h = if height % 2 == 0 { height } else { height + 1 };
-    let scale_filter = format!("scale={}:{}:flags=bicubic", scale_w, scale_h);
+    let rgb = color::is_rgb_output(format, &AnimationOptions::default());
+    let scale_filter = color::scale_filter(scale_w, scale_h, rgb);
+    // Canvas PNG frames are sRGB
+    let color_args = color::output_args(ColorSpace::Srgb, rgb);
+
+    if alpha {
+        let mut args = alpha::video_args(format, scale_filter);
+        args.extend(color_args);
+        return args;
+    }
+    if let Some(encoder) = encoder {
+        let mut args = video_codec::video_args(encoder, format, scale_filter);
+        args.extend(color_args);
+        return args;
+    }
 
     let is_macos = std::env::consts::OS == "macos";
     let is_windows = std::env::consts::OS == "windows";
 
-    match format {
+    let mut args = match format {
         "webm" => vec![
             "-vf".to_string(),
             scale_filter,
@@ -150,16 +377,7 @@ fn get_encoder_args(format: &str, width: u32, height: u32, use_hw: bool) -> Vec<
                 ]
             }
         }
-        "gif" => {
-            // Optimized GIF - better color palette for quality
-            let gif_filter = format!("{},fps=15,split[s0][s1];[s0]palettegen=max_colors=256:stats_mode=diff[p];[s1][p]paletteuse=dither=floyd_steinberg", scale_filter);
-            vec![
-                "-vf".to_string(),
-                gif_filter,
-                "-loop".to_string(),
-                "0".to_string(),
-            ]
-        }
+        "gif" => gif::video_args(scale_filter, &GifOptions::default(), false),
         // MP4 - H.264 for speed + compatibility
         _ => {
             if use_hw && is_macos {
@@ -217,16 +435,53 @@ fn get_encoder_args(format: &str, width: u32, height: u32, use_hw: bool) -> Vec<
                     "avc1".to_string(),
                     "-movflags".to_string(),
                     "+faststart".to_string(),
-                    "-color_primaries".to_string(), "bt709".to_string(),
-                    "-color_trc".to_string(), "bt709".to_string(),
-                    "-colorspace".to_string(), "bt709".to_string(),
                 ]
             }
         }
+    };
+
+    args.extend(color_args);
+
+    // mkv takes the mp4 H.264 args minus the mp4-only muxer flags
+    if format == "mkv" {
+        video_codec::matroska_args(args)
+    } else {
+        args
+    }
+}
+
+// Validate the alpha / codec choice for `format` and pick the explicit encoder, if any.
+// Transparent exports always use the format's own alpha codec.
+fn resolve_video_encoder(format: &str, alpha: bool, codec: VideoCodec) -> Result<Option<&'static str>, String> {
+    if alpha {
+        alpha::validate(format)?;
+        if codec != VideoCodec::Auto {
+            return Err(format!("Transparent {} exports can't use {:?} video", format, codec));
+        }
+        return Ok(None);
     }
+    video_codec::resolve(format, codec)
 }
 
+/// Round-trip known colour swatches through the export args of each format, to check the
+/// colour pipeline of the bundled ffmpeg
 #[tauri::command]
+async fn run_color_self_test(color_space: Option<ColorSpace>) -> Result<Vec<color::ColorCheck>, String> {
+    let space = color_space.unwrap_or_default();
+    tauri::async_runtime::spawn_blocking(move || color::self_test(space))
+        .await
+        .map_err(|e| format!("Color self-test task failed: {e}"))?
+}
+
+/// Video codecs the bundled ffmpeg can encode, for the export codec picker
+#[tauri::command]
+fn get_video_codecs() -> Vec<VideoCodec> {
+    video_codec::available()
+}
+
+/// Encode the PNG frames in a `create_temp_workspace` folder; the workspace is released once done
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
 async fn encode_video(
     app: tauri::AppHandle,
     frames_dir: String,
@@ -236,17 +491,32 @@ async fn encode_video(
     width: Option<u32>,
     height: Option<u32>,
     use_hw: Option<bool>,
-) -> Result<(), String> {
+    collision_policy: Option<CollisionPolicy>,
+    alpha: Option<bool>,
+    video_codec: Option<VideoCodec>,
+) -> Result<String, String> {
     let fps = fps.unwrap_or(30);
     let format = format.unwrap_or_else(|| "mp4".to_string());
     let width = width.unwrap_or(1080);
     let height = height.unwrap_or(1080);
     let use_hw = use_hw.unwrap_or(true); // Default to hardware encoding
+    let alpha = alpha.unwrap_or(false);
+    let encoder = resolve_video_encoder(&format, alpha, video_codec.unwrap_or_default())?;
+    if animated_image::is_animated_image_format(&format) || format == "image_sequence" {
+        return Err(format!("{} is only available from the streaming export", format));
+    }
+
+    path_scope::ensure_in_scope(&app, std::path::Path::new(&frames_dir))?;
+    // Only managed workspaces, so the frames are always released with the job
+    temp_workspace::resolve_inside_root(std::path::Path::new(&frames_dir))?;
+    let requested_path = path_scope::ensure_output_path(&app, &output_path)?;
 
     // Use PNG frames (more compatible than WebP for raw streams)
-    let input_pattern = PathBuf::from(&frames_dir).join("frame_%05d.png");
+    let input_pattern = PathBuf::from(&frames_dir).join(sequence::FRAME_PATTERN);
     let input_pattern_str = input_pattern.to_string_lossy().to_string();
-    let output_path_owned = output_path.clone();
+    let collision_policy = collision_policy.unwrap_or_default();
+    output::resolve_output_path(&requested_path, collision_policy)?;
+    let temp_output_path = output::temp_output_path(&requested_path);
 
     let total_frames = std::fs::read_dir(&frames_dir)
         .map_err(|e| format!("Failed to read frames dir: {e}"))?
@@ -263,7 +533,46 @@ async fn encode_video(
 
     let total_duration = (total_frames as f32 / fps as f32).max(0.01);
 
-    let mut exit_code: Option<i32> = None;
+    // Frames are already on disk at this point - only the output needs room
+    let (codec, quality) = disk_space::streaming_codec(&format, use_hw, alpha, encoder);
+    let output_bytes = disk_space::estimate_output_bytes(&disk_space::EstimateInput {
+        codec,
+        quality,
+        bitrate_kbps: None,
+        width,
+        height,
+        fps,
+        total_frames: total_frames as u32,
+        has_audio: false,
+        gif_fps: gif::effective_fps(None),
+    });
+    let space = disk_space::check_space(&requested_path, output_bytes, 0);
+    if !space.sufficient {
+        return Err(space.warning.unwrap_or_else(|| "Not enough disk space".into()));
+    }
+    if let Some(warning) = &space.warning {
+        log::warn!("[Encode] {}", warning);
+    }
+
+    // The frames already are the sequence - zip them as-is, no re-encode
+    if format == "png_sequence" {
+        sequence::zip_frames(std::path::Path::new(&frames_dir), &temp_output_path)
+            .inspect_err(|_| output::discard_output(&temp_output_path))?;
+        let final_path = output::commit_output(&temp_output_path, &requested_path, collision_policy)
+            .inspect_err(|_| output::discard_output(&temp_output_path))?;
+        temp_workspace::release_path(std::path::Path::new(&frames_dir));
+        let _ = app.emit(
+            "encode-video-progress",
+            ProgressPayload {
+                progress: 1.0,
+                message: "done".into(),
+            },
+        );
+        return Ok(final_path.to_string_lossy().to_string());
+    }
+
+    // (exit code, signal) from the Terminated event; stays None if ffmpeg never reports back
+    let mut termination: Option<(Option<i32>, Option<i32>)> = None;
 
     let mut args = vec![
         "-y".to_string(),
@@ -276,8 +585,8 @@ async fn encode_video(
         input_pattern_str.clone(),
     ];
 
-    args.extend(get_encoder_args(&format, width, height, use_hw));
-    args.push(output_path_owned.clone());
+    args.extend(get_encoder_args(&format, width, height, use_hw, alpha, encoder));
+    args.push(temp_output_path.to_string_lossy().to_string());
 
     let args_refs: Vec<&str> = args.iter().map(|s| s.as_str()).collect();
 
@@ -336,18 +645,28 @@ async fn encode_video(
                 );
             }
             CommandEvent::Terminated(payload) => {
-                exit_code = payload.code;
+                termination = Some((payload.code, payload.signal));
             }
             _ => {}
         }
     }
 
-    if let Some(code) = exit_code {
-        if code != 0 {
-            return Err(format!("ffmpeg exited with code {}", code));
-        }
+    // Only a clean exit is a finished file; a signal kill leaves a truncated one behind
+    if termination != Some((Some(0), None)) {
+        output::discard_output(&temp_output_path);
+        return Err(match termination {
+            Some((Some(code), _)) if code != 0 => format!("ffmpeg exited with code {}", code),
+            Some((_, Some(signal))) => format!("ffmpeg was killed by signal {}", signal),
+            _ => "ffmpeg exited without reporting a status".to_string(),
+        });
     }
 
+    let final_path = output::commit_output(&temp_output_path, &requested_path, collision_policy)
+        .inspect_err(|_| output::discard_output(&temp_output_path))?;
+
+    // Frames are no longer needed once the video is in place
+    temp_workspace::release_path(std::path::Path::new(&frames_dir));
+
     let _ = app.emit(
         "encode-video-progress",
         ProgressPayload {
@@ -356,13 +675,261 @@ async fn encode_video(
         },
     );
 
-    Ok(())
+    Ok(final_path.to_string_lossy().to_string())
+}
+
+/// Export settings `estimate_export_size` needs, as sent by the export menu (snake_case keys)
+#[derive(Deserialize)]
+struct ExportSizeRequest {
+    output_path: String,
+    width: u32,
+    height: u32,
+    fps: u32,
+    total_frames: u32,
+    #[serde(default)]
+    format: Option<String>,
+    #[serde(default)]
+    use_hw: Option<bool>,
+    #[serde(default)]
+    has_audio: bool,
+    // PNG frames written to the temp dir first (frame-dir exports)
+    #[serde(default)]
+    frames_dir: bool,
+    #[serde(default)]
+    alpha: bool,
+    #[serde(default)]
+    video_codec: VideoCodec,
+    // Frame file type when `format` is image_sequence
+    #[serde(default)]
+    sequence_format: sequence::SequenceFormat,
+    // Requested GIF frame rate (`GifOptions.fps`)
+    #[serde(default)]
+    gif_fps: Option<u32>,
 }
 
+/// Estimate export size and check it against free disk space, so the UI can warn before rendering.
 #[tauri::command]
-async fn cleanup_temp_dir(dir_path: String) -> Result<(), String> {
-    std::fs::remove_dir_all(&dir_path).map_err(|e| format!("Failed to cleanup temp dir: {e}"))?;
-    Ok(())
+fn estimate_export_size(request: ExportSizeRequest) -> Result<disk_space::ExportEstimate, String> {
+    let format = request.format.unwrap_or_else(|| "mp4".to_string());
+    let encoder = resolve_video_encoder(&format, request.alpha, request.video_codec)?;
+    let (codec, quality) = if format == "image_sequence" {
+        (request.sequence_format.estimate_codec(), None)
+    } else {
+        disk_space::streaming_codec(&format, request.use_hw.unwrap_or(true), request.alpha, encoder)
+    };
+    let output_bytes = disk_space::estimate_output_bytes(&disk_space::EstimateInput {
+        codec,
+        quality,
+        bitrate_kbps: None,
+        width: request.width,
+        height: request.height,
+        fps: request.fps,
+        total_frames: request.total_frames,
+        has_audio: request.has_audio,
+        gif_fps: gif::effective_fps(request.gif_fps),
+    });
+    let temp_bytes = if request.frames_dir {
+        disk_space::estimate_frames_dir_bytes(request.width, request.height, request.total_frames)
+    } else {
+        0
+    };
+
+    Ok(disk_space::check_space(
+        std::path::Path::new(&request.output_path),
+        output_bytes,
+        temp_bytes,
+    ))
+}
+
+/// Probe a media file (container, duration, streams, codecs, rotation, color, audio layout).
+/// Cached by path + mtime so the timeline and export validation can call it freely.
+#[tauri::command]
+async fn probe_media(app: tauri::AppHandle, path: String) -> Result<probe::MediaInfo, String> {
+    let trimmed = path.strip_prefix("file://").unwrap_or(&path).to_string();
+    path_scope::ensure_plain_file_path(&trimmed)?;
+    let resolved = path_scope::ensure_in_scope(&app, std::path::Path::new(&trimmed))?;
+    tauri::async_runtime::spawn_blocking(move || probe::probe(&resolved))
+        .await
+        .map_err(|e| format!("Probe task failed: {e}"))?
+}
+
+/// Probe an imported clip and transcode a webview-friendly proxy when needed
+/// (HEVC, VFR, rotation, HDR). Progress is emitted as `import-media-progress`.
+#[tauri::command]
+async fn import_media(
+    app: tauri::AppHandle,
+    path: String,
+    force_proxy: Option<bool>,
+) -> Result<media_import::ImportedMedia, String> {
+    let trimmed = path.strip_prefix("file://").unwrap_or(&path).to_string();
+    path_scope::ensure_plain_file_path(&trimmed)?;
+    let resolved = path_scope::ensure_in_scope(&app, std::path::Path::new(&trimmed))?;
+    let force_proxy = force_proxy.unwrap_or(false);
+    tauri::async_runtime::spawn_blocking(move || media_import::import(&app, &resolved, force_proxy))
+        .await
+        .map_err(|e| format!("Import task failed: {e}"))?
+}
+
+/// Timeline filmstrip: `count` evenly spaced thumbnails `height` px tall, cached by content hash.
+/// Returns cached frames immediately; otherwise frames arrive as `filmstrip-frame` events.
+#[tauri::command]
+async fn generate_filmstrip(
+    app: tauri::AppHandle,
+    path: String,
+    count: u32,
+    height: u32,
+) -> Result<filmstrip::Filmstrip, String> {
+    let trimmed = path.strip_prefix("file://").unwrap_or(&path).to_string();
+    path_scope::ensure_plain_file_path(&trimmed)?;
+    let resolved = path_scope::ensure_in_scope(&app, std::path::Path::new(&trimmed))?;
+    tauri::async_runtime::spawn_blocking(move || filmstrip::generate(&app, &resolved, count, height))
+        .await
+        .map_err(|e| format!("Filmstrip task failed: {e}"))?
+}
+
+/// Waveform peaks (min/max/RMS per bucket) with coarser mip levels for zooming out.
+/// `buckets` is the peak count of the finest level across the whole file.
+#[tauri::command]
+async fn compute_waveform(
+    app: tauri::AppHandle,
+    path: String,
+    buckets: u32,
+) -> Result<waveform::Waveform, String> {
+    let trimmed = path.strip_prefix("file://").unwrap_or(&path).to_string();
+    path_scope::ensure_plain_file_path(&trimmed)?;
+    let resolved = path_scope::ensure_in_scope(&app, std::path::Path::new(&trimmed))?;
+    tauri::async_runtime::spawn_blocking(move || waveform::compute(&app, &resolved, buckets))
+        .await
+        .map_err(|e| format!("Waveform task failed: {e}"))?
+}
+
+/// Onset detection and tempo estimation for snapping clips and animations to the music.
+/// Returns beat timestamps, BPM and strong onsets (drops, hits); cached by content hash.
+#[tauri::command]
+async fn analyze_beats(app: tauri::AppHandle, path: String) -> Result<beats::BeatAnalysis, String> {
+    let trimmed = path.strip_prefix("file://").unwrap_or(&path).to_string();
+    path_scope::ensure_plain_file_path(&trimmed)?;
+    let resolved = path_scope::ensure_in_scope(&app, std::path::Path::new(&trimmed))?;
+    tauri::async_runtime::spawn_blocking(move || beats::analyze(&app, &resolved))
+        .await
+        .map_err(|e| format!("Beat analysis task failed: {e}"))?
+}
+
+/// Export just the timeline audio mix (wav, flac, m4a or opus) of exactly `duration_ms`.
+/// Progress is emitted as `audio-mix-progress`.
+#[tauri::command]
+async fn export_audio_mix(
+    app: tauri::AppHandle,
+    tracks: Vec<AudioTrack>,
+    duration_ms: u64,
+    format: String,
+    output_path: String,
+    audio_options: Option<AudioOptions>,
+    collision_policy: Option<CollisionPolicy>,
+) -> Result<audio_export::AudioMixExport, String> {
+    let requested_path = path_scope::ensure_output_path(&app, &output_path)?;
+    let audio_options = audio_options.unwrap_or_default();
+    let collision_policy = collision_policy.unwrap_or_default();
+    tauri::async_runtime::spawn_blocking(move || {
+        let tracks = audio::prepare_tracks(&app, tracks);
+        audio_export::export(
+            &app,
+            &tracks,
+            duration_ms,
+            &format,
+            &requested_path,
+            &audio_options,
+            collision_policy,
+        )
+    })
+    .await
+    .map_err(|e| format!("Audio export task failed: {e}"))?
+}
+
+/// Register a video clip with the frame server. Frames are decoded by a persistent ffmpeg
+/// process at the export `fps`, so `get_video_frame` returns exactly frame `round(t * fps)`.
+#[tauri::command]
+fn open_video_clip(app: tauri::AppHandle, clip_id: String, path: String, fps: u32) -> Result<(), String> {
+    let trimmed = path.strip_prefix("file://").unwrap_or(&path).to_string();
+    path_scope::ensure_plain_file_path(&trimmed)?;
+    let resolved = path_scope::ensure_in_scope(&app, std::path::Path::new(&trimmed))?;
+    frame_server::open(&clip_id, resolved, fps)
+}
+
+/// Raw RGBA frame (width * height * 4 bytes) of a clip at `time_ms` from the clip start.
+/// Sequential calls are served from a small look-ahead queue; seeks restart the decoder.
+#[tauri::command]
+async fn get_video_frame(
+    clip_id: String,
+    time_ms: f64,
+    width: u32,
+    height: u32,
+) -> Result<tauri::ipc::Response, String> {
+    let frame = tauri::async_runtime::spawn_blocking(move || {
+        frame_server::get_frame(&clip_id, time_ms, width, height)
+    })
+    .await
+    .map_err(|e| format!("Frame task failed: {e}"))??;
+    Ok(tauri::ipc::Response::new(frame.to_vec()))
+}
+
+/// Stop a clip's decoder process
+#[tauri::command]
+fn close_video_clip(clip_id: String) {
+    frame_server::close(&clip_id);
+}
+
+/// Create a per-job temp folder under the app-owned workspace root and add it to the fs scope
+#[tauri::command]
+fn create_temp_workspace(app: tauri::AppHandle, label: Option<String>) -> Result<temp_workspace::TempWorkspace, String> {
+    let workspace = temp_workspace::create(label.as_deref())?;
+    if let Err(e) = path_scope::allow_dir(&app, std::path::Path::new(&workspace.path)) {
+        let _ = temp_workspace::release(&workspace.job_id);
+        return Err(e);
+    }
+    Ok(workspace)
+}
+
+/// Delete a job folder created by `create_temp_workspace`
+#[tauri::command]
+fn release_temp_workspace(job_id: String) -> Result<(), String> {
+    temp_workspace::release(&job_id)
+}
+
+/// Remove a temp folder - only paths inside the workspace root (and the fs scope) are accepted
+#[tauri::command]
+async fn cleanup_temp_dir(app: tauri::AppHandle, dir_path: String) -> Result<(), String> {
+    let resolved = path_scope::ensure_in_scope(&app, std::path::Path::new(&dir_path))?;
+    temp_workspace::remove_inside_root(&resolved)
+}
+
+/// Native picker for an audio file; the pick is added to the fs scope so the audio commands accept it
+#[tauri::command]
+async fn pick_audio_file(app: tauri::AppHandle) -> Result<Option<String>, String> {
+    let Some(picked) = app
+        .dialog()
+        .file()
+        .add_filter("Audio", &["mp3", "wav", "aac", "m4a", "ogg"])
+        .blocking_pick_file()
+    else {
+        return Ok(None);
+    };
+    let path = picked.into_path().map_err(|e| format!("Unsupported file pick: {e}"))?;
+    let resolved = path_scope::allow_file(&app, &path)?;
+    Ok(Some(resolved.to_string_lossy().to_string()))
+}
+
+/// Create the Downloads/Liike export folder and add it to the fs scope
+#[tauri::command]
+fn get_export_folder(app: tauri::AppHandle) -> Result<String, String> {
+    let folder = app
+        .path()
+        .download_dir()
+        .map_err(|e| format!("Downloads folder unavailable: {e}"))?
+        .join("Liike");
+    std::fs::create_dir_all(&folder).map_err(|e| format!("Failed to create export folder: {e}"))?;
+    let resolved = path_scope::allow_dir(&app, &folder)?;
+    Ok(resolved.to_string_lossy().to_string())
 }
 
 #[tauri::command]
@@ -439,27 +1006,29 @@ fn validate_ffmpeg_path(path: &PathBuf) -> bool {
         .unwrap_or(false)
 }
 
-#[derive(serde::Deserialize, Clone, Debug)]
-pub struct AudioTrack {
-    pub path: String,
-    pub delay_ms: u64,
-}
-
 // Get encoder arguments for rawvideo input (streaming mode)
 fn get_streaming_encoder_args(
-    format: &str,
-    input_width: u32,
-    input_height: u32,
-    output_width: u32,
-    output_height: u32,
-    fps: u32,
-    use_hw: bool,
-    audio_tracks: Vec<AudioTrack>
-) -> Vec<String> {
+    session: &SessionInput,
+    spec: &OutputSpec,
+    audio: &OutputAudio,
+) -> Result<Vec<String>, String> {
+    let format = spec.format();
+    let (output_width, output_height) = spec.size(session);
+    let alpha = spec.alpha();
+    let encoder = spec.encoder()?;
+    let animation_options = spec.animation_options.clone().unwrap_or_default();
+    let gif_options = spec.gif_options.clone().unwrap_or_default();
+    let audio_tracks = audio.tracks;
+    let (loudness, audio_conform) = (audio.loudness, audio.conform);
+    let use_hw = session.use_hw();
+    let color_space = session.color_space;
     let scale_w = if output_width % 2 == 0 { output_width } else { output_width + 1 };
     let scale_h = if output_height % 2 == 0 { output_height } else { output_height + 1 };
-    // Use bicubic for good quality/speed balance
-    let scale_filter = format!("scale={}:{}:flags=bicubic", scale_w, scale_h);
+    // Use bicubic for good quality/speed balance; the scale also does the YUV conversion
+    let rgb = color::is_rgb_output(format, &animation_options);
+    let scale_filter = color::scale_filter(scale_w, scale_h, rgb);
+    // Every output gets the same set of tags, after its own encoder args
+    let color_args = color::output_args(color_space, rgb);
 
     let is_macos = std::env::consts::OS == "macos";
     let is_windows = std::env::consts::OS == "windows";
@@ -474,41 +1043,64 @@ fn get_streaming_encoder_args(
         "-pix_fmt".to_string(),
         "rgba".to_string(),
         "-s".to_string(),
-        format!("{}x{}", input_width, input_height),
+        format!("{}x{}", session.input_width, session.input_height),
         "-r".to_string(),
-        fps.to_string(),
-        "-i".to_string(),
-        "pipe:0".to_string(), // Read from stdin
+        session.fps.to_string(),
     ];
+    args.extend(color::input_args(color_space));
+    args.extend(["-i".to_string(), "pipe:0".to_string()]); // Read from stdin
 
     // Add all audio inputs
-    for track in &audio_tracks {
+    for track in audio_tracks {
         args.extend(vec!["-i".to_string(), track.path.clone()]);
     }
+    // No tracks but a conform length means the export needs a (silent) audio stream
+    let silence = audio_conform.filter(|_| audio_tracks.is_empty());
+    if let Some(conform) = silence {
+        args.extend(audio::silence_input_args(conform));
+    }
 
     // Map video from stdin
     args.extend(vec!["-map".to_string(), "0:v:0".to_string()]);
 
-    if !audio_tracks.is_empty() {
-        if audio_tracks.len() == 1 && audio_tracks[0].delay_ms == 0 {
-            // Single audio track with no delay - just map it
-            args.extend(vec!["-map".to_string(), "1:a:0".to_string()]);
+    // Audio inputs start at 1 (0 is the rawvideo pipe)
+    match silence {
+        Some(conform) => args.extend(audio::silence_map_args(1, conform)),
+        None => args.extend(audio::mix_args(audio_tracks, 1, &session.audio_options, loudness, audio_conform)),
+    }
+    if !audio_tracks.is_empty() || silence.is_some() {
+        args.extend(audio::encode_args(format, &session.audio_options)?);
+    }
+
+    if animated_image::is_animated_image_format(format) {
+        args.extend(animated_image::video_args(format, scale_filter, &animation_options, alpha));
+        args.extend(color_args);
+        if format == "avif" && alpha {
+            // The AVIF alpha plane is full range whatever the colour stream uses
+            args.extend(["-color_range:v:1".to_string(), "pc".to_string()]);
+        }
+        return Ok(args);
+    }
+    if format == "gif" {
+        // Budget mode streams a lossless master; the GIF itself is encoded on finish
+        if gif_options.max_bytes.is_some() {
+            args.extend(gif::master_args(scale_filter));
         } else {
-            // Multiple audio tracks or delay needed - mix them
-            let mut filter = String::new();
-            for (i, track) in audio_tracks.iter().enumerate() {
-                // Apply delay to each track: adelay=ms|ms (for stereo)
-                filter.push_str(&format!("[{}:a]adelay={}|{}[a{}];", i + 1, track.delay_ms, track.delay_ms, i + 1));
-            }
-            
-            for i in 0..audio_tracks.len() {
-                filter.push_str(&format!("[a{}]", i + 1));
-            }
-            
-            filter.push_str(&format!("amix=inputs={}:duration=first[aout]", audio_tracks.len()));
-            args.extend(vec!["-filter_complex".to_string(), filter]);
-            args.extend(vec!["-map".to_string(), "[aout]".to_string()]);
+            args.extend(gif::video_args(scale_filter, &gif_options, alpha));
         }
+        args.extend(color_args);
+        return Ok(args);
+    }
+    // A PNG sequence keeps whatever alpha the frames have, so it always takes the alpha path
+    if alpha || format == "png_sequence" {
+        args.extend(alpha::video_args(format, scale_filter));
+        args.extend(color_args);
+        return Ok(args);
+    }
+    if let Some(encoder) = encoder {
+        args.extend(video_codec::video_args(encoder, format, scale_filter));
+        args.extend(color_args);
+        return Ok(args);
     }
 
     // Output encoding args based on format - PRIORITIZE QUALITY
@@ -541,15 +1133,6 @@ fn get_streaming_encoder_args(
                 "25".to_string(), // Better compression with lookahead
             ]);
         }
-        "gif" => {
-            let gif_filter = format!("{},fps=15,split[s0][s1];[s0]palettegen=max_colors=256:stats_mode=diff[p];[s1][p]paletteuse=dither=floyd_steinberg", scale_filter);
-            args.extend(vec![
-                "-vf".to_string(),
-                gif_filter,
-                "-loop".to_string(),
-                "0".to_string(),
-            ]);
-        }
         _ => {
             // MP4/MOV - H.264 for speed + compatibility
             if use_hw && is_macos {
@@ -566,10 +1149,6 @@ fn get_streaming_encoder_args(
                     "avc1".to_string(),
                     "-movflags".to_string(),
                     "+faststart".to_string(),
-                    // Color space for accurate reproduction
-                    "-color_primaries".to_string(), "bt709".to_string(),
-                    "-color_trc".to_string(), "bt709".to_string(),
-                    "-colorspace".to_string(), "bt709".to_string(),
                 ]);
             } else if use_hw && is_windows {
                 args.extend(vec![
@@ -593,9 +1172,6 @@ fn get_streaming_encoder_args(
                     "avc1".to_string(),
                     "-movflags".to_string(),
                     "+faststart".to_string(),
-                    "-color_primaries".to_string(), "bt709".to_string(),
-                    "-color_trc".to_string(), "bt709".to_string(),
-                    "-colorspace".to_string(), "bt709".to_string(),
                 ]);
             } else {
                 // Software encoding fallback - QUALITY FOCUSED
@@ -616,67 +1192,215 @@ fn get_streaming_encoder_args(
                     "+faststart".to_string(),
                     "-threads".to_string(),
                     "0".to_string(), // Use all available threads
-                    "-color_primaries".to_string(), "bt709".to_string(),
-                    "-color_trc".to_string(), "bt709".to_string(),
-                    "-colorspace".to_string(), "bt709".to_string(),
                 ]);
             }
         }
     }
-    
-    args
+    args.extend(color_args);
+
+    // mkv takes the mp4 H.264 args minus the mp4-only muxer flags
+    if format == "mkv" {
+        return Ok(video_codec::matroska_args(args));
+    }
+    Ok(args)
 }
 
-/// Start a streaming encoder session - spawns ffmpeg and returns encoder ID
+/// Start a streaming encoder session - spawns one encoder per output and returns the encoder ID.
+/// Every output is fed from the same frames, so mp4 + webm + gif need only one capture of the
+/// timeline; the first output is the one the top-level report fields describe.
 #[tauri::command]
 fn start_streaming_encode(
-    output_path: String,
-    width: u32,
-    height: u32,
-    fps: u32,
-    total_frames: u32,
-    format: Option<String>,
-    use_hw: Option<bool>,
-    audio_tracks: Option<Vec<AudioTrack>>,
-    input_width: Option<u32>,
-    input_height: Option<u32>,
+    app: tauri::AppHandle,
+    session: SessionInput,
+    outputs: Vec<OutputSpec>,
 ) -> Result<String, String> {
-    let format = format.unwrap_or_else(|| "mp4".to_string());
-    let use_hw = use_hw.unwrap_or(true);
-    let input_width = input_width.unwrap_or(width);
-    let input_height = input_height.unwrap_or(height);
-    let mut valid_audio_tracks = Vec::new();
-
-    if let Some(tracks) = audio_tracks {
-        for mut t in tracks {
-            let trimmed = t.path.strip_prefix("file://").unwrap_or(&t.path).to_string();
-            match std::fs::metadata(&trimmed) {
-                Ok(_) => {
-                    t.path = trimmed;
-                    valid_audio_tracks.push(t);
-                }
-                Err(e) => {
-                    log::warn!("[StreamEncode] Audio path not accessible, skipping: {} ({})", trimmed, e);
+    let mut outputs = outputs.into_iter();
+    let primary = outputs.next().ok_or("No outputs to encode")?;
+    let mut specs = vec![primary.clone()];
+    for spec in outputs {
+        // Two outputs racing for one file would leave whichever renamed last
+        if specs.iter().any(|other| other.output_path == spec.output_path) {
+            return Err(format!("Two outputs write to the same file: {}", spec.output_path));
+        }
+        specs.push(spec.with_defaults(&primary));
+    }
+
+    // Probe the audio once for the whole session, and only if some output can carry it
+    let mut input = session;
+    let tracks = std::mem::take(&mut input.audio_tracks);
+    if specs.iter().any(|spec| audio::format_supports_audio(spec.format())) {
+        input.audio_tracks = audio::prepare_tracks(&app, tracks);
+    } else if !tracks.is_empty() {
+        log::info!("[StreamEncode] No output can carry audio, ignoring audio tracks");
+    }
+
+    let multiple = specs.len() > 1;
+    let mut started: Vec<StreamingEncoder> = Vec::new();
+    // Outputs usually share a volume, so each disk check counts what the earlier ones need
+    let mut reserved_bytes = 0;
+    for spec in specs {
+        let label = spec.output_path.clone();
+        match start_output(&app, &input, spec, &mut reserved_bytes) {
+            Ok(encoder) => started.push(encoder),
+            Err(e) => {
+                for encoder in &mut started {
+                    abort_output(encoder);
                 }
+                return Err(if multiple { format!("{}: {}", label, e) } else { e });
             }
         }
     }
 
-    let ffmpeg_path = get_ffmpeg_path()?;
-    if !validate_ffmpeg_path(&ffmpeg_path) {
-        return Err("ffmpeg binary not found or not runnable".into());
+    register_session(StreamingSession {
+        input_width: input.input_width,
+        input_height: input.input_height,
+        total_frames: input.total_frames,
+        current_frame: Arc::new(Mutex::new(0)),
+        outputs: started,
+    })
+}
+
+// Validate one output and spawn its encoder
+fn start_output(
+    app: &tauri::AppHandle,
+    input: &SessionInput,
+    spec: OutputSpec,
+    reserved_bytes: &mut u64,
+) -> Result<StreamingEncoder, String> {
+    let format = spec.format().to_string();
+    let (width, height) = spec.size(input);
+    let (fps, total_frames, use_hw) = (input.fps, input.total_frames, input.use_hw());
+    let alpha = spec.alpha();
+    let encoder = spec.encoder()?;
+    color::validate(&format, input.color_space)?;
+    if animated_image::is_animated_image_format(&format) {
+        animated_image::validate(&format)?;
+    }
+    let gif_options = spec.gif_options.clone().unwrap_or_default();
+    let gif_budget = (format == "gif" && gif_options.max_bytes.is_some()).then(|| gif_options.clone());
+    if gif_options.max_bytes == Some(0) {
+        return Err("GIF size budget must be greater than 0".into());
+    }
+    let sequence_options = spec.sequence_options.clone().unwrap_or_default();
+    let is_image_sequence = format == "image_sequence";
+    let sequence_pattern = FramePattern::for_options(&sequence_options)?;
+    let collision_policy = match input.collision_policy {
+        // Never replace an existing folder wholesale - it may hold files this export didn't write
+        CollisionPolicy::Overwrite if is_image_sequence && !sequence_options.zip => CollisionPolicy::Increment,
+        policy => policy,
+    };
+    let requested_path = path_scope::ensure_output_path(app, &spec.output_path)?;
+    // Fail fast on an existing file instead of after the whole render
+    output::resolve_output_path(&requested_path, collision_policy)?;
+    let temp_output_path = output::temp_output_path(&requested_path);
+    let (input_width, input_height) = (input.input_width, input.input_height);
+    let audio_options = &input.audio_options;
+    let valid_audio_tracks = if !input.audio_tracks.is_empty() && !audio::format_supports_audio(&format) {
+        log::info!("[StreamEncode] {} cannot carry audio, ignoring audio tracks", format);
+        Vec::new()
+    } else {
+        input.audio_tracks.clone()
+    };
+
+    // Delivery targets like App Store previews need an audio stream even without tracks
+    let has_audio = !valid_audio_tracks.is_empty()
+        || (audio_options.silent_track && audio::format_supports_audio(&format));
+
+    // Reject codec/container mismatches (e.g. PCM in mp4) before measuring or spawning anything
+    if has_audio {
+        audio::encode_args(&format, audio_options)?;
     }
-    let mut args = get_streaming_encoder_args(
-        &format,
-        input_width,
-        input_height,
+
+    // Refuse up front rather than failing mid-export with a broken pipe in send_frame
+    let (codec, quality) = if is_image_sequence {
+        (sequence_options.format.estimate_codec(), None)
+    } else {
+        disk_space::streaming_codec(&format, use_hw, alpha, encoder)
+    };
+    let output_bytes = disk_space::estimate_output_bytes(&disk_space::EstimateInput {
+        codec,
+        quality,
+        bitrate_kbps: None,
         width,
         height,
         fps,
-        use_hw,
-        valid_audio_tracks.clone(),
-    );
-    args.push(output_path.clone());
+        total_frames,
+        has_audio,
+        gif_fps: gif::effective_fps(gif_options.fps),
+    });
+    // Sequence frames and the GIF master sit in a temp folder until finish
+    let temp_bytes = if format == "png_sequence" || (is_image_sequence && sequence_options.zip) {
+        output_bytes
+    } else if gif_budget.is_some() {
+        disk_space::estimate_frames_dir_bytes(width, height, total_frames)
+    } else {
+        0
+    };
+    let space = disk_space::check_space(&requested_path, output_bytes + *reserved_bytes, temp_bytes);
+    if !space.sufficient {
+        return Err(space.warning.unwrap_or_else(|| "Not enough disk space".into()));
+    }
+    if let Some(warning) = &space.warning {
+        log::warn!("[StreamEncode] {}", warning);
+    }
+    *reserved_bytes += output_bytes + temp_bytes;
+
+    if is_image_sequence {
+        let size = FrameSize {
+            input_width,
+            input_height,
+            output_width: width,
+            output_height: height,
+        };
+        return start_image_sequence(
+            requested_path,
+            temp_output_path,
+            collision_policy,
+            sequence_options,
+            sequence_pattern,
+            size,
+            total_frames,
+        );
+    }
+
+    let ffmpeg_path = get_ffmpeg_path()?;
+    if !validate_ffmpeg_path(&ffmpeg_path) {
+        return Err("ffmpeg binary not found or not runnable".into());
+    }
+    // Audio is padded/trimmed to exactly total_frames / fps
+    let audio_conform = has_audio
+        .then(|| Conform::new(&format, audio_options, total_frames, fps));
+    // Pass one of the loudnorm stage runs over the audio inputs before any frames arrive
+    let loudness = match audio_options.loudness_target {
+        Some(target) => audio::measure_loudness(&valid_audio_tracks, audio_options, audio_conform.as_ref(), target)
+            .unwrap_or_else(|e| {
+                log::warn!("[StreamEncode] {}, exporting without loudness normalization", e);
+                None
+            }),
+        None => None,
+    };
+    if let Some(measurement) = &loudness {
+        log::info!("[StreamEncode] Measured loudness: {:?}", measurement);
+    }
+
+    let output_audio = OutputAudio {
+        tracks: &valid_audio_tracks,
+        loudness: loudness.as_ref(),
+        conform: audio_conform.as_ref(),
+    };
+    let mut args = get_streaming_encoder_args(input, &spec, &output_audio)?;
+    let finalize = if format == "png_sequence" {
+        let workspace = temp_workspace::create(Some(&format))?;
+        args.push(PathBuf::from(&workspace.path).join(sequence::FRAME_PATTERN).to_string_lossy().to_string());
+        Finalize::Zip(workspace, FramePattern::default())
+    } else if let Some(options) = gif_budget {
+        let workspace = temp_workspace::create(Some(&format))?;
+        args.push(PathBuf::from(&workspace.path).join(gif::MASTER_FILE).to_string_lossy().to_string());
+        Finalize::GifBudget(workspace, options, alpha)
+    } else {
+        args.push(temp_output_path.to_string_lossy().to_string());
+        Finalize::Direct
+    };
 
     let log_path = std::env::temp_dir().join("liike_ffmpeg.log");
     append_ffmpeg_log(&log_path, &format!("[StreamEncode] Starting ffmpeg with audio={:?}: {:?} {:?}", valid_audio_tracks, ffmpeg_path, args));
@@ -688,9 +1412,12 @@ fn start_streaming_encode(
         .stdout(Stdio::null())
         .stderr(Stdio::piped())
         .spawn()
-        .map_err(|e| format!("Failed to spawn ffmpeg: {e}"))?;
+        .map_err(|e| {
+            release_work_dir(finalize.work_dir());
+            format!("Failed to spawn ffmpeg: {e}")
+        })?;
 
-    let mut stdin = process
+    let stdin = process
         .stdin
         .take()
         .ok_or("Failed to get ffmpeg stdin")?;
@@ -699,24 +1426,26 @@ fn start_streaming_encode(
     let last_error: Arc<Mutex<Option<String>>> = Arc::new(Mutex::new(None));
     let stderr_error = last_error.clone();
     let stderr_log_path = log_path.clone();
+    // Several outputs share the log, so each line says which one it came from
+    let stderr_tag = format.clone();
 
     // Spawn a thread to drain stderr and prevent deadlock, and log messages
     thread::spawn(move || {
         use std::io::{BufRead, BufReader};
         let reader = BufReader::new(stderr);
         for line in reader.lines() {
-            if let Ok(l) = line {
-                *stderr_error.lock().unwrap() = Some(l.clone());
-                append_ffmpeg_log(&stderr_log_path, &format!("[FFmpeg] {}", l));
-                log::info!("[FFmpeg] {}", l);
-            }
+            // Skip undecodable lines but keep draining, or ffmpeg blocks on a full pipe
+            let Ok(l) = line else { continue };
+            *stderr_error.lock().unwrap() = Some(l.clone());
+            append_ffmpeg_log(&stderr_log_path, &format!("[FFmpeg {}] {}", stderr_tag, l));
+            log::info!("[FFmpeg {}] {}", stderr_tag, l);
         }
     });
 
     // Create a bounded channel (size 60) for frame streaming
     // Increased to 60 (approx 2s at 30fps) to decouple frontend generation from encoding speed.
     // This prevents the frontend from stalling if ffmpeg has micro-stutters.
-    let (tx, rx): (SyncSender<Option<Vec<u8>>>, Receiver<Option<Vec<u8>>>) = sync_channel(60);
+    let (tx, rx) = sync_channel::<Option<Vec<u8>>>(60);
     
     // Spawn worker thread for writing frames
     let worker_error = last_error.clone();
@@ -746,57 +1475,120 @@ fn start_streaming_encode(
         Ok(())
     });
 
-    let encoder_id = format!("encoder_{}", std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap()
-        .as_millis());
-
-    let encoder = StreamingEncoder {
+    Ok(StreamingEncoder {
         process: Some(process),
         sender: Some(tx),
         worker_thread: Some(worker_thread),
-        input_width,
-        input_height,
+        format,
         total_frames,
         current_frame: Arc::new(Mutex::new(0)),
+        error: None,
         last_error,
         log_path,
+        output_path: requested_path,
+        temp_output_path,
+        collision_policy,
+        loudness,
+        audio_conform,
+        finalize,
+    })
+}
+
+// Image sequences are encoded in Rust: no ffmpeg process, a writer pool takes the frames
+fn start_image_sequence(
+    requested_path: PathBuf,
+    temp_output_path: PathBuf,
+    collision_policy: CollisionPolicy,
+    options: SequenceOptions,
+    pattern: FramePattern,
+    size: FrameSize,
+    total_frames: u32,
+) -> Result<StreamingEncoder, String> {
+    // Zipped sequences collect in a job folder; plain ones go straight into a hidden
+    // sibling folder that is renamed into place on finish
+    let (dir, finalize) = if options.zip {
+        let workspace = temp_workspace::create(Some("image_sequence"))?;
+        (PathBuf::from(&workspace.path), Finalize::Zip(workspace, pattern.clone()))
+    } else {
+        std::fs::create_dir(&temp_output_path).map_err(|e| format!("Failed to create sequence folder: {e}"))?;
+        (temp_output_path.clone(), Finalize::Direct)
     };
+    log::info!("[StreamEncode] Writing {:?} sequence into {}", options.format, dir.display());
+
+    let last_error: Arc<Mutex<Option<String>>> = Arc::new(Mutex::new(None));
+    let (tx, rx) = sync_channel::<Option<Vec<u8>>>(60);
+    let worker_thread = sequence::spawn_writer(rx, dir, pattern, options, size, last_error.clone());
+
+    Ok(StreamingEncoder {
+        process: None,
+        sender: Some(tx),
+        worker_thread: Some(worker_thread),
+        format: "image_sequence".to_string(),
+        total_frames,
+        current_frame: Arc::new(Mutex::new(0)),
+        error: None,
+        last_error,
+        log_path: std::env::temp_dir().join("liike_ffmpeg.log"),
+        output_path: requested_path,
+        temp_output_path,
+        collision_policy,
+        loudness: None,
+        audio_conform: None,
+        finalize,
+    })
+}
+
+fn register_session(session: StreamingSession) -> Result<String, String> {
+    let encoder_id = format!("encoder_{}", std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis());
 
     ENCODERS
         .lock()
         .map_err(|e| format!("Failed to lock encoders: {e}"))?
-        .insert(encoder_id.clone(), encoder);
+        .insert(encoder_id.clone(), session);
 
     log::info!("[StreamEncode] Encoder started: {}", encoder_id);
     Ok(encoder_id)
 }
 
-/// Send a single frame to the streaming encoder
+/// Send a single frame to the streaming encoder; every output of the session gets it.
+/// An output that fails stops taking frames while the others carry on - the call only
+/// errors once no output is left.
 /// frame_data: Base64-encoded RGBA pixel data
 #[tauri::command]
 fn send_frame(encoder_id: String, frame_data: Vec<u8>) -> Result<f32, String> {
-    // 1. Lock: Short critical section just to get the sender
-    let (sender, counter, total_frames, expected_size, last_error, log_path) = {
+    // 1. Lock: Short critical section just to get the senders
+    let (senders, counter, total_frames, expected_size) = {
         let encoders = ENCODERS
             .lock()
             .map_err(|e| format!("Failed to lock encoders: {e}"))?;
 
-        let encoder = encoders
+        let session = encoders
             .get(&encoder_id)
             .ok_or_else(|| format!("Encoder not found: {}", encoder_id))?;
 
-        let expected_size = (encoder.input_width * encoder.input_height * 4) as usize;
-        let sender = encoder.sender.clone().ok_or("Encoder closed")?;
-        
-        (
-            sender,
-            encoder.current_frame.clone(),
-            encoder.total_frames,
-            expected_size,
-            encoder.last_error.clone(),
-            encoder.log_path.clone(),
-        )
+        let expected_size = (session.input_width * session.input_height * 4) as usize;
+        let senders: Vec<_> = session
+            .outputs
+            .iter()
+            .enumerate()
+            .filter_map(|(index, encoder)| {
+                Some((
+                    index,
+                    encoder.sender.clone()?,
+                    encoder.current_frame.clone(),
+                    encoder.last_error.clone(),
+                    encoder.log_path.clone(),
+                ))
+            })
+            .collect();
+        if senders.is_empty() {
+            return Err("Encoder closed".into());
+        }
+
+        (senders, session.current_frame.clone(), session.total_frames, expected_size)
     };
 
     // 2. Validation (outside lock)
@@ -804,18 +1596,48 @@ fn send_frame(encoder_id: String, frame_data: Vec<u8>) -> Result<f32, String> {
         return Err(format!("Invalid frame data size: expected {}, got {}", expected_size, frame_data.len()));
     }
 
-    // 3. Send: Push to channel (may block if buffer full, but releases Mutex first!)
-    // This allows other interactions to proceed.
-    sender.send(Some(frame_data)).map_err(|_| {
-        let err = last_error.lock().unwrap();
-        if let Some(msg) = err.as_ref() {
-            format!("Failed to send frame to worker thread: {} (log: {})", msg, log_path.display())
-        } else {
-            format!("Failed to send frame to worker thread (log: {})", log_path.display())
+    // 3. Send: Push to each channel (may block if buffer full, but releases Mutex first!)
+    // This allows other interactions to proceed. The last output takes the frame itself.
+    let last = senders.len() - 1;
+    let mut frame_data = Some(frame_data);
+    let mut failed = Vec::new();
+    for (i, (index, sender, frames, last_error, log_path)) in senders.into_iter().enumerate() {
+        let frame = if i == last { frame_data.take() } else { frame_data.clone() };
+        match sender.send(frame) {
+            Ok(()) => *frames.lock().unwrap() += 1,
+            Err(_) => {
+                let err = last_error.lock().unwrap();
+                let msg = if let Some(msg) = err.as_ref() {
+                    format!("Failed to send frame to worker thread: {} (log: {})", msg, log_path.display())
+                } else {
+                    format!("Failed to send frame to worker thread (log: {})", log_path.display())
+                };
+                failed.push((index, msg));
+            }
         }
-    })?;
+    }
 
-    // 4. Update progress
+    // 4. Retire failed outputs, keeping their error for the status and finish reports
+    if !failed.is_empty() {
+        let mut encoders = ENCODERS
+            .lock()
+            .map_err(|e| format!("Failed to lock encoders: {e}"))?;
+        let session = encoders
+            .get_mut(&encoder_id)
+            .ok_or_else(|| format!("Encoder not found: {}", encoder_id))?;
+        for (index, msg) in failed {
+            let encoder = &mut session.outputs[index];
+            log::warn!("[StreamEncode] {} output stopped: {}", encoder.format, msg);
+            encoder.sender = None;
+            encoder.error = Some(msg);
+        }
+        if session.outputs.iter().all(|encoder| encoder.sender.is_none()) {
+            let errors: Vec<String> = session.outputs.iter().filter_map(|encoder| encoder.error.clone()).collect();
+            return Err(errors.join("; "));
+        }
+    }
+
+    // 5. Update progress
     let mut current = counter.lock().unwrap();
     *current += 1;
     let progress = *current as f32 / total_frames as f32;
@@ -823,17 +1645,43 @@ fn send_frame(encoder_id: String, frame_data: Vec<u8>) -> Result<f32, String> {
     Ok(progress)
 }
 
-/// Finish the streaming encode and cleanup
+/// Per-output progress and errors of a streaming session, in the order the outputs were given
 #[tauri::command]
-fn finish_streaming_encode(encoder_id: String) -> Result<(), String> {
-    let mut encoders = ENCODERS
+fn get_streaming_outputs(encoder_id: String) -> Result<Vec<OutputStatus>, String> {
+    let encoders = ENCODERS
         .lock()
         .map_err(|e| format!("Failed to lock encoders: {e}"))?;
 
-    let mut encoder = encoders
-        .remove(&encoder_id)
+    let session = encoders
+        .get(&encoder_id)
         .ok_or_else(|| format!("Encoder not found: {}", encoder_id))?;
 
+    Ok(session
+        .outputs
+        .iter()
+        .map(|encoder| {
+            let frames = *encoder.current_frame.lock().unwrap();
+            OutputStatus {
+                format: encoder.format.clone(),
+                path: encoder.output_path.to_string_lossy().to_string(),
+                frames,
+                progress: frames as f32 / encoder.total_frames.max(1) as f32,
+                error: encoder.error.clone(),
+            }
+        })
+        .collect())
+}
+
+fn release_work_dir(workspace: Option<&temp_workspace::TempWorkspace>) {
+    if let Some(workspace) = workspace {
+        if let Err(e) = temp_workspace::release(&workspace.job_id) {
+            log::warn!("[StreamEncode] {}", e);
+        }
+    }
+}
+
+// Flush remaining frames, close stdin and wait for ffmpeg to exit
+fn wait_for_encoder(encoder: &mut StreamingEncoder) -> Result<(), String> {
     // 1. Signal EOF to worker thread
     if let Some(sender) = encoder.sender.take() {
         let _ = sender.send(None);
@@ -859,11 +1707,167 @@ fn finish_streaming_encode(encoder_id: String) -> Result<(), String> {
         }
     }
 
-    let frames = *encoder.current_frame.lock().unwrap();
-    log::info!("[StreamEncode] Encoder finished: {} ({} frames)", encoder_id, frames);
     Ok(())
 }
 
+/// Finish the streaming encode and cleanup.
+/// Moves each output's temp file into place and reports every output: its final path (may
+/// differ from the requested one with the `increment` collision policy) or why it failed,
+/// along with the measured loudness, if normalized. Errors only when every output failed.
+#[tauri::command]
+fn finish_streaming_encode(app: tauri::AppHandle, encoder_id: String) -> Result<StreamingEncodeResult, String> {
+    let mut session = ENCODERS
+        .lock()
+        .map_err(|e| format!("Failed to lock encoders: {e}"))?
+        .remove(&encoder_id)
+        .ok_or_else(|| format!("Encoder not found: {}", encoder_id))?;
+
+    // Close every input first so the outputs flush side by side rather than one after another
+    for encoder in &mut session.outputs {
+        if let Some(sender) = encoder.sender.take() {
+            let _ = sender.send(None);
+        }
+    }
+
+    let outputs: Vec<OutputResult> = session
+        .outputs
+        .iter_mut()
+        .map(|encoder| {
+            finish_output(&app, encoder).unwrap_or_else(|e| {
+                // The error that stopped the output mid-stream says more than the broken pipe
+                let error = encoder.error.clone().unwrap_or(e);
+                log::error!("[StreamEncode] {} output failed: {}", encoder.format, error);
+                OutputResult {
+                    format: encoder.format.clone(),
+                    path: None,
+                    error: Some(error),
+                    frames: *encoder.current_frame.lock().unwrap(),
+                    loudness: None,
+                    audio_conformance: None,
+                    gif_attempts: None,
+                    warnings: Vec::new(),
+                }
+            })
+        })
+        .collect();
+
+    let Some(first) = outputs.iter().find(|output| output.path.is_some()).cloned() else {
+        let errors: Vec<String> = outputs.into_iter().filter_map(|output| output.error).collect();
+        return Err(errors.join("; "));
+    };
+    log::info!("[StreamEncode] Encoder finished: {}", encoder_id);
+
+    let multiple = outputs.len() > 1;
+    let warnings = outputs
+        .iter()
+        .flat_map(|output| {
+            output.warnings.iter().map(move |warning| {
+                if multiple {
+                    format!("{}: {}", output.format, warning)
+                } else {
+                    warning.clone()
+                }
+            })
+        })
+        .collect();
+
+    Ok(StreamingEncodeResult {
+        path: first.path.unwrap_or_default(),
+        frames: first.frames,
+        loudness: first.loudness,
+        audio_conformance: first.audio_conformance,
+        gif_attempts: first.gif_attempts,
+        warnings,
+        outputs,
+    })
+}
+
+// Wait for one output, finalize it and move it into place
+fn finish_output(app: &tauri::AppHandle, encoder: &mut StreamingEncoder) -> Result<OutputResult, String> {
+    // Never leave a half-written file behind - the previous output (if any) stays untouched
+    let written = wait_for_encoder(encoder).and_then(|_| match &encoder.finalize {
+        Finalize::Direct => Ok(None),
+        Finalize::Zip(workspace, pattern) => {
+            sequence::zip_sequence(std::path::Path::new(&workspace.path), &encoder.temp_output_path, pattern)
+                .map(|_| None)
+        }
+        Finalize::GifBudget(workspace, options, alpha) => gif::fit_to_budget(
+            &PathBuf::from(&workspace.path).join(gif::MASTER_FILE),
+            &encoder.temp_output_path,
+            options,
+            *alpha,
+            |attempt| {
+                let _ = app.emit("gif-size-attempt", attempt);
+            },
+        )
+        .map(Some),
+    });
+    release_work_dir(encoder.finalize.work_dir());
+    let gif_attempts = match written {
+        Ok(attempts) => attempts,
+        Err(e) => {
+            output::discard_output(&encoder.temp_output_path);
+            return Err(e);
+        }
+    };
+
+    let final_path = output::commit_output(
+        &encoder.temp_output_path,
+        &encoder.output_path,
+        encoder.collision_policy,
+    )
+    .inspect_err(|_| output::discard_output(&encoder.temp_output_path))?;
+
+    // Check the conform guarantee on the file that was actually written
+    let mut warnings = Vec::new();
+    let audio_conformance = encoder.audio_conform.as_ref().and_then(|conform| {
+        audio::verify_conformance(&final_path, conform)
+            .inspect_err(|e| log::warn!("[StreamEncode] Audio length check failed: {}", e))
+            .ok()
+    });
+    if let Some(c) = audio_conformance.as_ref().filter(|c| !c.within_tolerance) {
+        let warning = format!(
+            "Audio is {:.0} ms long but the video is {:.0} ms; it may drift out of sync",
+            c.audio_ms, c.expected_ms
+        );
+        log::warn!("[StreamEncode] {}", warning);
+        warnings.push(warning);
+    }
+
+    let frames = *encoder.current_frame.lock().unwrap();
+    log::info!("[StreamEncode] Output finished: {} ({} frames) -> {}", encoder.format, frames, final_path.display());
+
+    Ok(OutputResult {
+        format: encoder.format.clone(),
+        path: Some(final_path.to_string_lossy().to_string()),
+        error: None,
+        frames,
+        loudness: encoder.loudness.take(),
+        audio_conformance,
+        gif_attempts,
+        warnings,
+    })
+}
+
+// Stop an output without finishing it: kill ffmpeg and remove everything it wrote
+fn abort_output(encoder: &mut StreamingEncoder) {
+    // Drop sender to signal worker thread
+    encoder.sender = None;
+
+    // Wait for worker (optional, but good for cleanup)
+    if let Some(worker) = encoder.worker_thread.take() {
+        let _ = worker.join();
+    }
+
+    // Kill the ffmpeg process
+    if let Some(mut process) = encoder.process.take() {
+        let _ = process.kill();
+        let _ = process.wait();
+    }
+    output::discard_output(&encoder.temp_output_path);
+    release_work_dir(encoder.finalize.work_dir());
+}
+
 /// Cancel a streaming encode session
 #[tauri::command]
 fn cancel_streaming_encode(encoder_id: String) -> Result<(), String> {
@@ -871,20 +1875,11 @@ fn cancel_streaming_encode(encoder_id: String) -> Result<(), String> {
         .lock()
         .map_err(|e| format!("Failed to lock encoders: {e}"))?;
 
-    if let Some(mut encoder) = encoders.remove(&encoder_id) {
-        // Drop sender to signal worker thread
-        encoder.sender = None;
-        
-        // Wait for worker (optional, but good for cleanup)
-        if let Some(worker) = encoder.worker_thread.take() {
-            let _ = worker.join();
+    if let Some(mut session) = encoders.remove(&encoder_id) {
+        for encoder in &mut session.outputs {
+            abort_output(encoder);
         }
 
-        // Kill the ffmpeg process
-        if let Some(mut process) = encoder.process.take() {
-            let _ = process.kill();
-        }
-        
         log::info!("[StreamEncode] Encoder cancelled: {}", encoder_id);
     }
 
@@ -899,13 +1894,44 @@ pub fn run() {
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_dialog::init())
+        .setup(|app| {
+            // Proxies and other cached media are fed back into the media commands
+            match app.path().app_cache_dir() {
+                Ok(dir) => {
+                    if let Err(e) = path_scope::allow_dir(app.handle(), &dir) {
+                        log::warn!("[Scope] {}", e);
+                    }
+                }
+                Err(e) => log::warn!("[Scope] App cache folder unavailable: {e}"),
+            }
+            // Clear temp folders left behind by crashed renders without delaying startup
+            thread::spawn(temp_workspace::sweep_stale);
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             encode_video,
             copy_file,
+            estimate_export_size,
+            get_video_codecs,
+            run_color_self_test,
+            probe_media,
+            import_media,
+            generate_filmstrip,
+            compute_waveform,
+            analyze_beats,
+            export_audio_mix,
+            open_video_clip,
+            get_video_frame,
+            close_video_clip,
+            create_temp_workspace,
+            release_temp_workspace,
             cleanup_temp_dir,
+            pick_audio_file,
+            get_export_folder,
             precise_sleep,
             start_streaming_encode,
             send_frame,
+            get_streaming_outputs,
             finish_streaming_encode,
             cancel_streaming_encode
         ])
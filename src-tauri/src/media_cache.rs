@@ -0,0 +1,129 @@
+use sha2::{Digest, Sha256};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use tauri::Manager;
+
+// Hashing whole multi-GB recordings on every open is too slow; size + head + tail is
+// enough to tell files apart and survives renames/moves of the same content.
+const SAMPLE_BYTES: u64 = 1024 * 1024;
+
+// Tells apart temp files of writes that start within the same clock tick
+static WRITE_SEQ: AtomicU64 = AtomicU64::new(0);
+
+/// Content key for a media file, stable across renames and app restarts
+pub fn content_key(path: &Path) -> Result<String, String> {
+    let mut file = std::fs::File::open(path).map_err(|e| format!("Failed to open {}: {e}", path.display()))?;
+    let len = file
+        .metadata()
+        .map_err(|e| format!("Failed to stat {}: {e}", path.display()))?
+        .len();
+
+    let mut hasher = Sha256::new();
+    hasher.update(len.to_le_bytes());
+
+    let mut buf = vec![0u8; SAMPLE_BYTES.min(len) as usize];
+    file.read_exact(&mut buf).map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+    hasher.update(&buf);
+
+    if len > SAMPLE_BYTES * 2 {
+        file.seek(SeekFrom::End(-(SAMPLE_BYTES as i64)))
+            .map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+        file.read_exact(&mut buf).map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+        hasher.update(&buf);
+    }
+
+    let digest = hasher.finalize();
+    Ok(digest.iter().take(16).map(|b| format!("{:02x}", b)).collect())
+}
+
+/// Per-feature folder under the app cache dir (filmstrips, waveforms, proxies, ...)
+pub fn cache_dir(app: &tauri::AppHandle, kind: &str) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_cache_dir()
+        .map_err(|e| format!("Failed to resolve app cache dir: {e}"))?
+        .join(kind);
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create cache dir: {e}"))?;
+    Ok(dir)
+}
+
+/// Write via a temp file + rename so readers never see a partial cache entry.
+/// The temp name is unique per write, so concurrent writers of one entry don't share it.
+pub fn write_atomic(path: &Path, data: &[u8]) -> Result<(), String> {
+    let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let nonce = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    let seq = WRITE_SEQ.fetch_add(1, Ordering::Relaxed);
+    let tmp = path.with_file_name(format!(".{}.{}-{}-{}.tmp", name, std::process::id(), nonce, seq));
+
+    let written = std::fs::write(&tmp, data).and_then(|_| std::fs::rename(&tmp, path));
+    if let Err(e) = written {
+        let _ = std::fs::remove_file(&tmp);
+        return Err(format!("Failed to write cache entry: {e}"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn content_key_follows_content_not_name() {
+        let workspace = crate::temp_workspace::create(Some("cache_test")).unwrap();
+        let dir = PathBuf::from(&workspace.path);
+        let a = dir.join("a.mov");
+        let b = dir.join("b.mov");
+        std::fs::write(&a, b"same bytes").unwrap();
+        std::fs::write(&b, b"same bytes").unwrap();
+        assert_eq!(content_key(&a).unwrap(), content_key(&b).unwrap());
+        assert_eq!(content_key(&a).unwrap().len(), 32);
+
+        std::fs::write(&b, b"same bytez").unwrap();
+        assert_ne!(content_key(&a).unwrap(), content_key(&b).unwrap());
+
+        // Large files are keyed by head and tail; a change at the very end still counts
+        let mut large = vec![7u8; (SAMPLE_BYTES * 3) as usize];
+        std::fs::write(&a, &large).unwrap();
+        *large.last_mut().unwrap() = 8;
+        std::fs::write(&b, &large).unwrap();
+        assert_ne!(content_key(&a).unwrap(), content_key(&b).unwrap());
+
+        assert!(content_key(&dir.join("missing.mov")).is_err());
+        crate::temp_workspace::release(&workspace.job_id).unwrap();
+    }
+
+    #[test]
+    fn write_atomic_replaces_without_leftovers() {
+        let workspace = crate::temp_workspace::create(Some("cache_test")).unwrap();
+        let dir = PathBuf::from(&workspace.path);
+        let entry = dir.join("entry.json");
+        write_atomic(&entry, b"one").unwrap();
+        write_atomic(&entry, b"two").unwrap();
+        assert_eq!(std::fs::read(&entry).unwrap(), b"two");
+        let leftovers = std::fs::read_dir(&dir)
+            .unwrap()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_name().to_string_lossy().ends_with(".tmp"))
+            .count();
+        assert_eq!(leftovers, 0);
+
+        // Parallel writers of the same entry each get their own temp file
+        let writers: Vec<_> = (0..8u8)
+            .map(|i| {
+                let entry = entry.clone();
+                std::thread::spawn(move || write_atomic(&entry, &[i; 4096]))
+            })
+            .collect();
+        for writer in writers {
+            writer.join().unwrap().unwrap();
+        }
+        let data = std::fs::read(&entry).unwrap();
+        assert_eq!(data.len(), 4096);
+        assert!(data.iter().all(|b| *b == data[0]));
+        crate::temp_workspace::release(&workspace.job_id).unwrap();
+    }
+}
@@ -0,0 +1,324 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::{Condvar, Mutex};
+use tauri::Emitter;
+
+use crate::probe::{MediaInfo, StreamInfo};
+use crate::{ffmpeg_caps, media_cache, probe};
+
+// Codecs every supported webview decodes reliably
+const WEBVIEW_VIDEO_CODECS: &[&str] = &["h264", "vp8", "vp9"];
+const MAX_PROXY_FPS: f64 = 60.0;
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ImportedMedia {
+    pub original: String,
+    pub info: MediaInfo,
+    // Webview-friendly H.264 copy when the original needs one; exports may use either
+    pub proxy_path: Option<String>,
+    pub proxy_reasons: Vec<String>,
+    pub cached: bool,
+}
+
+// Stored next to each proxy so the cache entry can be traced back to its source
+#[derive(Serialize, Deserialize)]
+struct ProxyLink {
+    original: String,
+    proxy: String,
+    reasons: Vec<String>,
+}
+
+#[derive(Serialize, Clone)]
+struct ImportProgressPayload {
+    source: String,
+    progress: f32,
+}
+
+fn is_hdr(stream: &StreamInfo) -> bool {
+    matches!(stream.color_transfer.as_deref(), Some("smpte2084") | Some("arib-std-b67"))
+        || stream.color_primaries.as_deref() == Some("bt2020")
+}
+
+/// Why the webview can't play this video as-is (empty = no proxy needed)
+pub fn proxy_reasons(info: &MediaInfo) -> Vec<String> {
+    let Some(video) = info.streams.iter().find(|s| s.kind == "video") else {
+        return Vec::new();
+    };
+
+    let mut reasons = Vec::new();
+    if !WEBVIEW_VIDEO_CODECS.contains(&video.codec.as_str()) {
+        reasons.push(format!("codec {}", video.codec));
+    }
+    if video.is_vfr {
+        reasons.push("variable frame rate".to_string());
+    }
+    if video.rotation.unwrap_or(0) != 0 {
+        reasons.push(format!("rotation {}", video.rotation.unwrap_or(0)));
+    }
+    if is_hdr(video) {
+        reasons.push("HDR".to_string());
+    }
+    if let Some(pix_fmt) = &video.pix_fmt {
+        // 10-bit / 4:2:2 / 4:4:4 h264 is not universally decodable either
+        if pix_fmt != "yuv420p" && pix_fmt != "yuvj420p" {
+            reasons.push(format!("pixel format {}", pix_fmt));
+        }
+    }
+    reasons
+}
+
+// Tone-map HDR to SDR BT.709. Needs zscale (libzimg) to linearize PQ/HLG; a plain
+// colorspace conversion can't, and would leave highlights clipped and washed out.
+const TONEMAP_FILTER: &str =
+    "zscale=t=linear:npl=100,format=gbrpf32le,zscale=p=bt709,tonemap=tonemap=hable:desat=0,zscale=t=bt709:m=bt709:r=tv";
+
+fn can_tonemap() -> bool {
+    ffmpeg_caps::has_filter("zscale") && ffmpeg_caps::has_filter("tonemap")
+}
+
+fn proxy_filter(video: &StreamInfo) -> (String, f64) {
+    // VFR sources report their nominal rate as tbr; conform to it
+    let fps = video
+        .tbr
+        .or(video.fps)
+        .unwrap_or(30.0)
+        .clamp(1.0, MAX_PROXY_FPS)
+        .round();
+
+    let mut filters = Vec::new();
+    if is_hdr(video) {
+        filters.push(TONEMAP_FILTER.to_string());
+    }
+    // Rotation is applied by ffmpeg's autorotate on input; fps makes the output constant rate
+    filters.push(format!("fps={}", fps));
+    filters.push("scale=trunc(iw/2)*2:trunc(ih/2)*2:flags=bicubic".to_string());
+    filters.push("format=yuv420p".to_string());
+    (filters.join(","), fps)
+}
+
+// Proxy keys being transcoded - a second import of the same content waits for the first
+// instead of writing the same partial file alongside it
+lazy_static::lazy_static! {
+    static ref IN_PROGRESS: (Mutex<HashSet<String>>, Condvar) = (Mutex::new(HashSet::new()), Condvar::new());
+}
+
+// Holds a proxy key; dropping it (also when the transcode fails) wakes the waiting imports
+struct ProxyJob(String);
+
+impl Drop for ProxyJob {
+    fn drop(&mut self) {
+        let (jobs, done) = &*IN_PROGRESS;
+        if let Ok(mut jobs) = jobs.lock() {
+            jobs.remove(&self.0);
+        }
+        done.notify_all();
+    }
+}
+
+fn claim_proxy(key: &str) -> Result<ProxyJob, String> {
+    let (jobs, done) = &*IN_PROGRESS;
+    let mut jobs = jobs.lock().map_err(|e| format!("Failed to lock proxy jobs: {e}"))?;
+    while jobs.contains(key) {
+        jobs = done.wait(jobs).map_err(|e| format!("Failed to lock proxy jobs: {e}"))?;
+    }
+    jobs.insert(key.to_string());
+    Ok(ProxyJob(key.to_string()))
+}
+
+fn transcode_proxy(
+    app: &tauri::AppHandle,
+    source: &Path,
+    info: &MediaInfo,
+    target: &Path,
+) -> Result<(), String> {
+    let video = info
+        .streams
+        .iter()
+        .find(|s| s.kind == "video")
+        .ok_or("No video stream")?;
+    let (filter, fps) = proxy_filter(video);
+    let total_secs = (info.duration_ms.unwrap_or(0) as f32 / 1000.0).max(0.01);
+    let tmp = target.with_extension("partial.mp4");
+
+    let ffmpeg_path = crate::get_ffmpeg_path()?;
+    let mut command = Command::new(&ffmpeg_path);
+    command
+        .args(["-hide_banner", "-nostdin", "-y", "-progress", "pipe:1", "-i"])
+        .arg(source)
+        .args(["-map", "0:v:0", "-map", "0:a:0?", "-vf", &filter])
+        .args(["-r", &fps.to_string()])
+        .args(["-c:v", "libx264", "-preset", "veryfast", "-crf", "18", "-pix_fmt", "yuv420p"])
+        .args(["-color_primaries", "bt709", "-color_trc", "bt709", "-colorspace", "bt709"])
+        .args(["-c:a", "aac", "-b:a", "192k", "-ar", "48000"])
+        .args(["-metadata:s:v:0", "rotate=0", "-movflags", "+faststart"])
+        .arg(&tmp)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null());
+
+    log::info!("[Import] Transcoding proxy: {:?}", command);
+    let mut child = command.spawn().map_err(|e| format!("Failed to spawn ffmpeg: {e}"))?;
+    let source_str = source.to_string_lossy().to_string();
+
+    if let Some(stdout) = child.stdout.take() {
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            if let Some(out_time) = crate::parse_out_time(&line) {
+                let _ = app.emit(
+                    "import-media-progress",
+                    ImportProgressPayload {
+                        source: source_str.clone(),
+                        progress: (out_time / total_secs).min(1.0),
+                    },
+                );
+            }
+        }
+    }
+
+    let status = child.wait().map_err(|e| format!("Failed to wait for ffmpeg: {e}"))?;
+    if !status.success() {
+        let _ = std::fs::remove_file(&tmp);
+        return Err(format!("Proxy transcode failed: {}", status));
+    }
+    std::fs::rename(&tmp, target).map_err(|e| format!("Failed to store proxy: {e}"))
+}
+
+/// Probe an imported file and, when the webview can't play it, create (or reuse) a
+/// constant-frame-rate SDR H.264 proxy in the app cache.
+pub fn import(app: &tauri::AppHandle, source: &Path, force_proxy: bool) -> Result<ImportedMedia, String> {
+    let info = probe::probe(source)?;
+    let mut reasons = proxy_reasons(&info);
+    if force_proxy && reasons.is_empty() && info.streams.iter().any(|s| s.kind == "video") {
+        reasons.push("requested".to_string());
+    }
+
+    let original = source.to_string_lossy().to_string();
+    if reasons.is_empty() {
+        return Ok(ImportedMedia {
+            original,
+            info,
+            proxy_path: None,
+            proxy_reasons: reasons,
+            cached: false,
+        });
+    }
+
+    if let Some(video) = info.streams.iter().find(|s| s.kind == "video" && is_hdr(s)) {
+        if !can_tonemap() {
+            return Err(format!(
+                "{} is HDR ({}) and the bundled ffmpeg has no zscale filter to tone-map it",
+                source.display(),
+                video.color_transfer.as_deref().unwrap_or("bt2020")
+            ));
+        }
+    }
+
+    let key = media_cache::content_key(source)?;
+    let dir = media_cache::cache_dir(app, "proxies")?;
+    let proxy = dir.join(format!("{}.mp4", key));
+    // Checked under the claim, so an import that waited picks up the proxy just written
+    let _job = claim_proxy(&key)?;
+    let cached = proxy.exists();
+
+    if !cached {
+        transcode_proxy(app, source, &info, &proxy)?;
+    }
+
+    let link = ProxyLink {
+        original: original.clone(),
+        proxy: proxy.to_string_lossy().to_string(),
+        reasons: reasons.clone(),
+    };
+    if let Ok(json) = serde_json::to_vec_pretty(&link) {
+        if let Err(e) = media_cache::write_atomic(&dir.join(format!("{}.json", key)), &json) {
+            log::warn!("[Import] {}", e);
+        }
+    }
+
+    Ok(ImportedMedia {
+        original,
+        info,
+        proxy_path: Some(link.proxy),
+        proxy_reasons: reasons,
+        cached,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn video(transfer: &str, fps: f64, tbr: f64) -> StreamInfo {
+        StreamInfo {
+            kind: "video".to_string(),
+            codec: "hevc".to_string(),
+            pix_fmt: Some("yuv420p".to_string()),
+            color_transfer: Some(transfer.to_string()),
+            fps: Some(fps),
+            tbr: Some(tbr),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn hdr_proxies_are_tone_mapped() {
+        for transfer in ["smpte2084", "arib-std-b67"] {
+            let (filter, _) = proxy_filter(&video(transfer, 30.0, 30.0));
+            assert!(filter.starts_with(TONEMAP_FILTER), "{transfer}: {filter}");
+        }
+        let (filter, fps) = proxy_filter(&video("bt709", 30.0, 30.0));
+        assert_eq!(fps, 30.0);
+        assert_eq!(filter, "fps=30,scale=trunc(iw/2)*2:trunc(ih/2)*2:flags=bicubic,format=yuv420p");
+    }
+
+    #[test]
+    fn proxy_rate_follows_tbr_within_limits() {
+        // VFR screen recording: 59.88 average, 60 nominal
+        assert_eq!(proxy_filter(&video("bt709", 59.88, 60.0)).1, 60.0);
+        assert_eq!(proxy_filter(&video("bt709", 240.0, 240.0)).1, MAX_PROXY_FPS);
+    }
+
+    #[test]
+    fn same_proxy_is_transcoded_by_one_import_at_a_time() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::sync::Arc;
+
+        let first = claim_proxy("same_content").unwrap();
+        // Other content isn't held up
+        drop(claim_proxy("other_content").unwrap());
+
+        let claimed = Arc::new(AtomicBool::new(false));
+        let waiter = {
+            let claimed = claimed.clone();
+            std::thread::spawn(move || {
+                let _job = claim_proxy("same_content").unwrap();
+                claimed.store(true, Ordering::SeqCst);
+            })
+        };
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        assert!(!claimed.load(Ordering::SeqCst));
+
+        drop(first);
+        waiter.join().unwrap();
+        assert!(claimed.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn reasons_name_each_problem() {
+        let mut stream = video("smpte2084", 59.88, 60.0);
+        stream.is_vfr = true;
+        stream.rotation = Some(90);
+        stream.pix_fmt = Some("yuv420p10le".to_string());
+        let info = MediaInfo {
+            streams: vec![stream],
+            ..Default::default()
+        };
+        assert_eq!(
+            proxy_reasons(&info),
+            ["codec hevc", "variable frame rate", "rotation 90", "HDR", "pixel format yuv420p10le"]
+        );
+        assert!(proxy_reasons(&MediaInfo::default()).is_empty());
+    }
+}
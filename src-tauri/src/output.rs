@@ -0,0 +1,193 @@
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+/// What to do when the export target already exists
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CollisionPolicy {
+    #[default]
+    Overwrite,
+    // "clip.mp4" -> "clip (1).mp4", "clip (2).mp4", ...
+    Increment,
+    Fail,
+}
+
+fn split_name(path: &Path) -> (String, Option<String>) {
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| "output".to_string());
+    let ext = path.extension().map(|e| e.to_string_lossy().to_string());
+    (stem, ext)
+}
+
+fn numbered_path(path: &Path, n: u32) -> PathBuf {
+    let (stem, ext) = split_name(path);
+    let name = match ext {
+        Some(ext) => format!("{} ({}).{}", stem, n, ext),
+        None => format!("{} ({})", stem, n),
+    };
+    path.with_file_name(name)
+}
+
+/// Resolve the final output path for a policy. Called before encoding so `Fail` errors early;
+/// `commit_output` re-checks because another process may create the file in the meantime.
+pub fn resolve_output_path(path: &Path, policy: CollisionPolicy) -> Result<PathBuf, String> {
+    if !path.exists() {
+        return Ok(path.to_path_buf());
+    }
+    match policy {
+        CollisionPolicy::Overwrite => Ok(path.to_path_buf()),
+        CollisionPolicy::Fail => Err(format!("Output file already exists: {}", path.display())),
+        CollisionPolicy::Increment => (1..10_000)
+            .map(|n| numbered_path(path, n))
+            .find(|candidate| !candidate.exists())
+            .ok_or_else(|| format!("No free file name for output: {}", path.display())),
+    }
+}
+
+/// Hidden sibling the encoder writes into. Keeps the real extension last so ffmpeg still
+/// picks the right muxer, and lives in the same directory so the final rename is atomic.
+pub fn temp_output_path(path: &Path) -> PathBuf {
+    let (stem, ext) = split_name(path);
+    let nonce = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    let name = match ext {
+        Some(ext) => format!(".{}.{}-{}.partial.{}", stem, std::process::id(), nonce, ext),
+        None => format!(".{}.{}-{}.partial", stem, std::process::id(), nonce),
+    };
+    path.with_file_name(name)
+}
+
+// Move without clobbering: a hard link fails if the target exists, which makes the
+// check-and-create atomic. Falls back to exists+rename on filesystems without links (FAT/exFAT).
+fn rename_no_clobber(from: &Path, to: &Path) -> std::io::Result<()> {
+    match std::fs::hard_link(from, to) {
+        Ok(()) => {
+            let _ = std::fs::remove_file(from);
+            Ok(())
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => Err(e),
+        Err(_) => {
+            if to.exists() {
+                return Err(std::io::Error::from(std::io::ErrorKind::AlreadyExists));
+            }
+            std::fs::rename(from, to)
+        }
+    }
+}
+
+/// Move a finished temp output into place and return the path it ended up at.
+/// `target` is the path the user asked for; `Increment` numbers from there.
+pub fn commit_output(temp: &Path, target: &Path, policy: CollisionPolicy) -> Result<PathBuf, String> {
+    match policy {
+        CollisionPolicy::Overwrite => {
+            // rename replaces atomically on Unix and uses MOVEFILE_REPLACE_EXISTING on Windows
+            std::fs::rename(temp, target)
+                .map_err(|e| format!("Failed to move output into place: {e}"))?;
+            Ok(target.to_path_buf())
+        }
+        CollisionPolicy::Fail => match rename_no_clobber(temp, target) {
+            Ok(()) => Ok(target.to_path_buf()),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                Err(format!("Output file already exists: {}", target.display()))
+            }
+            Err(e) => Err(format!("Failed to move output into place: {e}")),
+        },
+        CollisionPolicy::Increment => {
+            let mut candidate = target.to_path_buf();
+            for n in 1..10_000 {
+                match rename_no_clobber(temp, &candidate) {
+                    Ok(()) => return Ok(candidate),
+                    Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                        candidate = numbered_path(target, n);
+                    }
+                    Err(e) => return Err(format!("Failed to move output into place: {e}")),
+                }
+            }
+            Err(format!("No free file name for output: {}", target.display()))
+        }
+    }
+}
+
+/// Remove a temp output (file, or folder for image sequences) after a failed or cancelled encode
+pub fn discard_output(temp: &Path) {
+    if temp.exists() {
+        let removed = if temp.is_dir() {
+            std::fs::remove_dir_all(temp)
+        } else {
+            std::fs::remove_file(temp)
+        };
+        if let Err(e) = removed {
+            log::warn!("[Output] Failed to remove partial output {}: {}", temp.display(), e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("output_{}_{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn free_path_is_kept_for_every_policy() {
+        let dir = scratch("free");
+        let target = dir.join("clip.mp4");
+        for policy in [CollisionPolicy::Overwrite, CollisionPolicy::Increment, CollisionPolicy::Fail] {
+            assert_eq!(resolve_output_path(&target, policy).unwrap(), target);
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn existing_path_follows_policy() {
+        let dir = scratch("existing");
+        let target = dir.join("clip.mp4");
+        std::fs::write(&target, b"old").unwrap();
+        std::fs::write(dir.join("clip (1).mp4"), b"old").unwrap();
+
+        assert_eq!(resolve_output_path(&target, CollisionPolicy::Overwrite).unwrap(), target);
+        assert!(resolve_output_path(&target, CollisionPolicy::Fail).is_err());
+        assert_eq!(
+            resolve_output_path(&target, CollisionPolicy::Increment).unwrap(),
+            dir.join("clip (2).mp4")
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn numbering_without_extension() {
+        assert_eq!(numbered_path(Path::new("/out/frames"), 3), Path::new("/out/frames (3)"));
+    }
+
+    #[test]
+    fn temp_path_keeps_extension_and_directory() {
+        let temp = temp_output_path(Path::new("/out/clip.webm"));
+        assert_eq!(temp.parent(), Some(Path::new("/out")));
+        let name = temp.file_name().unwrap().to_string_lossy().to_string();
+        assert!(name.starts_with(".clip."));
+        assert!(name.ends_with(".partial.webm"));
+    }
+
+    #[test]
+    fn commit_increments_past_existing_file() {
+        let dir = scratch("commit");
+        let target = dir.join("clip.mp4");
+        std::fs::write(&target, b"old").unwrap();
+        let temp = temp_output_path(&target);
+        std::fs::write(&temp, b"new").unwrap();
+
+        let committed = commit_output(&temp, &target, CollisionPolicy::Increment).unwrap();
+        assert_eq!(committed, dir.join("clip (1).mp4"));
+        assert_eq!(std::fs::read(&target).unwrap(), b"old");
+        assert!(!temp.exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,141 @@
+use std::path::{Component, Path, PathBuf};
+use tauri_plugin_fs::FsExt;
+
+/// Canonicalize a path that may not exist yet (e.g. a copy destination).
+/// The deepest existing ancestor is resolved through the filesystem (symlinks, `..`);
+/// the non-existing remainder may only contain plain names.
+pub fn canonicalize_lenient(path: &Path) -> Result<PathBuf, String> {
+    if !path.is_absolute() {
+        return Err(format!("Path must be absolute: {}", path.display()));
+    }
+
+    let mut existing = path.to_path_buf();
+    let mut remainder = Vec::new();
+    while !existing.exists() {
+        let name = match existing.components().next_back() {
+            Some(Component::Normal(name)) => name.to_os_string(),
+            _ => return Err(format!("Invalid path: {}", path.display())),
+        };
+        remainder.push(name);
+        if !existing.pop() {
+            return Err(format!("Invalid path: {}", path.display()));
+        }
+    }
+
+    let mut resolved = dunce::canonicalize(&existing)
+        .map_err(|e| format!("Failed to resolve path {}: {e}", path.display()))?;
+    for name in remainder.into_iter().rev() {
+        resolved.push(name);
+    }
+    Ok(resolved)
+}
+
+/// Resolve `path` and check it against the fs plugin scope. Custom commands don't get the
+/// plugin's checks for free, so they call this instead. The scope only holds what was granted
+/// at runtime: dialog picks and the app's own folders (see `allow_dir`).
+pub fn ensure_in_scope<R: tauri::Runtime>(app: &tauri::AppHandle<R>, path: &Path) -> Result<PathBuf, String> {
+    let resolved = canonicalize_lenient(path)?;
+    let scope = app.fs_scope();
+    if scope.is_forbidden(&resolved) || !scope.is_allowed(&resolved) {
+        return Err(format!("Path is outside the allowed scope: {}", path.display()));
+    }
+    Ok(resolved)
+}
+
+/// Grant a file the user picked in a native dialog
+pub fn allow_file<R: tauri::Runtime>(app: &tauri::AppHandle<R>, path: &Path) -> Result<PathBuf, String> {
+    let resolved = canonicalize_lenient(path)?;
+    app.fs_scope()
+        .allow_file(&resolved)
+        .map_err(|e| format!("Failed to allow {}: {e}", path.display()))?;
+    Ok(resolved)
+}
+
+/// Grant an app-owned folder (export folder, cache, temp workspace) and everything below it
+pub fn allow_dir<R: tauri::Runtime>(app: &tauri::AppHandle<R>, path: &Path) -> Result<PathBuf, String> {
+    let resolved = canonicalize_lenient(path)?;
+    app.fs_scope()
+        .allow_directory(&resolved, true)
+        .map_err(|e| format!("Failed to allow {}: {e}", path.display()))?;
+    Ok(resolved)
+}
+
+// "C:\..." / "C:/..." - a drive letter, not a protocol
+fn is_windows_drive(path: &str) -> bool {
+    let bytes = path.as_bytes();
+    bytes.len() >= 3
+        && bytes[0].is_ascii_alphabetic()
+        && bytes[1] == b':'
+        && (bytes[2] == b'\\' || bytes[2] == b'/')
+}
+
+/// Reject strings ffmpeg would not treat as a plain local file: leading `-` is parsed as an
+/// option and `scheme:` as a protocol (`pipe:`, `http:`, `concat:` ...).
+pub fn ensure_plain_file_path(path: &str) -> Result<(), String> {
+    let trimmed = path.trim();
+    if trimmed.is_empty() {
+        return Err("Path is empty".into());
+    }
+    if trimmed.starts_with('-') {
+        return Err(format!("Path must not start with '-': {}", path));
+    }
+
+    let scheme_end = trimmed.find(':');
+    if let Some(idx) = scheme_end {
+        let scheme = &trimmed[..idx];
+        let looks_like_scheme = !scheme.is_empty()
+            && scheme.chars().all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '-' || c == '.');
+        if looks_like_scheme && !is_windows_drive(trimmed) {
+            return Err(format!("Protocol paths are not allowed: {}", path));
+        }
+    }
+
+    if !Path::new(trimmed).is_absolute() {
+        return Err(format!("Path must be absolute: {}", path));
+    }
+    Ok(())
+}
+
+/// Full check for encoder output paths: plain file path, inside the fs scope.
+pub fn ensure_output_path<R: tauri::Runtime>(app: &tauri::AppHandle<R>, path: &str) -> Result<PathBuf, String> {
+    ensure_plain_file_path(path)?;
+    ensure_in_scope(app, Path::new(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lenient_canonicalize_resolves_before_checking() {
+        let base = dunce::canonicalize(std::env::temp_dir()).unwrap().join(format!("path_scope_{}", std::process::id()));
+        std::fs::create_dir_all(base.join("export")).unwrap();
+        // The missing tail is kept as-is, the existing part goes through the filesystem
+        assert_eq!(
+            canonicalize_lenient(&base.join("export").join("new").join("clip.mp4")).unwrap(),
+            base.join("export").join("new").join("clip.mp4")
+        );
+        assert_eq!(
+            canonicalize_lenient(&base.join("export").join("..").join("clip.mp4")).unwrap(),
+            base.join("clip.mp4")
+        );
+        // `..` inside the missing part can't be resolved safely
+        assert!(canonicalize_lenient(&base.join("missing").join("..").join("clip.mp4")).is_err());
+        std::fs::remove_dir_all(&base).unwrap();
+    }
+
+    #[test]
+    fn relative_path_fails() {
+        assert!(canonicalize_lenient(Path::new("clip.mp4")).is_err());
+    }
+
+    #[test]
+    fn plain_file_paths() {
+        assert!(ensure_plain_file_path("/tmp/out.mp4").is_ok());
+        assert!(ensure_plain_file_path("-y").is_err());
+        assert!(ensure_plain_file_path("pipe:1").is_err());
+        assert!(ensure_plain_file_path("concat:/a.mp4|/b.mp4").is_err());
+        assert!(ensure_plain_file_path("out.mp4").is_err());
+        assert!(is_windows_drive("C:\\Users\\clip.mp4"));
+    }
+}
@@ -0,0 +1,562 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct StreamInfo {
+    pub index: u32,
+    pub kind: String, // "video" | "audio" | "subtitle" | "data"
+    pub codec: String,
+    pub profile: Option<String>,
+    pub bitrate_kbps: Option<u32>,
+    // Video
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub rotation: Option<i32>, // Degrees from the display matrix, normalized to 0/90/180/270
+    pub fps: Option<f64>,      // Average frame rate
+    pub tbr: Option<f64>,      // Real base frame rate (r_frame_rate)
+    pub is_vfr: bool,
+    pub pix_fmt: Option<String>,
+    pub color_range: Option<String>,
+    pub color_space: Option<String>,
+    pub color_primaries: Option<String>,
+    pub color_transfer: Option<String>,
+    // Audio
+    pub sample_rate: Option<u32>,
+    pub channels: Option<u32>,
+    pub channel_layout: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct MediaInfo {
+    pub path: String,
+    pub container: Option<String>,
+    pub duration_ms: Option<u64>,
+    pub bitrate_kbps: Option<u32>,
+    pub streams: Vec<StreamInfo>,
+}
+
+impl MediaInfo {
+    pub fn first_audio(&self) -> Option<&StreamInfo> {
+        self.streams.iter().find(|s| s.kind == "audio")
+    }
+
+    pub fn has_audio(&self) -> bool {
+        self.first_audio().is_some()
+    }
+}
+
+// path -> (mtime, size, info). Size catches rewrites within the mtime granularity.
+lazy_static::lazy_static! {
+    static ref PROBE_CACHE: Mutex<HashMap<PathBuf, (SystemTime, u64, MediaInfo)>> = Mutex::new(HashMap::new());
+}
+
+// avg fps and tbr further apart than this means frames are not evenly spaced
+// (iPhone screen recordings typically report "59.88 fps, 60 tbr")
+const VFR_TOLERANCE: f64 = 0.001;
+
+/// Probe a media file with the bundled ffmpeg, cached by path + mtime
+pub fn probe(path: &Path) -> Result<MediaInfo, String> {
+    let meta = std::fs::metadata(path).map_err(|e| format!("Media not accessible: {} ({e})", path.display()))?;
+    let mtime = meta.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+    let size = meta.len();
+
+    if let Ok(cache) = PROBE_CACHE.lock() {
+        if let Some((cached_mtime, cached_size, info)) = cache.get(path) {
+            if *cached_mtime == mtime && *cached_size == size {
+                return Ok(info.clone());
+            }
+        }
+    }
+
+    let ffmpeg_path = crate::get_ffmpeg_path()?;
+    // Without an output ffmpeg prints the input description to stderr and exits non-zero
+    let output = Command::new(&ffmpeg_path)
+        .args(["-hide_banner", "-nostdin", "-i"])
+        .arg(path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .output()
+        .map_err(|e| format!("Failed to run ffmpeg: {e}"))?;
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let mut info = parse_probe_output(&stderr)
+        .ok_or_else(|| format!("Unrecognized media file: {}", last_line(&stderr)))?;
+    info.path = path.to_string_lossy().to_string();
+
+    if let Ok(mut cache) = PROBE_CACHE.lock() {
+        cache.insert(path.to_path_buf(), (mtime, size, info.clone()));
+    }
+    Ok(info)
+}
+
+fn last_line(text: &str) -> &str {
+    text.lines().rev().find(|l| !l.trim().is_empty()).unwrap_or("").trim()
+}
+
+// Split on commas that are not inside () or [] - "yuv420p(tv, bt709)" stays one part
+fn split_top_level(s: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0i32;
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        match c {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(s[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(s[start..].trim());
+    parts
+}
+
+fn parse_duration(value: &str) -> Option<u64> {
+    // "00:01:02.50"
+    let mut fields = value.trim().split(':');
+    let h: f64 = fields.next()?.parse().ok()?;
+    let m: f64 = fields.next()?.parse().ok()?;
+    let s: f64 = fields.next()?.parse().ok()?;
+    Some(((h * 3600.0 + m * 60.0 + s) * 1000.0).round() as u64)
+}
+
+fn parse_number_with_suffix(part: &str, suffix: &str) -> Option<f64> {
+    let value = part.strip_suffix(suffix)?.trim();
+    // "29.97", "30k" (tbr can be abbreviated)
+    if let Some(k) = value.strip_suffix('k') {
+        return k.parse::<f64>().ok().map(|v| v * 1000.0);
+    }
+    value.parse().ok()
+}
+
+fn channels_for_layout(layout: &str) -> Option<u32> {
+    match layout {
+        "mono" => Some(1),
+        "stereo" => Some(2),
+        "2.1" | "3.0" => Some(3),
+        "4.0" | "quad" => Some(4),
+        "5.0" | "5.0(side)" => Some(5),
+        "5.1" | "5.1(side)" => Some(6),
+        "6.1" => Some(7),
+        "7.1" | "7.1(wide)" => Some(8),
+        _ => layout.strip_suffix(" channels").and_then(|n| n.trim().parse().ok()),
+    }
+}
+
+fn normalize_rotation(degrees: f64) -> i32 {
+    (((degrees.round() as i32) % 360) + 360) % 360
+}
+
+// "yuv420p(tv, bt2020nc/bt2020/arib-std-b67, progressive)"
+fn parse_pix_fmt(part: &str, stream: &mut StreamInfo) {
+    let (name, details) = match part.find('(') {
+        Some(idx) => (&part[..idx], Some(part[idx + 1..].trim_end_matches(')'))),
+        None => (part, None),
+    };
+    stream.pix_fmt = Some(name.trim().to_string());
+
+    let Some(details) = details else { return };
+    for item in details.split(',').map(str::trim) {
+        match item {
+            "tv" | "pc" => stream.color_range = Some(item.to_string()),
+            "progressive" | "top first" | "bottom first" | "top coded first (swapped)" => {}
+            _ => {
+                // Either "space/primaries/transfer" or one name shared by all three
+                let names: Vec<&str> = item.split('/').collect();
+                if names.len() == 3 {
+                    stream.color_space = Some(names[0].to_string());
+                    stream.color_primaries = Some(names[1].to_string());
+                    stream.color_transfer = Some(names[2].to_string());
+                } else if names.len() == 1 && stream.color_space.is_none() {
+                    stream.color_space = Some(item.to_string());
+                    stream.color_primaries = Some(item.to_string());
+                    stream.color_transfer = Some(item.to_string());
+                }
+            }
+        }
+    }
+}
+
+fn parse_stream_line(line: &str) -> Option<StreamInfo> {
+    // "Stream #0:1[0x2](und): Audio: aac (LC) (mp4a / 0x6134706D), 44100 Hz, stereo, fltp, 128 kb/s (default)"
+    let rest = line.trim().strip_prefix("Stream #")?;
+    let index_str = rest.split(':').nth(1)?;
+    let index: u32 = index_str
+        .chars()
+        .take_while(|c| c.is_ascii_digit())
+        .collect::<String>()
+        .parse()
+        .ok()?;
+
+    // The description starts after ": Video: " / ": Audio: " - the bit before may hold "[0x1](und)"
+    let (kind, desc) = ["Video", "Audio", "Subtitle", "Data", "Attachment"]
+        .iter()
+        .filter_map(|kind| {
+            let marker = format!(": {}: ", kind);
+            rest.find(&marker).map(|idx| (idx, *kind, &rest[idx + marker.len()..]))
+        })
+        .min_by_key(|(idx, _, _)| *idx)
+        .map(|(_, kind, desc)| (kind, desc))?;
+
+    let mut stream = StreamInfo {
+        index,
+        kind: kind.trim().to_lowercase(),
+        ..Default::default()
+    };
+
+    let parts = split_top_level(desc);
+    let codec_part = parts.first()?;
+    stream.codec = codec_part.split_whitespace().next().unwrap_or("").to_string();
+    if let Some(start) = codec_part.find('(') {
+        if let Some(end) = codec_part[start..].find(')') {
+            let profile = &codec_part[start + 1..start + end];
+            if !profile.contains(" / ") {
+                stream.profile = Some(profile.to_string());
+            }
+        }
+    }
+
+    for (i, part) in parts.iter().enumerate().skip(1) {
+        // Trailing flags like "(default)" hang off the last part
+        let part = part.split(" (").next().unwrap_or(part).trim();
+        if let Some(kbps) = parse_number_with_suffix(part, " kb/s") {
+            stream.bitrate_kbps = Some(kbps as u32);
+            continue;
+        }
+        match stream.kind.as_str() {
+            "video" => {
+                if i == 1 {
+                    parse_pix_fmt(parts[1], &mut stream);
+                } else if let Some(fps) = parse_number_with_suffix(part, " fps") {
+                    stream.fps = Some(fps);
+                } else if let Some(tbr) = parse_number_with_suffix(part, " tbr") {
+                    stream.tbr = Some(tbr);
+                } else if stream.width.is_none() {
+                    let dims = part.split_whitespace().next().unwrap_or("");
+                    if let Some((w, h)) = dims.split_once('x') {
+                        if let (Ok(w), Ok(h)) = (w.parse(), h.parse()) {
+                            stream.width = Some(w);
+                            stream.height = Some(h);
+                        }
+                    }
+                }
+            }
+            "audio" => {
+                if let Some(hz) = parse_number_with_suffix(part, " Hz") {
+                    stream.sample_rate = Some(hz as u32);
+                } else if stream.channel_layout.is_none() && stream.sample_rate.is_some() {
+                    stream.channels = channels_for_layout(part);
+                    stream.channel_layout = Some(part.to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if let (Some(fps), Some(tbr)) = (stream.fps, stream.tbr) {
+        stream.is_vfr = tbr > 0.0 && ((fps - tbr) / tbr).abs() > VFR_TOLERANCE;
+    }
+
+    Some(stream)
+}
+
+/// Parse the input description ffmpeg prints for `ffmpeg -i <file>`
+pub(crate) fn parse_probe_output(stderr: &str) -> Option<MediaInfo> {
+    let mut info = MediaInfo::default();
+    let mut saw_input = false;
+
+    for line in stderr.lines() {
+        let trimmed = line.trim();
+        if let Some(rest) = trimmed.strip_prefix("Input #0, ") {
+            saw_input = true;
+            info.container = rest.split(", from ").next().map(|s| s.to_string());
+        } else if trimmed.starts_with("Input #") {
+            // Only the first input is of interest
+            break;
+        } else if let Some(rest) = trimmed.strip_prefix("Duration: ") {
+            for part in rest.split(", ") {
+                if let Some(bitrate) = part.strip_prefix("bitrate: ") {
+                    info.bitrate_kbps = parse_number_with_suffix(bitrate, " kb/s").map(|v| v as u32);
+                } else if !part.contains(':') || part.starts_with("start:") {
+                    continue;
+                } else {
+                    info.duration_ms = parse_duration(part);
+                }
+            }
+        } else if trimmed.starts_with("Stream #") {
+            if let Some(stream) = parse_stream_line(trimmed) {
+                info.streams.push(stream);
+            }
+        } else if let Some(rest) = trimmed.strip_prefix("displaymatrix: rotation of ") {
+            // Side data belongs to the stream printed just above it
+            let degrees: Option<f64> = rest.trim_end_matches(" degrees").trim().parse().ok();
+            if let (Some(stream), Some(degrees)) = (info.streams.last_mut(), degrees) {
+                stream.rotation = Some(normalize_rotation(degrees));
+            }
+        } else if let Some(rest) = trimmed.strip_prefix("rotate") {
+            // Older builds: "rotate          : 90" in stream metadata
+            let degrees: Option<f64> = rest.trim_start().strip_prefix(':').and_then(|v| v.trim().parse().ok());
+            if let (Some(stream), Some(degrees)) = (info.streams.last_mut(), degrees) {
+                if stream.rotation.is_none() {
+                    stream.rotation = Some(normalize_rotation(degrees));
+                }
+            }
+        }
+    }
+
+    if saw_input && !info.streams.is_empty() {
+        Some(info)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // `ffmpeg -hide_banner -i <file>` stderr, one per kind of clip users import
+
+    const H264_SDR: &str = "\
+Input #0, mov,mp4,m4a,3gp,3g2,mj2, from 'clip.mp4':
+  Metadata:
+    major_brand     : isom
+    minor_version   : 512
+    compatible_brands: isomiso2avc1mp41
+    encoder         : Lavf60.16.100
+  Duration: 00:00:12.50, start: 0.000000, bitrate: 4521 kb/s
+  Stream #0:0[0x1](und): Video: h264 (High) (avc1 / 0x31637661), yuv420p(tv, bt709, progressive), 1920x1080 [SAR 1:1 DAR 16:9], 4385 kb/s, 30 fps, 30 tbr, 15360 tbn (default)
+      Metadata:
+        handler_name    : VideoHandler
+        vendor_id       : [0][0][0][0]
+  Stream #0:1[0x2](und): Audio: aac (LC) (mp4a / 0x6134706D), 48000 Hz, stereo, fltp, 128 kb/s (default)
+      Metadata:
+        handler_name    : SoundHandler
+        vendor_id       : [0][0][0][0]
+At least one output file must be specified
+";
+
+    const HEVC_HDR10: &str = "\
+Input #0, mov,mp4,m4a,3gp,3g2,mj2, from 'hdr.mov':
+  Metadata:
+    major_brand     : qt  
+    creation_time   : 2024-03-14T09:26:53.000000Z
+  Duration: 00:00:05.01, start: 0.000000, bitrate: 24876 kb/s
+  Stream #0:0[0x1](und): Video: hevc (Main 10) (hvc1 / 0x31637668), yuv420p10le(tv, bt2020nc/bt2020/smpte2084), 3840x2160 [SAR 1:1 DAR 16:9], 24700 kb/s, 23.98 fps, 23.98 tbr, 24k tbn (default)
+      Metadata:
+        handler_name    : Core Media Video
+      Side data:
+        Mastering Display Metadata, has_primaries:1 has_luminance:1 r(0.6800,0.3200) g(0.2650,0.6900) b(0.1500 0.0600) wp(0.3127, 0.3290) min_luminance=0.000100, max_luminance=1000.000000
+        Content Light Level Metadata, MaxCLL=1000, MaxFALL=400
+At least one output file must be specified
+";
+
+    const VFR_SCREEN_RECORDING: &str = "\
+Input #0, mov,mp4,m4a,3gp,3g2,mj2, from 'ScreenRecording_05-02-2024.MP4':
+  Metadata:
+    major_brand     : qt  
+    creation_time   : 2024-05-02T10:11:12.000000Z
+    com.apple.quicktime.make: Apple
+  Duration: 00:00:31.42, start: 0.000000, bitrate: 6012 kb/s
+  Stream #0:0[0x1](und): Video: hevc (Main) (hvc1 / 0x31637668), yuv420p(tv, bt709), 886x1920, 5870 kb/s, 59.88 fps, 60 tbr, 600 tbn (default)
+      Metadata:
+        handler_name    : Core Media Video
+  Stream #0:1[0x2](und): Audio: aac (LC) (mp4a / 0x6134706D), 44100 Hz, mono, fltp, 96 kb/s (default)
+  Stream #0:2[0x3](und): Data: none (mebx / 0x7862656D), 0 kb/s (default)
+      Metadata:
+        handler_name    : Core Media Metadata
+At least one output file must be specified
+";
+
+    const AUDIO_ONLY: &str = "\
+Input #0, wav, from 'voiceover.wav':
+  Metadata:
+    encoder         : Lavf60.16.100
+  Duration: 00:00:07.25, bitrate: 1536 kb/s
+  Stream #0:0: Audio: pcm_s16le ([1][0][0][0] / 0x0001), 48000 Hz, 2 channels, s16, 1536 kb/s
+At least one output file must be specified
+";
+
+    const ROTATED_IPHONE: &str = "\
+Input #0, mov,mp4,m4a,3gp,3g2,mj2, from 'IMG_0420.MOV':
+  Metadata:
+    major_brand     : qt  
+    minor_version   : 0
+    creation_time   : 2024-06-01T08:30:00.000000Z
+    com.apple.quicktime.location.ISO6709: +52.5200+013.4050+034.000/
+    com.apple.quicktime.make: Apple
+    com.apple.quicktime.model: iPhone 14 Pro
+  Duration: 00:00:04.52, start: 0.000000, bitrate: 10754 kb/s
+  Stream #0:0[0x1](und): Video: hevc (Main) (hvc1 / 0x31637668), yuv420p(tv, bt709), 1920x1080, 10590 kb/s, 29.97 fps, 29.97 tbr, 600 tbn (default)
+      Metadata:
+        creation_time   : 2024-06-01T08:30:00.000000Z
+        handler_name    : Core Media Video
+        encoder         : HEVC
+      Side data:
+        displaymatrix: rotation of -90.00 degrees
+  Stream #0:1[0x2](und): Audio: aac (LC) (mp4a / 0x6134706D), 44100 Hz, stereo, fltp, 160 kb/s (default)
+At least one output file must be specified
+";
+
+    fn parse(stderr: &str) -> MediaInfo {
+        parse_probe_output(stderr).expect("fixture should parse")
+    }
+
+    #[test]
+    fn h264_sdr() {
+        let info = parse(H264_SDR);
+        assert_eq!(info.container.as_deref(), Some("mov,mp4,m4a,3gp,3g2,mj2"));
+        assert_eq!(info.duration_ms, Some(12_500));
+        assert_eq!(info.bitrate_kbps, Some(4521));
+        assert_eq!(info.streams.len(), 2);
+
+        let video = &info.streams[0];
+        assert_eq!((video.kind.as_str(), video.codec.as_str()), ("video", "h264"));
+        assert_eq!(video.profile.as_deref(), Some("High"));
+        assert_eq!((video.width, video.height), (Some(1920), Some(1080)));
+        assert_eq!(video.pix_fmt.as_deref(), Some("yuv420p"));
+        assert_eq!(video.color_range.as_deref(), Some("tv"));
+        assert_eq!(video.color_transfer.as_deref(), Some("bt709"));
+        assert_eq!((video.fps, video.tbr), (Some(30.0), Some(30.0)));
+        assert_eq!(video.bitrate_kbps, Some(4385));
+        assert!(!video.is_vfr);
+        assert_eq!(video.rotation, None);
+
+        let audio = info.first_audio().unwrap();
+        assert_eq!(audio.index, 1);
+        assert_eq!(audio.codec, "aac");
+        assert_eq!(audio.sample_rate, Some(48_000));
+        assert_eq!(audio.channels, Some(2));
+        assert_eq!(audio.channel_layout.as_deref(), Some("stereo"));
+        assert_eq!(audio.bitrate_kbps, Some(128));
+    }
+
+    #[test]
+    fn hevc_10_bit_hdr() {
+        let info = parse(HEVC_HDR10);
+        assert!(!info.has_audio());
+        let video = &info.streams[0];
+        assert_eq!(video.codec, "hevc");
+        assert_eq!(video.profile.as_deref(), Some("Main 10"));
+        assert_eq!((video.width, video.height), (Some(3840), Some(2160)));
+        assert_eq!(video.pix_fmt.as_deref(), Some("yuv420p10le"));
+        assert_eq!(video.color_space.as_deref(), Some("bt2020nc"));
+        assert_eq!(video.color_primaries.as_deref(), Some("bt2020"));
+        assert_eq!(video.color_transfer.as_deref(), Some("smpte2084"));
+        assert_eq!(video.fps, Some(23.98));
+        assert!(!video.is_vfr);
+    }
+
+    #[test]
+    fn vfr_screen_recording() {
+        let info = parse(VFR_SCREEN_RECORDING);
+        assert_eq!(info.duration_ms, Some(31_420));
+        let video = &info.streams[0];
+        assert_eq!((video.width, video.height), (Some(886), Some(1920)));
+        assert_eq!((video.fps, video.tbr), (Some(59.88), Some(60.0)));
+        assert!(video.is_vfr);
+
+        let audio = info.first_audio().unwrap();
+        assert_eq!((audio.sample_rate, audio.channels), (Some(44_100), Some(1)));
+
+        // The timed-metadata track is kept but never mistaken for audio or video
+        let data = &info.streams[2];
+        assert_eq!((data.index, data.kind.as_str(), data.codec.as_str()), (2, "data", "none"));
+        assert_eq!(data.width, None);
+    }
+
+    #[test]
+    fn audio_only() {
+        let info = parse(AUDIO_ONLY);
+        assert_eq!(info.container.as_deref(), Some("wav"));
+        assert_eq!(info.duration_ms, Some(7_250));
+        assert_eq!(info.streams.len(), 1);
+        let audio = info.first_audio().unwrap();
+        assert_eq!(audio.codec, "pcm_s16le");
+        // "([1][0][0][0] / 0x0001)" is a codec tag, not a profile
+        assert_eq!(audio.profile, None);
+        assert_eq!(audio.channels, Some(2));
+        assert_eq!(audio.channel_layout.as_deref(), Some("2 channels"));
+        assert_eq!(audio.bitrate_kbps, Some(1536));
+    }
+
+    #[test]
+    fn rotated_iphone_clip() {
+        let info = parse(ROTATED_IPHONE);
+        let video = &info.streams[0];
+        // Stored landscape, displayed portrait
+        assert_eq!((video.width, video.height), (Some(1920), Some(1080)));
+        assert_eq!(video.rotation, Some(270));
+        assert_eq!(video.fps, Some(29.97));
+        // Side data is attached to the stream above it only
+        assert_eq!(info.first_audio().unwrap().rotation, None);
+    }
+
+    #[test]
+    fn legacy_rotate_metadata() {
+        let stderr = ROTATED_IPHONE
+            .replace("      Side data:\n        displaymatrix: rotation of -90.00 degrees\n", "")
+            .replace("        encoder         : HEVC\n", "        rotate          : 90\n");
+        assert_eq!(parse(&stderr).streams[0].rotation, Some(90));
+    }
+
+    #[test]
+    fn only_the_first_input_is_read() {
+        let both = format!("{}{}", AUDIO_ONLY, H264_SDR.replace("Input #0", "Input #1"));
+        let info = parse(&both);
+        assert_eq!(info.streams.len(), 1);
+        assert_eq!(info.container.as_deref(), Some("wav"));
+    }
+
+    #[test]
+    fn unreadable_input_is_rejected() {
+        assert!(parse_probe_output("notes.txt: Invalid data found when processing input\n").is_none());
+        // A container without streams is no use either
+        assert!(parse_probe_output("Input #0, ffmetadata, from 'x':\n  Duration: N/A, bitrate: N/A\n").is_none());
+    }
+
+    #[test]
+    fn pix_fmt_variants() {
+        let parse_fmt = |part: &str| {
+            let mut stream = StreamInfo::default();
+            parse_pix_fmt(part, &mut stream);
+            stream
+        };
+
+        let hlg = parse_fmt("yuv420p10le(tv, bt2020nc/bt2020/arib-std-b67, progressive)");
+        assert_eq!(hlg.pix_fmt.as_deref(), Some("yuv420p10le"));
+        assert_eq!(hlg.color_transfer.as_deref(), Some("arib-std-b67"));
+
+        let jpeg = parse_fmt("yuvj420p(pc, bt470bg/unknown/unknown)");
+        assert_eq!(jpeg.color_range.as_deref(), Some("pc"));
+        assert_eq!(jpeg.color_space.as_deref(), Some("bt470bg"));
+        assert_eq!(jpeg.color_primaries.as_deref(), Some("unknown"));
+
+        let bare = parse_fmt("rgb24");
+        assert_eq!(bare.pix_fmt.as_deref(), Some("rgb24"));
+        assert_eq!((bare.color_range, bare.color_space), (None, None));
+    }
+
+    #[test]
+    fn stream_line_index_and_kind() {
+        let stream = parse_stream_line(
+            "Stream #0:3[0x4](eng): Subtitle: mov_text (tx3g / 0x67337874), 0 kb/s (default)",
+        )
+        .unwrap();
+        assert_eq!((stream.index, stream.kind.as_str(), stream.codec.as_str()), (3, "subtitle", "mov_text"));
+
+        // A language tag that looks like a kind doesn't confuse the split
+        let stream = parse_stream_line("Stream #0:12(Video): Audio: opus, 48000 Hz, 5.1, fltp").unwrap();
+        assert_eq!((stream.index, stream.kind.as_str()), (12, "audio"));
+        assert_eq!(stream.channels, Some(6));
+
+        assert!(parse_stream_line("Stream mapping:").is_none());
+    }
+}
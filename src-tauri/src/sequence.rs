@@ -0,0 +1,334 @@
+use image::imageops::{self, FilterType};
+use image::{DynamicImage, ImageFormat, RgbaImage};
+use serde::Deserialize;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{sync_channel, Receiver};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+/// File name pattern ffmpeg writes sequence frames with, and the frame-dir exports use
+pub const FRAME_PATTERN: &str = "frame_%05d.png";
+// Encoding a 4K PNG takes far longer than receiving it; more threads than this just fight
+// the renderer for cores
+const MAX_WRITERS: usize = 8;
+
+/// File type of an `image_sequence` export, written by Rust rather than ffmpeg
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SequenceFormat {
+    #[default]
+    Png,
+    // 16 bits per channel for compositing apps; the canvas itself is 8-bit
+    Png16,
+    Tiff,
+    // Lossless WebP
+    Webp,
+}
+
+impl SequenceFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            SequenceFormat::Png | SequenceFormat::Png16 => "png",
+            SequenceFormat::Tiff => "tif",
+            SequenceFormat::Webp => "webp",
+        }
+    }
+
+    /// Pseudo codec name for the disk space estimate
+    pub fn estimate_codec(self) -> &'static str {
+        match self {
+            SequenceFormat::Png => "png",
+            SequenceFormat::Png16 => "png16",
+            SequenceFormat::Tiff => "tiff",
+            SequenceFormat::Webp => "webp_lossless",
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct SequenceOptions {
+    #[serde(default)]
+    pub format: SequenceFormat,
+    // File name without extension, with one frame counter like "shot_%04d" (default "frame_%05d")
+    #[serde(default)]
+    pub pattern: Option<String>,
+    // Number of the first frame
+    #[serde(default)]
+    pub start_number: u32,
+    // Zip the frames into a single file on finish instead of leaving a folder
+    #[serde(default)]
+    pub zip: bool,
+}
+
+/// Parsed frame file name pattern: `prefix`, zero-padded frame number, `suffix`.`extension`
+#[derive(Clone, Debug)]
+pub struct FramePattern {
+    prefix: String,
+    width: usize,
+    suffix: String,
+    extension: &'static str,
+}
+
+impl Default for FramePattern {
+    fn default() -> Self {
+        FramePattern {
+            prefix: "frame_".to_string(),
+            width: 5,
+            suffix: String::new(),
+            extension: "png",
+        }
+    }
+}
+
+impl FramePattern {
+    /// Parse a printf-style pattern (`%d` or `%0Nd`, exactly once) for `format` files
+    pub fn parse(pattern: &str, format: SequenceFormat) -> Result<Self, String> {
+        if pattern.contains(['/', '\\']) || pattern.contains("..") {
+            return Err(format!("Frame pattern must be a plain file name: {}", pattern));
+        }
+        let invalid = || format!("Frame pattern needs one frame number like %05d: {}", pattern);
+        let start = pattern.find('%').ok_or_else(invalid)?;
+        let rest = &pattern[start + 1..];
+        let end = rest.find('d').ok_or_else(invalid)?;
+        let spec = &rest[..end];
+        if !spec.chars().all(|c| c.is_ascii_digit()) || (!spec.is_empty() && !spec.starts_with('0')) {
+            return Err(invalid());
+        }
+        let suffix = &rest[end + 1..];
+        if suffix.contains('%') {
+            return Err(invalid());
+        }
+        Ok(FramePattern {
+            prefix: pattern[..start].to_string(),
+            width: spec.parse::<usize>().unwrap_or(0).min(10),
+            suffix: suffix.to_string(),
+            extension: format.extension(),
+        })
+    }
+
+    /// Pattern an `image_sequence` session writes with (default "frame_%05d")
+    pub fn for_options(options: &SequenceOptions) -> Result<Self, String> {
+        Self::parse(options.pattern.as_deref().unwrap_or("frame_%05d"), options.format)
+    }
+
+    pub fn name(&self, index: u32) -> String {
+        format!("{}{:0width$}{}.{}", self.prefix, index, self.suffix, self.extension, width = self.width)
+    }
+
+    fn matches(&self, name: &str) -> bool {
+        let tail = format!("{}.{}", self.suffix, self.extension);
+        name.len() > self.prefix.len() + tail.len()
+            && name.starts_with(&self.prefix)
+            && name.ends_with(&tail)
+            && name[self.prefix.len()..name.len() - tail.len()].chars().all(|c| c.is_ascii_digit())
+    }
+}
+
+/// Zip the `frame_NNNNN.png` files of `dir` into `target`, in frame order. PNGs are already
+/// compressed, so entries are stored. Returns the number of frames written.
+pub fn zip_frames(dir: &Path, target: &Path) -> Result<u32, String> {
+    zip_sequence(dir, target, &FramePattern::default())
+}
+
+/// Zip the files of `dir` named by `pattern` into `target`, in frame order
+pub fn zip_sequence(dir: &Path, target: &Path, pattern: &FramePattern) -> Result<u32, String> {
+    let mut names: Vec<String> = std::fs::read_dir(dir)
+        .map_err(|e| format!("Failed to read frames dir: {e}"))?
+        .filter_map(|entry| {
+            let name = entry.ok()?.file_name().to_string_lossy().to_string();
+            pattern.matches(&name).then_some(name)
+        })
+        .collect();
+    if names.is_empty() {
+        return Err("No frames to zip".into());
+    }
+    // Numbers can outgrow the padding, so order by value rather than by name
+    names.sort_by_key(|name| {
+        name[pattern.prefix.len()..name.len() - pattern.suffix.len() - pattern.extension.len() - 1]
+            .parse::<u64>()
+            .unwrap_or(0)
+    });
+
+    let file = File::create(target).map_err(|e| format!("Failed to create zip: {e}"))?;
+    let mut zip = ZipWriter::new(BufWriter::new(file));
+    let options = SimpleFileOptions::default()
+        .compression_method(CompressionMethod::Stored)
+        .large_file(true);
+
+    for name in &names {
+        let data = std::fs::read(dir.join(name)).map_err(|e| format!("Failed to read {}: {e}", name))?;
+        zip.start_file(name.as_str(), options)
+            .map_err(|e| format!("Failed to add {} to zip: {e}", name))?;
+        zip.write_all(&data).map_err(|e| format!("Failed to write zip: {e}"))?;
+    }
+    zip.finish()
+        .map_err(|e| format!("Failed to finish zip: {e}"))?
+        .flush()
+        .map_err(|e| format!("Failed to flush zip: {e}"))?;
+    Ok(names.len() as u32)
+}
+
+/// Frame geometry for the sequence writer: RGBA frames arrive at `input_*` and are
+/// resized to `output_*` when they differ
+#[derive(Clone, Copy)]
+pub struct FrameSize {
+    pub input_width: u32,
+    pub input_height: u32,
+    pub output_width: u32,
+    pub output_height: u32,
+}
+
+fn write_frame(data: Vec<u8>, size: FrameSize, format: SequenceFormat, path: &Path) -> Result<(), String> {
+    let mut frame = RgbaImage::from_raw(size.input_width, size.input_height, data)
+        .ok_or("Frame data does not match the input size")?;
+    if (size.input_width, size.input_height) != (size.output_width, size.output_height) {
+        frame = imageops::resize(&frame, size.output_width, size.output_height, FilterType::CatmullRom);
+    }
+    let (image, image_format) = match format {
+        SequenceFormat::Png => (DynamicImage::ImageRgba8(frame), ImageFormat::Png),
+        SequenceFormat::Png16 => (
+            DynamicImage::ImageRgba16(DynamicImage::ImageRgba8(frame).to_rgba16()),
+            ImageFormat::Png,
+        ),
+        SequenceFormat::Tiff => (DynamicImage::ImageRgba8(frame), ImageFormat::Tiff),
+        SequenceFormat::Webp => (DynamicImage::ImageRgba8(frame), ImageFormat::WebP),
+    };
+    image
+        .save_with_format(path, image_format)
+        .map_err(|e| format!("Failed to write {}: {e}", path.display()))
+}
+
+/// Writer thread for an image-sequence session. Takes frames from `rx` like the ffmpeg stdin
+/// writer does and encodes them on a small pool, numbering from `options.start_number`.
+/// The first failure is stored in `last_error` and stops the session.
+pub fn spawn_writer(
+    rx: Receiver<Option<Vec<u8>>>,
+    dir: PathBuf,
+    pattern: FramePattern,
+    options: SequenceOptions,
+    size: FrameSize,
+    last_error: Arc<Mutex<Option<String>>>,
+) -> thread::JoinHandle<Result<(), String>> {
+    thread::spawn(move || {
+        let writers = thread::available_parallelism()
+            .map(|n| n.get().saturating_sub(1))
+            .unwrap_or(2)
+            .clamp(1, MAX_WRITERS);
+        // Bounded so a slow disk pushes back on send_frame instead of buffering frames in memory
+        let (job_tx, job_rx) = sync_channel::<(u32, Vec<u8>)>(writers * 2);
+        let job_rx = Arc::new(Mutex::new(job_rx));
+
+        let handles: Vec<_> = (0..writers)
+            .map(|_| {
+                let job_rx = job_rx.clone();
+                let dir = dir.clone();
+                let pattern = pattern.clone();
+                let last_error = last_error.clone();
+                let format = options.format;
+                thread::spawn(move || loop {
+                    let job = job_rx.lock().unwrap().recv();
+                    let Ok((index, data)) = job else { break };
+                    if let Err(e) = write_frame(data, size, format, &dir.join(pattern.name(index))) {
+                        log::error!("[Sequence] {}", e);
+                        last_error.lock().unwrap().get_or_insert(e);
+                        break;
+                    }
+                })
+            })
+            .collect();
+        drop(job_rx);
+
+        let mut index = options.start_number;
+        while let Ok(Some(frame)) = rx.recv() {
+            if last_error.lock().unwrap().is_some() || job_tx.send((index, frame)).is_err() {
+                break;
+            }
+            index += 1;
+        }
+        drop(job_tx);
+        for handle in handles {
+            let _ = handle.join();
+        }
+
+        match last_error.lock().unwrap().clone() {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_padded_and_plain_counters() {
+        let padded = FramePattern::parse("shot_%04d", SequenceFormat::Tiff).unwrap();
+        assert_eq!(padded.name(7), "shot_0007.tif");
+        // Numbers wider than the padding are written in full
+        assert_eq!(padded.name(123_456), "shot_123456.tif");
+
+        let plain = FramePattern::parse("%d_final", SequenceFormat::Webp).unwrap();
+        assert_eq!(plain.name(42), "42_final.webp");
+
+        assert_eq!(FramePattern::default().name(1), "frame_00001.png");
+        let defaults = FramePattern::for_options(&SequenceOptions::default()).unwrap();
+        assert_eq!(defaults.name(1), FramePattern::default().name(1));
+    }
+
+    #[test]
+    fn rejects_bad_patterns() {
+        for pattern in [
+            "frame",           // no counter
+            "frame_%05",       // no conversion
+            "frame_%5d",       // space padding would put blanks in file names
+            "frame_%-5d",      // flags
+            "frame_%05d_%03d", // two counters
+            "frame_%s",        // not a number
+            "../frame_%05d",   // escapes the output folder
+            "sub/frame_%05d",
+            "sub\\frame_%05d",
+        ] {
+            assert!(FramePattern::parse(pattern, SequenceFormat::Png).is_err(), "{pattern}");
+        }
+    }
+
+    #[test]
+    fn matches_only_its_own_frames() {
+        let pattern = FramePattern::parse("shot_%03d_v2", SequenceFormat::Png).unwrap();
+        assert!(pattern.matches("shot_001_v2.png"));
+        assert!(pattern.matches("shot_1234_v2.png"));
+        assert!(!pattern.matches("shot__v2.png"));
+        assert!(!pattern.matches("shot_001_v2.tif"));
+        assert!(!pattern.matches("shot_001_v3.png"));
+        assert!(!pattern.matches("shot_0a1_v2.png"));
+        assert!(!pattern.matches("take_001_v2.png"));
+    }
+
+    #[test]
+    fn zips_frames_in_numeric_order() {
+        let workspace = crate::temp_workspace::create(Some("sequence_test")).unwrap();
+        let dir = PathBuf::from(&workspace.path);
+        let pattern = FramePattern::parse("f%02d", SequenceFormat::Png).unwrap();
+        for index in [100, 2, 10] {
+            std::fs::write(dir.join(pattern.name(index)), [index as u8]).unwrap();
+        }
+        std::fs::write(dir.join("notes.txt"), b"ignored").unwrap();
+        let target = dir.join("frames.zip");
+
+        assert_eq!(zip_sequence(&dir, &target, &pattern).unwrap(), 3);
+        let mut archive = zip::ZipArchive::new(File::open(&target).unwrap()).unwrap();
+        let names: Vec<String> = (0..archive.len())
+            .map(|i| archive.by_index(i).unwrap().name().to_string())
+            .collect();
+        assert_eq!(names, ["f02.png", "f10.png", "f100.png"]);
+
+        assert!(zip_sequence(&dir, &target, &FramePattern::default()).is_err());
+        crate::temp_workspace::release(&workspace.job_id).unwrap();
+    }
+}
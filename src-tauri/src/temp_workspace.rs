@@ -0,0 +1,253 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+// Job folders older than this are swept on startup even if their owner looks alive
+const STALE_AFTER: Duration = Duration::from_secs(24 * 60 * 60);
+// Written into each job folder so other app instances can tell who owns it
+const OWNER_FILE: &str = ".owner";
+
+#[derive(Serialize, Clone, Debug)]
+pub struct TempWorkspace {
+    pub job_id: String,
+    pub path: String,
+}
+
+// Live jobs created by this process: job id -> folder
+lazy_static::lazy_static! {
+    static ref JOBS: Mutex<HashMap<String, PathBuf>> = Mutex::new(HashMap::new());
+}
+
+/// App-owned root every temp job folder lives under
+pub fn root() -> PathBuf {
+    std::env::temp_dir().join("liike-jobs")
+}
+
+fn sanitize_label(label: &str) -> String {
+    let cleaned: String = label
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .take(32)
+        .collect();
+    if cleaned.is_empty() {
+        "job".to_string()
+    } else {
+        cleaned
+    }
+}
+
+/// Create a fresh per-job folder and register it as live
+pub fn create(label: Option<&str>) -> Result<TempWorkspace, String> {
+    let millis = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    let job_id = format!("{}_{}_{}", sanitize_label(label.unwrap_or("job")), std::process::id(), millis);
+    let path = root().join(&job_id);
+
+    // Register before touching disk so a concurrent sweep never sees an unowned folder
+    JOBS.lock()
+        .map_err(|e| format!("Failed to lock temp workspaces: {e}"))?
+        .insert(job_id.clone(), path.clone());
+
+    let created = std::fs::create_dir_all(&path)
+        .map_err(|e| format!("Failed to create temp workspace: {e}"))
+        .and_then(|_| {
+            std::fs::write(path.join(OWNER_FILE), std::process::id().to_string())
+                .map_err(|e| format!("Failed to write temp workspace owner: {e}"))
+        });
+    if let Err(e) = created {
+        if let Ok(mut jobs) = JOBS.lock() {
+            jobs.remove(&job_id);
+        }
+        return Err(e);
+    }
+
+    log::info!("[TempWorkspace] Created {}", path.display());
+    Ok(TempWorkspace {
+        job_id,
+        path: path.to_string_lossy().to_string(),
+    })
+}
+
+/// Delete a job folder and forget it
+pub fn release(job_id: &str) -> Result<(), String> {
+    let path = JOBS
+        .lock()
+        .map_err(|e| format!("Failed to lock temp workspaces: {e}"))?
+        .remove(job_id);
+
+    match path {
+        Some(path) => remove_job_dir(&path),
+        None => Err(format!("Temp workspace not found: {}", job_id)),
+    }
+}
+
+/// Release the job owning `path` if it is a managed workspace; no-op for other paths
+pub fn release_path(path: &Path) {
+    let Ok(canonical) = path.canonicalize() else { return };
+    let job_id = JOBS.lock().ok().and_then(|jobs| {
+        jobs.iter()
+            .find(|(_, dir)| dir.canonicalize().map(|d| d == canonical).unwrap_or(false))
+            .map(|(id, _)| id.clone())
+    });
+    if let Some(job_id) = job_id {
+        if let Err(e) = release(&job_id) {
+            log::warn!("[TempWorkspace] {}", e);
+        }
+    }
+}
+
+/// Canonicalize `path` and make sure it is strictly inside the workspace root.
+/// Canonicalizing both sides resolves `..` segments and symlinks before comparing.
+pub fn resolve_inside_root(path: &Path) -> Result<PathBuf, String> {
+    let root = root()
+        .canonicalize()
+        .map_err(|e| format!("Temp workspace root unavailable: {e}"))?;
+    let canonical = path
+        .canonicalize()
+        .map_err(|e| format!("Invalid temp path {}: {e}", path.display()))?;
+
+    if canonical == root || !canonical.starts_with(&root) {
+        return Err(format!("Refusing to touch path outside temp workspace: {}", path.display()));
+    }
+    Ok(canonical)
+}
+
+fn remove_job_dir(path: &Path) -> Result<(), String> {
+    if !path.exists() {
+        return Ok(());
+    }
+    let canonical = resolve_inside_root(path)?;
+    std::fs::remove_dir_all(&canonical).map_err(|e| format!("Failed to remove temp workspace: {e}"))?;
+    log::info!("[TempWorkspace] Removed {}", canonical.display());
+    Ok(())
+}
+
+/// Remove any path inside the root (job folder or something nested in one)
+pub fn remove_inside_root(path: &Path) -> Result<(), String> {
+    let canonical = resolve_inside_root(path)?;
+
+    // Keep the registry in sync when a whole job folder goes away
+    if let Ok(mut jobs) = JOBS.lock() {
+        jobs.retain(|_, dir| dir.canonicalize().map(|d| d != canonical).unwrap_or(false));
+    }
+
+    if canonical.is_dir() {
+        std::fs::remove_dir_all(&canonical)
+    } else {
+        std::fs::remove_file(&canonical)
+    }
+    .map_err(|e| format!("Failed to cleanup temp dir: {e}"))
+}
+
+#[cfg(unix)]
+fn process_alive(pid: u32) -> bool {
+    // Signal 0 only checks for existence; EPERM still means the process is there
+    let result = unsafe { libc::kill(pid as libc::pid_t, 0) };
+    result == 0 || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+#[cfg(windows)]
+fn process_alive(pid: u32) -> bool {
+    use windows_sys::Win32::Foundation::{CloseHandle, GetLastError, ERROR_ACCESS_DENIED, STILL_ACTIVE};
+    use windows_sys::Win32::System::Threading::{GetExitCodeProcess, OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION};
+
+    unsafe {
+        let handle = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+        if handle.is_null() {
+            // Access denied means it exists but belongs to someone else; anything else means gone
+            return GetLastError() == ERROR_ACCESS_DENIED;
+        }
+        let mut exit_code = 0u32;
+        let queried = GetExitCodeProcess(handle, &mut exit_code);
+        CloseHandle(handle);
+        // A handle can outlive the process; only STILL_ACTIVE means it is running.
+        // If the query itself fails, keep the folder and let the age threshold decide.
+        queried == 0 || exit_code == STILL_ACTIVE as u32
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn process_alive(_pid: u32) -> bool {
+    // No cheap liveness check here - fall back to the age threshold alone
+    true
+}
+
+fn owner_pid(dir: &Path) -> Option<u32> {
+    std::fs::read_to_string(dir.join(OWNER_FILE)).ok()?.trim().parse().ok()
+}
+
+/// Startup janitor: delete job folders that are too old or whose owning process is gone.
+/// Folders owned by live jobs of this process are always kept.
+pub fn sweep_stale() {
+    let root = root();
+    let Ok(entries) = std::fs::read_dir(&root) else { return };
+    let now = SystemTime::now();
+    let own_pid = std::process::id();
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        // Checked per entry (not snapshotted) so jobs created mid-sweep are never touched
+        let is_live = JOBS
+            .lock()
+            .map(|jobs| jobs.values().any(|dir| dir == &path))
+            .unwrap_or(true);
+        if !path.is_dir() || is_live {
+            continue;
+        }
+
+        let age = entry
+            .metadata()
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|modified| now.duration_since(modified).ok())
+            .unwrap_or(Duration::ZERO);
+        let orphaned = match owner_pid(&path) {
+            Some(pid) => pid == own_pid || !process_alive(pid),
+            // Owner file missing: crashed between mkdir and writing it. Give other
+            // instances a moment in case they are in that window right now.
+            None => age > Duration::from_secs(60),
+        };
+
+        if age > STALE_AFTER || orphaned {
+            match remove_job_dir(&path) {
+                Ok(()) => log::info!("[TempWorkspace] Swept stale folder {}", path.display()),
+                Err(e) => log::warn!("[TempWorkspace] Failed to sweep {}: {}", path.display(), e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn own_process_is_alive() {
+        assert!(process_alive(std::process::id()));
+    }
+
+    #[test]
+    fn labels_are_safe_folder_names() {
+        assert_eq!(sanitize_label("image_sequence"), "image_sequence");
+        assert_eq!(sanitize_label("../gif budget"), "___gif_budget");
+        assert_eq!(sanitize_label(""), "job");
+        assert_eq!(sanitize_label(&"a".repeat(64)).len(), 32);
+    }
+
+    #[test]
+    fn workspace_round_trip() {
+        let workspace = create(Some("test")).unwrap();
+        let path = PathBuf::from(&workspace.path);
+        assert_eq!(owner_pid(&path), Some(std::process::id()));
+        assert!(resolve_inside_root(&path).is_ok());
+        assert!(resolve_inside_root(&root()).is_err());
+
+        release(&workspace.job_id).unwrap();
+        assert!(!path.exists());
+        assert!(release(&workspace.job_id).is_err());
+    }
+}
@@ -0,0 +1,8 @@
+// Helpers shared by the unit tests of the ffmpeg argument builders
+
+/// Value following `flag` in an ffmpeg arg list; the last one wins, like ffmpeg itself
+pub fn value_of<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+    args.iter()
+        .rposition(|arg| arg == flag)
+        .map(|i| args[i + 1].as_str())
+}
@@ -0,0 +1,188 @@
+use serde::{Deserialize, Serialize};
+
+use crate::ffmpeg_caps;
+
+// Software defaults tuned for marketing clips: visually close to the H.264 crf 21 export
+// at roughly half (HEVC) to a third (AV1) of the size
+const X265_CRF: u32 = 24;
+const X265_PRESET: &str = "medium";
+const SVTAV1_CRF: u32 = 32;
+const SVTAV1_PRESET: u32 = 8;
+const AOM_CRF: u32 = 32;
+const AOM_CPU_USED: u32 = 6;
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum VideoCodec {
+    /// Whatever the format always used (H.264 for mp4/mkv/mov, VP9 for webm)
+    #[default]
+    Auto,
+    H264,
+    Hevc,
+    Av1,
+}
+
+// AV1 via SVT-AV1 when available (much faster), libaom otherwise
+fn av1_encoder() -> Option<&'static str> {
+    ["libsvtav1", "libaom-av1"]
+        .into_iter()
+        .find(|name| ffmpeg_caps::has_encoder(name))
+}
+
+/// Pick the encoder for an explicit codec choice. `None` keeps the format's existing
+/// encoder path (including hardware H.264); errors when the container can't hold the
+/// codec or the bundled ffmpeg lacks an encoder for it.
+pub fn resolve(format: &str, codec: VideoCodec) -> Result<Option<&'static str>, String> {
+    match (format, codec) {
+        (_, VideoCodec::Auto) => Ok(None),
+        ("mp4" | "mkv", VideoCodec::H264) => Ok(None),
+        ("mp4" | "mkv", VideoCodec::Hevc) => {
+            if ffmpeg_caps::has_encoder("libx265") {
+                Ok(Some("libx265"))
+            } else {
+                Err("The bundled ffmpeg has no HEVC encoder (libx265)".into())
+            }
+        }
+        ("mp4" | "mkv" | "webm", VideoCodec::Av1) => av1_encoder()
+            .map(Some)
+            .ok_or_else(|| "The bundled ffmpeg has no AV1 encoder (libsvtav1 or libaom-av1)".to_string()),
+        (_, codec) => Err(format!("{:?} video is not supported in {}", codec, format)),
+    }
+}
+
+/// Encoders an explicit codec choice can use with this ffmpeg, for the export menu
+pub fn available() -> Vec<VideoCodec> {
+    let mut codecs = vec![VideoCodec::Auto, VideoCodec::H264];
+    if ffmpeg_caps::has_encoder("libx265") {
+        codecs.push(VideoCodec::Hevc);
+    }
+    if av1_encoder().is_some() {
+        codecs.push(VideoCodec::Av1);
+    }
+    codecs
+}
+
+/// CRF the encoder is run at, for size estimates
+pub fn default_quality(encoder: &str) -> f64 {
+    match encoder {
+        "libx265" => X265_CRF as f64,
+        "libsvtav1" => SVTAV1_CRF as f64,
+        _ => AOM_CRF as f64,
+    }
+}
+
+/// Video encoding args for an encoder picked by `resolve`; colour tags are added by the caller
+pub fn video_args(encoder: &str, format: &str, scale_filter: String) -> Vec<String> {
+    let mut args = vec!["-vf".to_string(), scale_filter, "-c:v".to_string(), encoder.to_string()];
+    match encoder {
+        "libx265" => {
+            args.extend([
+                "-preset".to_string(),
+                X265_PRESET.to_string(),
+                "-crf".to_string(),
+                X265_CRF.to_string(),
+                "-x265-params".to_string(),
+                "log-level=error".to_string(),
+            ]);
+            // QuickTime and Safari only play HEVC in mp4 when tagged hvc1 (ffmpeg defaults to hev1)
+            if format == "mp4" {
+                args.extend(["-tag:v".to_string(), "hvc1".to_string()]);
+            }
+        }
+        "libsvtav1" => args.extend([
+            "-preset".to_string(),
+            SVTAV1_PRESET.to_string(),
+            "-crf".to_string(),
+            SVTAV1_CRF.to_string(),
+        ]),
+        _ => args.extend([
+            "-crf".to_string(),
+            AOM_CRF.to_string(),
+            "-b:v".to_string(),
+            "0".to_string(),
+            "-cpu-used".to_string(),
+            AOM_CPU_USED.to_string(),
+            "-row-mt".to_string(),
+            "1".to_string(),
+            "-tiles".to_string(),
+            "2x2".to_string(),
+        ]),
+    }
+    args.extend(["-pix_fmt".to_string(), "yuv420p".to_string()]);
+    if format == "mp4" {
+        args.extend(["-movflags".to_string(), "+faststart".to_string()]);
+    }
+    args
+}
+
+/// Drop the mp4-only muxer flags from the H.264 args so they can go into Matroska,
+/// which rejects unknown muxer options
+pub fn matroska_args(args: Vec<String>) -> Vec<String> {
+    let mut out = Vec::with_capacity(args.len());
+    let mut iter = args.into_iter();
+    while let Some(arg) = iter.next() {
+        if arg == "-movflags" || arg == "-tag:v" {
+            iter.next();
+            continue;
+        }
+        out.push(arg);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::value_of;
+
+    #[test]
+    fn auto_and_h264_keep_the_existing_path() {
+        assert_eq!(resolve("webm", VideoCodec::Auto), Ok(None));
+        assert_eq!(resolve("mp4", VideoCodec::H264), Ok(None));
+        assert_eq!(resolve("mkv", VideoCodec::H264), Ok(None));
+    }
+
+    #[test]
+    fn containers_refuse_codecs_they_cannot_hold() {
+        assert_eq!(
+            resolve("webm", VideoCodec::Hevc),
+            Err("Hevc video is not supported in webm".to_string())
+        );
+        assert!(resolve("webm", VideoCodec::H264).is_err());
+        assert!(resolve("mov", VideoCodec::Av1).is_err());
+        assert!(resolve("gif", VideoCodec::Hevc).is_err());
+    }
+
+    #[test]
+    fn hevc_in_mp4_is_tagged_hvc1() {
+        let mp4 = video_args("libx265", "mp4", "scale=2:2".into());
+        assert_eq!(value_of(&mp4, "-tag:v"), Some("hvc1"));
+        assert_eq!(value_of(&mp4, "-crf"), Some("24"));
+        assert_eq!(value_of(&mp4, "-movflags"), Some("+faststart"));
+
+        let mkv = video_args("libx265", "mkv", "scale=2:2".into());
+        assert_eq!(value_of(&mkv, "-tag:v"), None);
+        assert_eq!(value_of(&mkv, "-movflags"), None);
+    }
+
+    #[test]
+    fn av1_encoders_get_their_own_rate_control() {
+        let svt = video_args("libsvtav1", "webm", "scale=2:2".into());
+        assert_eq!(value_of(&svt, "-preset"), Some("8"));
+        assert_eq!(value_of(&svt, "-b:v"), None);
+        let aom = video_args("libaom-av1", "webm", "scale=2:2".into());
+        assert_eq!(value_of(&aom, "-b:v"), Some("0"));
+        assert_eq!(value_of(&aom, "-cpu-used"), Some("6"));
+        assert_eq!(default_quality("libsvtav1"), 32.0);
+    }
+
+    #[test]
+    fn matroska_args_drop_mp4_muxer_flags() {
+        let args = video_args("libx265", "mp4", "scale=2:2".into());
+        let mkv = matroska_args(args.clone());
+        assert_eq!(value_of(&mkv, "-tag:v"), None);
+        assert_eq!(value_of(&mkv, "-movflags"), None);
+        assert_eq!(mkv.len(), args.len() - 4);
+        assert_eq!(value_of(&mkv, "-pix_fmt"), Some("yuv420p"));
+    }
+}
@@ -0,0 +1,328 @@
+use serde::Serialize;
+use std::io::{BufReader, Read};
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use crate::{media_cache, probe};
+
+// Mono 16 kHz is plenty for drawing peaks and keeps decode output small
+const SAMPLE_RATE: u32 = 16_000;
+const MAX_BUCKETS: u32 = 200_000;
+// Coarsest mip level still worth keeping
+const MIN_LEVEL_PEAKS: usize = 64;
+const FILE_MAGIC: &[u8; 4] = b"LWF1";
+
+#[derive(Serialize, Clone, Debug)]
+pub struct WaveformLevel {
+    pub samples_per_peak: u32,
+    pub count: u32,
+    // Interleaved [min, max, rms] per peak, scaled to i16 range
+    pub peaks: Vec<i16>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct Waveform {
+    pub key: String,
+    pub sample_rate: u32,
+    pub duration_ms: u64,
+    // Level 0 is the finest; each next level halves the resolution
+    pub levels: Vec<WaveformLevel>,
+}
+
+#[derive(Clone, Copy)]
+struct Peak {
+    min: f32,
+    max: f32,
+    sum_sq: f64,
+    samples: u32,
+}
+
+impl Peak {
+    fn empty() -> Self {
+        Peak { min: 0.0, max: 0.0, sum_sq: 0.0, samples: 0 }
+    }
+
+    fn push(&mut self, sample: f32) {
+        if self.samples == 0 {
+            self.min = sample;
+            self.max = sample;
+        } else {
+            self.min = self.min.min(sample);
+            self.max = self.max.max(sample);
+        }
+        self.sum_sq += (sample as f64) * (sample as f64);
+        self.samples += 1;
+    }
+
+    fn merge(a: &Peak, b: &Peak) -> Peak {
+        if b.samples == 0 {
+            return *a;
+        }
+        Peak {
+            min: a.min.min(b.min),
+            max: a.max.max(b.max),
+            sum_sq: a.sum_sq + b.sum_sq,
+            samples: a.samples + b.samples,
+        }
+    }
+
+    fn rms(&self) -> f32 {
+        if self.samples == 0 {
+            0.0
+        } else {
+            (self.sum_sq / self.samples as f64).sqrt() as f32
+        }
+    }
+}
+
+fn to_i16(v: f32) -> i16 {
+    (v.clamp(-1.0, 1.0) * i16::MAX as f32).round() as i16
+}
+
+fn encode_level(samples_per_peak: u32, peaks: &[Peak]) -> WaveformLevel {
+    let mut data = Vec::with_capacity(peaks.len() * 3);
+    for p in peaks {
+        data.push(to_i16(p.min));
+        data.push(to_i16(p.max));
+        data.push(to_i16(p.rms()));
+    }
+    WaveformLevel {
+        samples_per_peak,
+        count: peaks.len() as u32,
+        peaks: data,
+    }
+}
+
+// Build the mip chain by pairwise merging until the level gets too coarse to be useful
+fn build_levels(base_samples_per_peak: u32, base: Vec<Peak>) -> Vec<WaveformLevel> {
+    let mut levels = vec![encode_level(base_samples_per_peak, &base)];
+    let mut current = base;
+    let mut samples_per_peak = base_samples_per_peak;
+    while current.len() / 2 >= MIN_LEVEL_PEAKS {
+        current = current
+            .chunks(2)
+            .map(|pair| Peak::merge(&pair[0], pair.get(1).unwrap_or(&Peak::empty())))
+            .collect();
+        samples_per_peak *= 2;
+        levels.push(encode_level(samples_per_peak, &current));
+    }
+    levels
+}
+
+fn serialize(waveform: &Waveform) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(FILE_MAGIC);
+    out.extend_from_slice(&waveform.sample_rate.to_le_bytes());
+    out.extend_from_slice(&waveform.duration_ms.to_le_bytes());
+    out.extend_from_slice(&(waveform.levels.len() as u32).to_le_bytes());
+    for level in &waveform.levels {
+        out.extend_from_slice(&level.samples_per_peak.to_le_bytes());
+        out.extend_from_slice(&level.count.to_le_bytes());
+        for v in &level.peaks {
+            out.extend_from_slice(&v.to_le_bytes());
+        }
+    }
+    out
+}
+
+fn deserialize(key: &str, bytes: &[u8]) -> Option<Waveform> {
+    let mut pos = 0usize;
+    let mut take = |n: usize| -> Option<&[u8]> {
+        let slice = bytes.get(pos..pos + n)?;
+        pos += n;
+        Some(slice)
+    };
+    if take(4)? != FILE_MAGIC {
+        return None;
+    }
+    let sample_rate = u32::from_le_bytes(take(4)?.try_into().ok()?);
+    let duration_ms = u64::from_le_bytes(take(8)?.try_into().ok()?);
+    let level_count = u32::from_le_bytes(take(4)?.try_into().ok()?);
+    let mut levels = Vec::with_capacity(level_count as usize);
+    for _ in 0..level_count {
+        let samples_per_peak = u32::from_le_bytes(take(4)?.try_into().ok()?);
+        let count = u32::from_le_bytes(take(4)?.try_into().ok()?);
+        let raw = take(count as usize * 3 * 2)?;
+        let peaks = raw
+            .chunks_exact(2)
+            .map(|b| i16::from_le_bytes([b[0], b[1]]))
+            .collect();
+        levels.push(WaveformLevel { samples_per_peak, count, peaks });
+    }
+    Some(Waveform {
+        key: key.to_string(),
+        sample_rate,
+        duration_ms,
+        levels,
+    })
+}
+
+// Samples folded into each base peak so `total_samples` spreads over about `buckets` peaks
+fn samples_per_peak(total_samples: u64, buckets: u32) -> u32 {
+    (total_samples / buckets as u64).clamp(1, u32::MAX as u64) as u32
+}
+
+/// Peaks for an audio (or video) file at `buckets` resolution plus coarser mip levels.
+/// Cached on disk by content hash, so reopening a project doesn't decode again.
+pub fn compute(app: &tauri::AppHandle, source: &Path, buckets: u32) -> Result<Waveform, String> {
+    let buckets = buckets.clamp(MIN_LEVEL_PEAKS as u32, MAX_BUCKETS);
+    let key = format!("{}_{}", media_cache::content_key(source)?, buckets);
+    let cache_path = media_cache::cache_dir(app, "waveforms")?.join(format!("{}.bin", key));
+
+    if let Ok(bytes) = std::fs::read(&cache_path) {
+        if let Some(waveform) = deserialize(&key, &bytes) {
+            return Ok(waveform);
+        }
+        log::warn!("[Waveform] Ignoring corrupt cache entry {}", cache_path.display());
+    }
+
+    let info = probe::probe(source)?;
+    if !info.has_audio() {
+        return Err("No audio stream".into());
+    }
+    let probed_ms = info.duration_ms.filter(|&ms| ms > 0);
+    let expected_samples = match probed_ms {
+        Some(ms) => ms * SAMPLE_RATE as u64 / 1000,
+        // No duration in the container (e.g. raw streams): count the samples with a decode pass
+        // first, or every sample would end up a peak of its own
+        None => {
+            log::info!("[Waveform] No duration for {}, counting samples", source.display());
+            decode_peaks(source, u32::MAX)?.first().map_or(0, |p| p.samples as u64)
+        }
+    };
+    if expected_samples == 0 {
+        return Err("Audio decoded to no samples".into());
+    }
+    let duration_ms = probed_ms.unwrap_or(expected_samples * 1000 / SAMPLE_RATE as u64);
+    let samples_per_peak = samples_per_peak(expected_samples, buckets);
+
+    let base = decode_peaks(source, samples_per_peak)?;
+    if base.is_empty() {
+        return Err("Audio decoded to no samples".into());
+    }
+
+    let waveform = Waveform {
+        key,
+        sample_rate: SAMPLE_RATE,
+        duration_ms,
+        levels: build_levels(samples_per_peak, base),
+    };
+    if let Err(e) = media_cache::write_atomic(&cache_path, &serialize(&waveform)) {
+        log::warn!("[Waveform] {}", e);
+    }
+    Ok(waveform)
+}
+
+/// Decode mono f32 PCM from ffmpeg's stdout and fold it into peaks as it streams in
+fn decode_peaks(source: &Path, samples_per_peak: u32) -> Result<Vec<Peak>, String> {
+    let ffmpeg_path = crate::get_ffmpeg_path()?;
+    let mut child = Command::new(&ffmpeg_path)
+        .args(["-hide_banner", "-nostdin", "-loglevel", "error", "-i"])
+        .arg(source)
+        .args(["-vn", "-ac", "1", "-ar"])
+        .arg(SAMPLE_RATE.to_string())
+        .args(["-f", "f32le", "pipe:1"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Failed to spawn ffmpeg: {e}"))?;
+
+    let stdout = child.stdout.take().ok_or("Failed to get ffmpeg stdout")?;
+    let mut reader = BufReader::with_capacity(256 * 1024, stdout);
+    let mut peaks = Vec::new();
+    let mut current = Peak::empty();
+    let mut buf = [0u8; 64 * 1024];
+    let mut carry: Vec<u8> = Vec::with_capacity(4);
+
+    loop {
+        let n = reader.read(&mut buf).map_err(|e| format!("Failed to read ffmpeg output: {e}"))?;
+        if n == 0 {
+            break;
+        }
+        // Reads don't line up with 4-byte samples; keep the leftover bytes for the next round
+        carry.extend_from_slice(&buf[..n]);
+        let whole = carry.len() / 4 * 4;
+        for chunk in carry[..whole].chunks_exact(4) {
+            current.push(f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]));
+            if current.samples >= samples_per_peak {
+                peaks.push(current);
+                current = Peak::empty();
+            }
+        }
+        carry.drain(..whole);
+    }
+    if current.samples > 0 {
+        peaks.push(current);
+    }
+
+    let status = child.wait().map_err(|e| format!("Failed to wait for ffmpeg: {e}"))?;
+    if !status.success() {
+        return Err(format!("ffmpeg failed to decode audio: {}", status));
+    }
+    Ok(peaks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn peak(samples: &[f32]) -> Peak {
+        let mut peak = Peak::empty();
+        for &sample in samples {
+            peak.push(sample);
+        }
+        peak
+    }
+
+    #[test]
+    fn peaks_track_min_max_and_rms() {
+        let p = peak(&[0.5, -0.25, 0.5, -0.5]);
+        assert_eq!((p.min, p.max), (-0.5, 0.5));
+        assert!((p.rms() - 0.451).abs() < 0.001, "{}", p.rms());
+        // A peak of only positive samples doesn't drag min down to zero
+        assert_eq!(peak(&[0.2, 0.4]).min, 0.2);
+        assert_eq!(Peak::empty().rms(), 0.0);
+        assert_eq!(to_i16(2.0), i16::MAX);
+    }
+
+    #[test]
+    fn levels_halve_down_to_the_coarsest_useful_one() {
+        let base: Vec<Peak> = (0..300).map(|i| peak(&[i as f32 / 300.0])).collect();
+        let levels = build_levels(100, base);
+        let counts: Vec<u32> = levels.iter().map(|l| l.count).collect();
+        assert_eq!(counts, [300, 150, 75]);
+        assert_eq!(levels[2].samples_per_peak, 400);
+        assert!(levels.iter().all(|l| l.peaks.len() == l.count as usize * 3));
+        // Merged peaks keep the extremes of both halves
+        assert_eq!(levels[1].peaks[1], to_i16(1.0 / 300.0));
+    }
+
+    #[test]
+    fn sample_count_is_spread_over_the_buckets() {
+        // 60 s at 16 kHz into 1000 buckets
+        assert_eq!(samples_per_peak(960_000, 1000), 960);
+        assert_eq!(samples_per_peak(500, 1000), 1);
+        assert_eq!(samples_per_peak(u64::MAX, MIN_LEVEL_PEAKS as u32), u32::MAX);
+    }
+
+    #[test]
+    fn cache_file_round_trips() {
+        let waveform = Waveform {
+            key: "k".to_string(),
+            sample_rate: SAMPLE_RATE,
+            duration_ms: 1234,
+            levels: build_levels(10, (0..130).map(|i| peak(&[-(i as f32) / 200.0, 0.3])).collect()),
+        };
+        let bytes = serialize(&waveform);
+        let read = deserialize("k", &bytes).unwrap();
+        assert_eq!((read.sample_rate, read.duration_ms), (SAMPLE_RATE, 1234));
+        assert_eq!(read.levels.len(), waveform.levels.len());
+        for (a, b) in read.levels.iter().zip(&waveform.levels) {
+            assert_eq!((a.samples_per_peak, a.count, &a.peaks), (b.samples_per_peak, b.count, &b.peaks));
+        }
+
+        assert!(deserialize("k", &bytes[..bytes.len() - 1]).is_none());
+        assert!(deserialize("k", b"XXXX").is_none());
+    }
+}
@@ -126,6 +126,26 @@ const App = () => {
           </div>
         </div>
       )}
+      {!renderStatus.error && renderStatus.warnings && renderStatus.warnings.length > 0 && (
+        <div className="fixed inset-0 z-[999] bg-black/70 backdrop-blur-sm flex items-center justify-center">
+          <div className="w-full max-w-lg bg-ui-panel border border-ui-border rounded-xl p-5 shadow-2xl">
+            <div className="text-sm font-bold text-amber-400">Export Finished With Warnings</div>
+            <ul className="mt-2 space-y-1 text-sm text-white/90">
+              {renderStatus.warnings.map((warning) => (
+                <li key={warning}>{warning}</li>
+              ))}
+            </ul>
+            <div className="mt-4 flex justify-end gap-2">
+              <button
+                onClick={() => setRenderStatus({ warnings: [] })}
+                className="px-3 py-1.5 rounded-md bg-ui-highlight/40 text-white text-xs hover:bg-ui-highlight/60"
+              >
+                Close
+              </button>
+            </div>
+          </div>
+        </div>
+      )}
       {/* Header - spans all columns */}
       <Header onRender={handleRender} />
 
@@ -0,0 +1,133 @@
+import { Repeat } from 'lucide-react';
+import { useTimelineStore } from '../store/timelineStore';
+import type { TimelineClip } from '../store/timelineStore';
+import { SliderControl } from './ui/SliderControl';
+
+// Longest overlap offered between loop repeats; the encoder also caps it at half the clip
+const MAX_LOOP_CROSSFADE_MS = 2000;
+const MAX_FADE_MS = 5000;
+// Same floor as resizing a clip on the timeline
+const MIN_CLIP_MS = 100;
+
+/**
+ * Export settings of the selected audio clip, shown next to the timeline
+ */
+export const ClipAudioPanel = ({ clip }: { clip: TimelineClip }) => {
+  const updateClip = useTimelineStore((state) => state.updateClip);
+  const data = clip.data ?? {};
+
+  const updateData = (patch: Partial<NonNullable<TimelineClip['data']>>) => {
+    updateClip(clip.id, { data: { ...data, ...patch } });
+  };
+
+  // Trimming works in source time; the clip's timeline length follows from the speed
+  const rate = data.playbackRate ?? 1;
+  const sourceInMs = Math.round(data.sourceInMs ?? 0);
+  const sourceOutMs = Math.round(sourceInMs + clip.durationMs * rate);
+  const sourceLengthMs = Math.max(sourceOutMs, Math.round(data.sourceDurationMs ?? sourceOutMs));
+  const minSpanMs = Math.ceil(MIN_CLIP_MS * rate);
+
+  const setRange = (inMs: number, outMs: number, playbackRate = rate) => {
+    updateClip(clip.id, {
+      durationMs: Math.max(MIN_CLIP_MS, (outMs - inMs) / playbackRate),
+      data: { ...data, sourceInMs: inMs, playbackRate },
+    });
+  };
+
+  const maxFadeMs = Math.max(10, Math.min(MAX_FADE_MS, Math.floor(clip.durationMs / 2 / 10) * 10));
+  const loop = data.loop ?? false;
+  const maxCrossfadeMs = Math.max(0, Math.min(MAX_LOOP_CROSSFADE_MS, Math.floor(clip.durationMs / 2 / 10) * 10));
+
+  return (
+    <div className="w-56 border-l border-ui-border bg-ui-panel/20 overflow-y-auto no-scrollbar flex-shrink-0 p-3 space-y-3">
+      <h3 className="text-[9px] font-bold uppercase tracking-widest text-ui-muted truncate">
+        {clip.name}
+      </h3>
+
+      <SliderControl
+        label="Gain"
+        value={Math.round((data.volume ?? 1) * 100)}
+        min={0}
+        max={200}
+        unit="%"
+        onChange={(percent) => updateData({ volume: percent / 100 })}
+      />
+
+      <SliderControl
+        label="Speed"
+        value={rate}
+        min={0.25}
+        max={4}
+        step={0.05}
+        unit="x"
+        onChange={(playbackRate) => setRange(sourceInMs, sourceOutMs, playbackRate)}
+      />
+
+      {sourceOutMs - minSpanMs > 0 && (
+        <SliderControl
+          label="Trim start"
+          value={sourceInMs}
+          min={0}
+          max={sourceOutMs - minSpanMs}
+          step={10}
+          unit="ms"
+          onChange={(inMs) => setRange(inMs, sourceOutMs)}
+        />
+      )}
+
+      {sourceLengthMs > sourceInMs + minSpanMs && (
+        <SliderControl
+          label="Trim end"
+          value={sourceOutMs}
+          min={sourceInMs + minSpanMs}
+          max={sourceLengthMs}
+          step={10}
+          unit="ms"
+          onChange={(outMs) => setRange(sourceInMs, outMs)}
+        />
+      )}
+
+      <SliderControl
+        label="Fade in"
+        value={Math.min(data.fadeInMs ?? 0, maxFadeMs)}
+        min={0}
+        max={maxFadeMs}
+        step={10}
+        unit="ms"
+        onChange={(fadeInMs) => updateData({ fadeInMs })}
+      />
+
+      <SliderControl
+        label="Fade out"
+        value={Math.min(data.fadeOutMs ?? 0, maxFadeMs)}
+        min={0}
+        max={maxFadeMs}
+        step={10}
+        unit="ms"
+        onChange={(fadeOutMs) => updateData({ fadeOutMs })}
+      />
+
+      <button
+        onClick={() => updateData({ loop: !loop })}
+        className={`flex w-full items-center gap-1.5 px-2 py-1 rounded-lg text-[11px] font-medium transition-colors ${
+          loop ? 'bg-accent/20 text-accent' : 'text-ui-text hover:text-white'
+        }`}
+      >
+        <Repeat className="w-3.5 h-3.5" />
+        Loop to end of export
+      </button>
+
+      {loop && (
+        <SliderControl
+          label="Loop crossfade"
+          value={Math.min(data.loopCrossfadeMs ?? 0, maxCrossfadeMs)}
+          min={0}
+          max={maxCrossfadeMs}
+          step={10}
+          unit="ms"
+          onChange={(loopCrossfadeMs) => updateData({ loopCrossfadeMs })}
+        />
+      )}
+    </div>
+  );
+};
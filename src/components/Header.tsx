@@ -1,4 +1,5 @@
 import { getVersion } from '@tauri-apps/api/app';
+import { invoke } from '@tauri-apps/api/core';
 import { downloadDir } from '@tauri-apps/api/path';
 import { BaseDirectory, writeFile } from '@tauri-apps/plugin-fs';
 import { Command } from '@tauri-apps/plugin-shell';
@@ -7,7 +8,14 @@ import { type as osType } from '@tauri-apps/plugin-os';
 import { ChevronDown, Download, Film, Image as ImageIcon, RefreshCw } from 'lucide-react';
 import { useEffect, useRef, useState } from 'react';
 import { useRenderStore } from '../store/renderStore';
-import type { ExportFormat } from '../store/renderStore';
+import type {
+  ExportColorSpace,
+  ExportEstimate,
+  ExportFormat,
+  SequenceFormat,
+  VideoCodec,
+} from '../store/renderStore';
+import { getExportFolder } from '../utils/renderUtils';
 import { RenderOverlay } from './RenderOverlay';
 import logo from '../assets/images/logo.png';
 
@@ -37,12 +45,48 @@ const EXPORT_OPTIONS: ExportOption[] = [
     description: 'Universal format',
     icon: <Film className="w-4 h-4" />,
   },
+  {
+    id: 'mkv',
+    label: 'MKV Video',
+    description: 'Matroska, any codec',
+    icon: <Film className="w-4 h-4" />,
+  },
   {
     id: 'gif',
     label: 'GIF',
     description: 'Animated Image',
     icon: <ImageIcon className="w-4 h-4" />,
   },
+  {
+    id: 'animated_webp',
+    label: 'Animated WebP',
+    description: 'Full colour, small, transparent',
+    icon: <ImageIcon className="w-4 h-4" />,
+  },
+  {
+    id: 'apng',
+    label: 'APNG',
+    description: 'Lossless animated PNG',
+    icon: <ImageIcon className="w-4 h-4" />,
+  },
+  {
+    id: 'avif',
+    label: 'Animated AVIF',
+    description: 'Smallest animated image',
+    icon: <ImageIcon className="w-4 h-4" />,
+  },
+  {
+    id: 'png_sequence',
+    label: 'PNG Sequence',
+    description: 'Every frame, zipped',
+    icon: <ImageIcon className="w-4 h-4" />,
+  },
+  {
+    id: 'image_sequence',
+    label: 'Image Sequence',
+    description: 'Numbered frames for After Effects',
+    icon: <ImageIcon className="w-4 h-4" />,
+  },
   {
     id: 'png',
     label: 'PNG Image',
@@ -63,6 +107,50 @@ type HeaderProps = {
 
 const FPS_OPTIONS = [30, 50, 60] as const;
 
+// GitHub caps README images at 10 MB; Slack and chat apps tend to choke past a few MB
+const GIF_SIZE_LIMIT_OPTIONS: [number | null, string][] = [
+  [null, 'No limit'],
+  [5 * 1024 * 1024, '5 MB'],
+  [10 * 1024 * 1024, '10 MB'],
+  [25 * 1024 * 1024, '25 MB'],
+];
+
+const SEQUENCE_FORMAT_OPTIONS: [SequenceFormat, string][] = [
+  ['png', 'PNG'],
+  ['png16', 'PNG 16'],
+  ['tiff', 'TIFF'],
+  ['webp', 'WebP'],
+];
+
+const COLOR_SPACE_OPTIONS: [ExportColorSpace, string][] = [
+  ['srgb', 'sRGB'],
+  ['display_p3', 'Display P3'],
+];
+
+// Extra outputs encoded from the same capture as the chosen format
+const EXTRA_FORMAT_OPTIONS: [ExportFormat, string][] = [
+  ['mp4', 'MP4'],
+  ['webm', 'WebM'],
+  ['gif', 'GIF'],
+];
+
+const VIDEO_CODEC_OPTIONS: [VideoCodec, string][] = [
+  ['auto', 'Auto'],
+  ['h264', 'H.264'],
+  ['hevc', 'HEVC'],
+  ['av1', 'AV1'],
+];
+
+// Single-frame exports are written by the webview and need no size check
+const ESTIMATED_FORMATS = EXPORT_OPTIONS.map((option) => option.id).filter(
+  (format) => format !== 'png' && format !== 'webp'
+);
+
+const formatBytes = (bytes: number) =>
+  bytes >= 1024 * 1024 * 1024
+    ? `${(bytes / (1024 * 1024 * 1024)).toFixed(1)} GB`
+    : `${Math.max(1, Math.round(bytes / (1024 * 1024)))} MB`;
+
 type VersionInfo = {
   version: string;
   url: string;
@@ -87,6 +175,32 @@ export const Header = ({ onRender }: HeaderProps) => {
   const setRenderQuality = useRenderStore((state) => state.setRenderQuality);
   const fastExport = useRenderStore((state) => state.fastExport);
   const setFastExport = useRenderStore((state) => state.setFastExport);
+  const collisionPolicy = useRenderStore((state) => state.collisionPolicy);
+  const setCollisionPolicy = useRenderStore((state) => state.setCollisionPolicy);
+  const transparentExport = useRenderStore((state) => state.transparentExport);
+  const setTransparentExport = useRenderStore((state) => state.setTransparentExport);
+  const gifMaxBytes = useRenderStore((state) => state.gifMaxBytes);
+  const setGifMaxBytes = useRenderStore((state) => state.setGifMaxBytes);
+  const sequenceFormat = useRenderStore((state) => state.sequenceFormat);
+  const setSequenceFormat = useRenderStore((state) => state.setSequenceFormat);
+  const gifFps = useRenderStore((state) => state.gifFps);
+  const sequenceZip = useRenderStore((state) => state.sequenceZip);
+  const setSequenceZip = useRenderStore((state) => state.setSequenceZip);
+  const exportColorSpace = useRenderStore((state) => state.exportColorSpace);
+  const setExportColorSpace = useRenderStore((state) => state.setExportColorSpace);
+  const extraFormats = useRenderStore((state) => state.extraFormats);
+  const setExtraFormats = useRenderStore((state) => state.setExtraFormats);
+  const videoCodec = useRenderStore((state) => state.videoCodec);
+  const setVideoCodec = useRenderStore((state) => state.setVideoCodec);
+  const durationMs = useRenderStore((state) => state.durationMs);
+  const mediaAssets = useRenderStore((state) => state.mediaAssets);
+  const canvasWidth = useRenderStore((state) => state.canvasWidth);
+  const canvasHeight = useRenderStore((state) => state.canvasHeight);
+  const includeAudio = useRenderStore((state) => state.includeAudio);
+  // Size estimate and free-space check per format, refreshed while the menu is open
+  const [estimates, setEstimates] = useState<Partial<Record<ExportFormat, ExportEstimate>>>({});
+  // Codecs the bundled ffmpeg can encode; the rest are shown disabled
+  const [availableCodecs, setAvailableCodecs] = useState<VideoCodec[]>(['auto', 'h264']);
   const [isDropdownOpen, setIsDropdownOpen] = useState(false);
   const dropdownRef = useRef<HTMLDivElement>(null);
 
@@ -106,6 +220,71 @@ export const Header = ({ onRender }: HeaderProps) => {
     return () => document.removeEventListener('mousedown', handleClickOutside);
   }, []);
 
+  useEffect(() => {
+    invoke<VideoCodec[]>('get_video_codecs')
+      .then(setAvailableCodecs)
+      .catch((e) => console.warn('Failed to query video codecs:', e));
+  }, []);
+
+  useEffect(() => {
+    if (!isDropdownOpen) return;
+    let cancelled = false;
+    const estimate = async () => {
+      // Same duration, frame count and size the render itself will use
+      const maxVideoDuration = mediaAssets.reduce(
+        (max, asset) => (asset?.type === 'video' && asset.duration ? Math.max(max, asset.duration) : max),
+        0
+      );
+      const totalFrames = Math.max(1, Math.ceil((Math.max(durationMs, maxVideoDuration) / 1000) * fps));
+      const qualityMultiplier = renderQuality === '4k' ? 2 : 1;
+      const exportFolder = await getExportFolder();
+      const results = await Promise.all(
+        ESTIMATED_FORMATS.map(async (format) => {
+          try {
+            const result = await invoke<ExportEstimate>('estimate_export_size', {
+              request: {
+                output_path: exportFolder,
+                width: (canvasWidth || 1080) * qualityMultiplier,
+                height: (canvasHeight || 1080) * qualityMultiplier,
+                fps,
+                total_frames: totalFrames,
+                format,
+                use_hw: true,
+                has_audio: includeAudio && ['mp4', 'mkv', 'webm', 'mov'].includes(format),
+                alpha: transparentExport,
+                video_codec: ['mp4', 'mkv', 'webm'].includes(format) ? videoCodec : 'auto',
+                sequence_format: sequenceFormat,
+                gif_fps: gifFps,
+              },
+            });
+            return [format, result] as const;
+          } catch {
+            // Combinations the backend refuses (e.g. alpha in mp4) just show no estimate
+            return [format, undefined] as const;
+          }
+        })
+      );
+      if (!cancelled) setEstimates(Object.fromEntries(results));
+    };
+    estimate().catch((e) => console.warn('Failed to estimate export size:', e));
+    return () => {
+      cancelled = true;
+    };
+  }, [
+    isDropdownOpen,
+    durationMs,
+    mediaAssets,
+    fps,
+    renderQuality,
+    canvasWidth,
+    canvasHeight,
+    includeAudio,
+    transparentExport,
+    videoCodec,
+    sequenceFormat,
+    gifFps,
+  ]);
+
   useEffect(() => {
     const checkVersion = async () => {
       try {
@@ -447,22 +626,225 @@ export const Header = ({ onRender }: HeaderProps) => {
                     </button>
                   </div>
                 </div>
+                {/* Codec Selector */}
+                <div className="px-3 py-2.5 border-b border-ui-border">
+                  <div className="text-[10px] text-ui-muted uppercase tracking-wider mb-2">
+                    Codec (MP4 / MKV / WebM)
+                  </div>
+                  <div className="flex gap-1">
+                    {VIDEO_CODEC_OPTIONS.map(([codec, label]) => (
+                      <button
+                        key={codec}
+                        onClick={() => setVideoCodec(codec)}
+                        disabled={!availableCodecs.includes(codec)}
+                        className={`flex-1 px-2 py-1.5 rounded-md text-xs font-medium transition-colors disabled:opacity-40 disabled:pointer-events-none ${
+                          videoCodec === codec
+                            ? 'bg-accent text-black'
+                            : 'bg-ui-highlight/30 text-ui-muted hover:bg-ui-highlight/50 hover:text-white'
+                        }`}
+                      >
+                        {label}
+                      </button>
+                    ))}
+                  </div>
+                </div>
+                {/* Extra Outputs Selector */}
+                <div className="px-3 py-2.5 border-b border-ui-border">
+                  <div className="text-[10px] text-ui-muted uppercase tracking-wider mb-2">
+                    Also Export
+                  </div>
+                  <div className="flex gap-1">
+                    {EXTRA_FORMAT_OPTIONS.map(([format, label]) => (
+                      <button
+                        key={format}
+                        onClick={() =>
+                          setExtraFormats(
+                            extraFormats.includes(format)
+                              ? extraFormats.filter((f) => f !== format)
+                              : [...extraFormats, format]
+                          )
+                        }
+                        className={`flex-1 px-2 py-1.5 rounded-md text-xs font-medium transition-colors ${
+                          extraFormats.includes(format)
+                            ? 'bg-accent text-black'
+                            : 'bg-ui-highlight/30 text-ui-muted hover:bg-ui-highlight/50 hover:text-white'
+                        }`}
+                      >
+                        {label}
+                      </button>
+                    ))}
+                  </div>
+                </div>
+                {/* GIF Size Limit Selector */}
+                <div className="px-3 py-2.5 border-b border-ui-border">
+                  <div className="text-[10px] text-ui-muted uppercase tracking-wider mb-2">
+                    GIF Size Limit
+                  </div>
+                  <div className="flex gap-1">
+                    {GIF_SIZE_LIMIT_OPTIONS.map(([bytes, label]) => (
+                      <button
+                        key={label}
+                        onClick={() => setGifMaxBytes(bytes)}
+                        className={`flex-1 px-2 py-1.5 rounded-md text-xs font-medium transition-colors ${
+                          gifMaxBytes === bytes
+                            ? 'bg-accent text-black'
+                            : 'bg-ui-highlight/30 text-ui-muted hover:bg-ui-highlight/50 hover:text-white'
+                        }`}
+                      >
+                        {label}
+                      </button>
+                    ))}
+                  </div>
+                </div>
+                {/* Image Sequence Selector */}
+                <div className="px-3 py-2.5 border-b border-ui-border">
+                  <div className="text-[10px] text-ui-muted uppercase tracking-wider mb-2">
+                    Image Sequence
+                  </div>
+                  <div className="flex gap-1">
+                    {SEQUENCE_FORMAT_OPTIONS.map(([format, label]) => (
+                      <button
+                        key={format}
+                        onClick={() => setSequenceFormat(format)}
+                        className={`flex-1 px-2 py-1.5 rounded-md text-xs font-medium transition-colors ${
+                          sequenceFormat === format
+                            ? 'bg-accent text-black'
+                            : 'bg-ui-highlight/30 text-ui-muted hover:bg-ui-highlight/50 hover:text-white'
+                        }`}
+                      >
+                        {label}
+                      </button>
+                    ))}
+                  </div>
+                  <div className="flex gap-1 mt-1">
+                    {(
+                      [
+                        [false, 'Folder'],
+                        [true, 'Zip'],
+                      ] as const
+                    ).map(([zip, label]) => (
+                      <button
+                        key={label}
+                        onClick={() => setSequenceZip(zip)}
+                        className={`flex-1 px-2 py-1.5 rounded-md text-xs font-medium transition-colors ${
+                          sequenceZip === zip
+                            ? 'bg-accent text-black'
+                            : 'bg-ui-highlight/30 text-ui-muted hover:bg-ui-highlight/50 hover:text-white'
+                        }`}
+                      >
+                        {label}
+                      </button>
+                    ))}
+                  </div>
+                </div>
+                {/* Background Selector */}
+                <div className="px-3 py-2.5 border-b border-ui-border">
+                  <div className="text-[10px] text-ui-muted uppercase tracking-wider mb-2">
+                    Background
+                  </div>
+                  <div className="flex gap-1">
+                    {(
+                      [
+                        [false, 'Solid'],
+                        [true, 'Transparent'],
+                      ] as const
+                    ).map(([transparent, label]) => (
+                      <button
+                        key={label}
+                        onClick={() => setTransparentExport(transparent)}
+                        className={`flex-1 px-2 py-1.5 rounded-md text-xs font-medium transition-colors ${
+                          transparentExport === transparent
+                            ? 'bg-accent text-black'
+                            : 'bg-ui-highlight/30 text-ui-muted hover:bg-ui-highlight/50 hover:text-white'
+                        }`}
+                      >
+                        {label}
+                      </button>
+                    ))}
+                  </div>
+                </div>
+                {/* Color Space Selector */}
+                <div className="px-3 py-2.5 border-b border-ui-border">
+                  <div className="text-[10px] text-ui-muted uppercase tracking-wider mb-2">
+                    Color
+                  </div>
+                  <div className="flex gap-1">
+                    {COLOR_SPACE_OPTIONS.map(([colorSpace, label]) => (
+                      <button
+                        key={colorSpace}
+                        onClick={() => setExportColorSpace(colorSpace)}
+                        className={`flex-1 px-2 py-1.5 rounded-md text-xs font-medium transition-colors ${
+                          exportColorSpace === colorSpace
+                            ? 'bg-accent text-black'
+                            : 'bg-ui-highlight/30 text-ui-muted hover:bg-ui-highlight/50 hover:text-white'
+                        }`}
+                      >
+                        {label}
+                      </button>
+                    ))}
+                  </div>
+                </div>
+                {/* Existing File Selector */}
+                <div className="px-3 py-2.5 border-b border-ui-border">
+                  <div className="text-[10px] text-ui-muted uppercase tracking-wider mb-2">
+                    If File Exists
+                  </div>
+                  <div className="flex gap-1">
+                    {(
+                      [
+                        ['increment', 'Rename'],
+                        ['overwrite', 'Replace'],
+                        ['fail', 'Stop'],
+                      ] as const
+                    ).map(([policy, label]) => (
+                      <button
+                        key={policy}
+                        onClick={() => setCollisionPolicy(policy)}
+                        className={`flex-1 px-2 py-1.5 rounded-md text-xs font-medium transition-colors ${
+                          collisionPolicy === policy
+                            ? 'bg-accent text-black'
+                            : 'bg-ui-highlight/30 text-ui-muted hover:bg-ui-highlight/50 hover:text-white'
+                        }`}
+                      >
+                        {label}
+                      </button>
+                    ))}
+                  </div>
+                </div>
                 <div className="p-1">
-                  {EXPORT_OPTIONS.map((option) => (
-                    <button
-                      key={option.id}
-                      onClick={() => handleExport(option.id)}
-                      className="w-full flex items-center gap-3 px-3 py-2.5 rounded-lg text-left hover:bg-ui-highlight/50 transition-colors group"
-                    >
-                      <span className="text-ui-muted group-hover:text-accent transition-colors">
-                        {option.icon}
-                      </span>
-                      <div className="flex-1">
-                        <div className="text-sm font-medium text-white">{option.label}</div>
-                        <div className="text-[10px] text-ui-muted">{option.description}</div>
-                      </div>
-                    </button>
-                  ))}
+                  {EXPORT_OPTIONS.map((option) => {
+                    const estimate = estimates[option.id];
+                    return (
+                      <button
+                        key={option.id}
+                        onClick={() => handleExport(option.id)}
+                        disabled={estimate?.sufficient === false}
+                        className="w-full flex items-center gap-3 px-3 py-2.5 rounded-lg text-left hover:bg-ui-highlight/50 transition-colors group disabled:opacity-50 disabled:pointer-events-none"
+                      >
+                        <span className="text-ui-muted group-hover:text-accent transition-colors">
+                          {option.icon}
+                        </span>
+                        <div className="flex-1">
+                          <div className="flex items-baseline justify-between gap-2">
+                            <span className="text-sm font-medium text-white">{option.label}</span>
+                            {estimate && (
+                              <span className="text-[10px] font-mono text-ui-muted">
+                                ~{formatBytes(estimate.output_bytes)}
+                              </span>
+                            )}
+                          </div>
+                          <div className="text-[10px] text-ui-muted">{option.description}</div>
+                          {estimate?.warning && (
+                            <div
+                              className={`text-[10px] ${estimate.sufficient ? 'text-amber-400' : 'text-red-400'}`}
+                            >
+                              {estimate.warning}
+                            </div>
+                          )}
+                        </div>
+                      </button>
+                    );
+                  })}
                 </div>
               </div>
             )}
@@ -25,11 +25,13 @@ import {
   Volume2,
   VolumeX,
 } from 'lucide-react';
-import { open } from '@tauri-apps/plugin-dialog';
+import { invoke } from '@tauri-apps/api/core';
 import { useTimelineStore, ANIMATION_PRESETS } from '../store/timelineStore';
 import type { TimelineClip, AnimationPreset } from '../store/timelineStore';
 import { useRenderStore } from '../store/renderStore';
 import { ANIMATION_SPEED_MULTIPLIERS } from '../constants/layoutAnimationPresets';
+import { ClipAudioPanel } from './ClipAudioPanel';
+import { filmstripCount, useFilmstrip } from '../hooks/useFilmstrip';
 
 const MS_PER_SECOND = 1000;
 const PIXELS_PER_SECOND = 200;
@@ -99,6 +101,37 @@ type DragState = {
   initialPlayheadMs?: number;
 };
 
+// Width of one filmstrip tile on a clip, in px
+const FILMSTRIP_TILE_PX = 48;
+
+// Thumbnails tiled across a video clip, each showing the source time under its centre
+const ClipFilmstrip = ({ clip, width }: { clip: TimelineClip; width: number }) => {
+  const sourceDurationMs = clip.data?.sourceDurationMs;
+  const frames = useFilmstrip(clip.data?.mediaUrl, sourceDurationMs);
+  if (!sourceDurationMs || frames.length === 0) return null;
+
+  const rate = clip.data?.playbackRate ?? 1;
+  const sourceInMs = clip.data?.sourceInMs ?? 0;
+  const count = filmstripCount(sourceDurationMs);
+  const tiles = Math.max(1, Math.ceil(width / FILMSTRIP_TILE_PX));
+
+  return (
+    <div className="absolute inset-0 flex overflow-hidden rounded-lg opacity-60 pointer-events-none">
+      {Array.from({ length: tiles }, (_, tile) => {
+        const clipMs = ((tile + 0.5) * FILMSTRIP_TILE_PX / width) * clip.durationMs;
+        const sourceMs = Math.min(sourceDurationMs, sourceInMs + clipMs * rate);
+        const index = Math.min(count - 1, Math.floor((sourceMs / sourceDurationMs) * count));
+        const src = frames[index];
+        return (
+          <div key={tile} className="h-full flex-shrink-0" style={{ width: `${FILMSTRIP_TILE_PX}px` }}>
+            {src && <img src={src} alt="" className="w-full h-full object-cover" draggable={false} />}
+          </div>
+        );
+      })}
+    </div>
+  );
+};
+
 const TimelineClipComponent = ({
   clip,
   zoom,
@@ -137,6 +170,8 @@ const TimelineClipComponent = ({
         }
       }}
     >
+      {clip.type === 'visual' && clip.data?.mediaUrl && <ClipFilmstrip clip={clip} width={Math.max(width, 40)} />}
+
       {/* Resize handle left */}
       <div
         className="absolute left-0 -translate-x-1/2 top-0 bottom-0 w-3 cursor-ew-resize group/resize-left rounded-l-lg"
@@ -300,6 +335,9 @@ export const Timeline = () => {
   const audioTrack = tracks.find((t) => t.type === 'audio');
   const audioClip = audioTrack?.clips[0];
   const audioUrl = audioClip?.data?.mediaUrl;
+  const selectedAudioClip = tracks
+    .flatMap((t) => t.clips)
+    .find((c) => c.id === selectedClipId && c.type === 'audio');
 
   // Audio playback sync
   const isAudioMuted = audioTrack?.muted ?? false;
@@ -325,8 +363,13 @@ export const Timeline = () => {
     // Check if playhead is within audio clip range
     const isInRange = playheadMs >= audioStartMs && playheadMs <= audioStartMs + audioDurationMs;
 
+    // Preview the trim, speed and gain the export will use
+    const rate = audioClip?.data?.playbackRate ?? 1;
+    audio.playbackRate = rate;
+    audio.volume = Math.min(1, audioClip?.data?.volume ?? 1);
+
     if (isPlaying && isInRange) {
-      const audioTime = (playheadMs - audioStartMs) / 1000;
+      const audioTime = ((audioClip?.data?.sourceInMs ?? 0) + (playheadMs - audioStartMs) * rate) / 1000;
       if (Math.abs(audio.currentTime - audioTime) > 0.1) {
         audio.currentTime = audioTime;
       }
@@ -338,7 +381,17 @@ export const Timeline = () => {
     return () => {
       if (!isPlaying) audio.pause();
     };
-  }, [isPlaying, playheadMs, audioUrl, audioClip?.startMs, audioClip?.durationMs, isAudioMuted]);
+  }, [
+    isPlaying,
+    playheadMs,
+    audioUrl,
+    audioClip?.startMs,
+    audioClip?.durationMs,
+    audioClip?.data?.sourceInMs,
+    audioClip?.data?.playbackRate,
+    audioClip?.data?.volume,
+    isAudioMuted,
+  ]);
 
   // Keyboard shortcuts
   useEffect(() => {
@@ -591,10 +644,8 @@ export const Timeline = () => {
 
   // Handle audio file import
   const handleAudioImport = async () => {
-    const file = await open({
-      multiple: false,
-      filters: [{ name: 'Audio', extensions: ['mp3', 'wav', 'aac', 'm4a', 'ogg'] }],
-    });
+    // Picked in the backend so the file is added to the fs scope the audio commands check
+    const file = await invoke<string | null>('pick_audio_file');
 
     if (!file) return;
 
@@ -618,7 +669,7 @@ export const Timeline = () => {
       durationMs: audioDurationMs,
       color: '#22c55e',
       icon: 'music',
-      data: { mediaUrl: file, volume: 1 },
+      data: { mediaUrl: file, volume: 1, sourceDurationMs: audio.duration ? audio.duration * 1000 : undefined },
     });
   };
 
@@ -871,6 +922,9 @@ export const Timeline = () => {
             </div>
           </div>
         </div>
+
+        {/* Selected audio clip settings */}
+        {selectedAudioClip && <ClipAudioPanel clip={selectedAudioClip} />}
       </div>
     </footer>
   );
@@ -3,6 +3,7 @@ export type FramePreset = {
   width: number;
   height: number;
   ratio: string;
+  requiresAudio?: boolean; // Delivery target rejects videos without an audio stream
 };
 
 export type FrameCategory = {
@@ -28,10 +29,10 @@ export const FRAMES_DATA: FrameCategory[] = [
   {
     category: 'App Store (iOS)',
     frames: [
-      { label: 'iPhone 6.5"', width: 1284, height: 2778, ratio: '19.5:9' },
-      { label: 'iPhone 5.5"', width: 1242, height: 2208, ratio: '16:9' },
-      { label: 'iPad Pro 12.9"', width: 2048, height: 2732, ratio: '4:3' },
-      { label: 'Mac App Preview', width: 2880, height: 1800, ratio: '16:10' },
+      { label: 'iPhone 6.5"', width: 1284, height: 2778, ratio: '19.5:9', requiresAudio: true },
+      { label: 'iPhone 5.5"', width: 1242, height: 2208, ratio: '16:9', requiresAudio: true },
+      { label: 'iPad Pro 12.9"', width: 2048, height: 2732, ratio: '4:3', requiresAudio: true },
+      { label: 'Mac App Preview', width: 2880, height: 1800, ratio: '16:10', requiresAudio: true },
     ]
   },
   {
@@ -52,3 +53,9 @@ export const getFrameLabel = (width: number, height: number): string => {
   }
   return `${width} × ${height}`;
 };
+
+// App previews and some ad platforms only accept files with an audio stream
+export const frameRequiresAudio = (width: number, height: number): boolean =>
+  FRAMES_DATA.some((group) =>
+    group.frames.some((f) => f.requiresAudio && f.width === width && f.height === height)
+  );
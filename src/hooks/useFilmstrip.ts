@@ -0,0 +1,115 @@
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+import { readFile } from '@tauri-apps/plugin-fs';
+import { useEffect, useState } from 'react';
+import { type TempWorkspace, writeMediaFile } from '../utils/mediaUtils';
+
+// Thumbnail height in px; twice the clip row so they stay sharp on HiDPI screens
+const THUMBNAIL_HEIGHT = 80;
+// One thumbnail per second of source, capped like the backend
+const MAX_THUMBNAILS = 120;
+
+// Thumbnails generated for a source of this length
+export const filmstripCount = (sourceDurationMs: number): number =>
+  Math.min(MAX_THUMBNAILS, Math.max(1, Math.round(sourceDurationMs / 1000)));
+
+type Filmstrip = { key: string; count: number; cached: boolean; frames: string[] };
+type FilmstripFrame = { key: string; index: number; path: string };
+type FilmstripDone = { key: string; error: string | null };
+
+/**
+ * Thumbnails spread evenly over a video's whole source, as object URLs (undefined until loaded).
+ * Generated in the background and cached by content; frames appear as they are decoded.
+ */
+export const useFilmstrip = (mediaUrl?: string, sourceDurationMs?: number): (string | undefined)[] => {
+  const [frames, setFrames] = useState<(string | undefined)[]>([]);
+
+  useEffect(() => {
+    if (!mediaUrl || !sourceDurationMs) return;
+
+    let disposed = false;
+    let key: string | null = null;
+    let workspace: TempWorkspace | null = null;
+    const objectUrls: string[] = [];
+    // Events can arrive before generate_filmstrip returns the key; hold them until then
+    const early: Array<() => void> = [];
+    setFrames([]);
+
+    const release = () => {
+      if (!workspace) return;
+      const { job_id } = workspace;
+      workspace = null;
+      invoke('release_temp_workspace', { jobId: job_id }).catch(() => {});
+    };
+
+    const show = async (index: number, path: string) => {
+      const data = await readFile(path);
+      if (disposed) return;
+      const url = URL.createObjectURL(new Blob([data], { type: 'image/png' }));
+      objectUrls.push(url);
+      setFrames((prev) => {
+        const next = [...prev];
+        next[index] = url;
+        return next;
+      });
+    };
+
+    const whenKeyed = (run: (current: string) => void) => {
+      if (key === null) early.push(() => run(key!));
+      else run(key);
+    };
+
+    const unlistenFrame = listen<FilmstripFrame>('filmstrip-frame', ({ payload }) =>
+      whenKeyed((current) => {
+        if (payload.key === current) void show(payload.index, payload.path);
+      })
+    );
+    const unlistenDone = listen<FilmstripDone>('filmstrip-done', ({ payload }) =>
+      whenKeyed((current) => {
+        if (payload.key !== current) return;
+        if (payload.error) console.warn('[Filmstrip]', payload.error);
+        release();
+      })
+    );
+
+    (async () => {
+      try {
+        await Promise.all([unlistenFrame, unlistenDone]);
+        let path = mediaUrl;
+        if (mediaUrl.startsWith('blob:')) {
+          workspace = await invoke<TempWorkspace>('create_temp_workspace', { label: 'filmstrip' });
+          path = await writeMediaFile(mediaUrl, workspace.path, 'source');
+        }
+        if (disposed) {
+          release();
+          return;
+        }
+
+        const strip = await invoke<Filmstrip>('generate_filmstrip', {
+          path,
+          count: filmstripCount(sourceDurationMs),
+          height: THUMBNAIL_HEIGHT,
+        });
+        key = strip.key;
+        if (strip.cached) {
+          release();
+          strip.frames.forEach((framePath, index) => void show(index, framePath));
+        }
+        early.splice(0).forEach((run) => run());
+      } catch (error) {
+        console.warn('[Filmstrip] Failed to generate thumbnails:', error);
+        release();
+      }
+    })();
+
+    return () => {
+      disposed = true;
+      unlistenFrame.then((unlisten) => unlisten());
+      unlistenDone.then((unlisten) => unlisten());
+      objectUrls.forEach((url) => URL.revokeObjectURL(url));
+      release();
+    };
+  }, [mediaUrl, sourceDurationMs]);
+
+  return frames;
+};
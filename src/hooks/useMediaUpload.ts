@@ -53,7 +53,7 @@ export const useMediaUpload = () => {
                 durationMs: duration,
                 color: '#3b82f6', // Blue for video
                 icon: 'clapperboard',
-                data: { mediaUrl: url, slotIndex: targetSlot ?? 0 },
+                data: { mediaUrl: url, slotIndex: targetSlot ?? 0, sourceDurationMs: duration },
               });
             }
         }
@@ -100,7 +100,7 @@ export const useMediaUpload = () => {
                 durationMs: duration,
                 color: '#3b82f6',
                 icon: 'clapperboard',
-                data: { mediaUrl: url, slotIndex: targetIdx !== -1 ? targetIdx : i },
+                data: { mediaUrl: url, slotIndex: targetIdx !== -1 ? targetIdx : i, sourceDurationMs: duration },
               });
            }
         }
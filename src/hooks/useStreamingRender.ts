@@ -1,11 +1,13 @@
 import { invoke } from '@tauri-apps/api/core';
-import { appDataDir, dirname, join } from '@tauri-apps/api/path';
+import { dirname, join } from '@tauri-apps/api/path';
 import { platform } from '@tauri-apps/plugin-os';
 import { Command } from '@tauri-apps/plugin-shell';
 import { useCallback, useRef, useState } from 'react';
-import type { ExportFormat } from '../store/renderStore';
+import type { ExportFormat, OutputStatus, RenderReport } from '../store/renderStore';
 import { useRenderStore } from '../store/renderStore';
+import type { TimelineClip } from '../store/timelineStore';
 import { useTimelineStore } from '../store/timelineStore';
+import { frameRequiresAudio } from '../constants/framePresets';
 import {
   captureFrame,
   clearExportContext,
@@ -18,9 +20,12 @@ import {
   preloadResources,
   prepareExportContext,
   seekTimeline,
+  setCaptureColorSpace,
+  setCaptureTransparent,
   waitForRender,
   yieldToMain
 } from '../utils/renderUtils';
+import { openVideoFrameSources, seekVideoFrames } from '../utils/frameServer';
 
 const revealInFileManager = async (filePath: string) => {
   const os = platform();
@@ -35,6 +40,51 @@ const revealInFileManager = async (filePath: string) => {
   }
 };
 
+// Mirrors the Rust `AudioTrack` (snake_case keys)
+type AudioTrackSpec = {
+  path: string;
+  delay_ms: number;
+  source_in_ms: number;
+  source_out_ms?: number; // Omitted = play to the end of the source
+  playback_rate: number;
+  volume_db: number;
+  fade_in_ms: number;
+  fade_out_ms: number;
+  mute: boolean;
+  role: 'music' | 'camera';
+  loop: boolean;
+  loop_crossfade_ms: number;
+};
+
+// Clip ends closer than this to the end of the source count as untrimmed
+const TRIM_TOLERANCE_MS = 10;
+
+// Only the part of the source the clip actually shows, at the clip's speed
+const toAudioTrackSpec = (clip: TimelineClip, path: string, role: AudioTrackSpec['role']): AudioTrackSpec => {
+  const rate = clip.data?.playbackRate ?? 1;
+  const sourceInMs = Math.round(clip.data?.sourceInMs ?? 0);
+  const sourceOutMs = sourceInMs + Math.round(clip.durationMs * rate);
+  // Without a known source length the out-point can't be told apart from the end, so send it
+  const sourceDurationMs = clip.data?.sourceDurationMs;
+  const trimmedEnd = sourceDurationMs === undefined || sourceOutMs < sourceDurationMs - TRIM_TOLERANCE_MS;
+  // Clip volume is linear 0..1; the mixer works in dB
+  const volume = clip.data?.volume ?? 1;
+  return {
+    path,
+    delay_ms: Math.round(clip.startMs),
+    source_in_ms: sourceInMs,
+    source_out_ms: trimmedEnd ? sourceOutMs : undefined,
+    playback_rate: rate,
+    volume_db: volume > 0 ? 20 * Math.log10(volume) : 0,
+    fade_in_ms: Math.round(clip.data?.fadeInMs ?? 0),
+    fade_out_ms: Math.round(clip.data?.fadeOutMs ?? 0),
+    mute: volume <= 0,
+    role,
+    loop: clip.data?.loop ?? false,
+    loop_crossfade_ms: Math.round(clip.data?.loopCrossfadeMs ?? 0),
+  };
+};
+
 export type StreamingRenderOptions = {
   node: HTMLElement | null;
   durationMs: number;
@@ -71,7 +121,31 @@ export const useStreamingRender = () => {
     renderQuality, 
     mediaAssets,
     includeAudio,
-    cameraAudioEnabled
+    cameraAudioEnabled,
+    duckMusic,
+    loudnessTarget,
+    audioCodec,
+    audioBitrateKbps,
+    audioTailFadeMs,
+    silentAudioTrack,
+    transparentExport,
+    exportColorSpace,
+    videoCodec,
+    animationLossless,
+    animationQuality,
+    animationLoopCount,
+    gifFps,
+    gifColors,
+    gifDither,
+    gifDitherStrength,
+    gifPalette,
+    gifLoopCount,
+    gifMaxBytes,
+    sequenceFormat,
+    sequencePattern,
+    sequenceZip,
+    extraFormats,
+    collisionPolicy
   } = useRenderStore();
   const [state, setState] = useState<StreamingRenderState>({
     isRendering: false,
@@ -149,7 +223,7 @@ export const useStreamingRender = () => {
 
       // Setup output path
       const exportFolder = await getExportFolder();
-      const ext = getFileExtension(format);
+      const ext = format === 'image_sequence' && sequenceZip ? 'zip' : getFileExtension(format);
       
       // Image Export (PNG/WebP) or screenshot fallback if no duration
       const isImageFallback = effectiveDuration < 100;
@@ -189,13 +263,19 @@ export const useStreamingRender = () => {
            const { playheadMs } = useTimelineStore.getState();
            seekTimeline(playheadMs);
            pauseAndSeekAnimations(node, playheadMs);
-           await pauseAndSeekVideos(node, playheadMs);
            await waitForRender(50); // Convert DOM to canvas needs a settled DOM
 
            // Preload resources (images, videos) and fonts - CRITICAL for export
            await preloadResources(node);
            await preloadFonts(node);
 
+           // Exact frame from the backend decoder; <video> seeking is only the fallback
+           if (await openVideoFrameSources(node, fps, Math.max(outputWidth, outputHeight))) {
+             await seekVideoFrames(playheadMs);
+           } else {
+             await pauseAndSeekVideos(node, playheadMs);
+           }
+
            // Prepare export context for correct layered rendering (Background -> Video -> Device)
            const nodeRect = node.getBoundingClientRect();
            await prepareExportContext(node, nodeRect.width, nodeRect.height, outputWidth, outputHeight);
@@ -256,9 +336,6 @@ export const useStreamingRender = () => {
       // Video Export Logic
       const filename = getVideoFilename(outputName, outputWidth, outputHeight, ext);
       const outputPath = await join(exportFolder, filename);
-      const tempBase = await appDataDir();
-      const tempFolder = await join(tempBase, 'exports');
-      const tempOutputPath = await join(tempFolder, filename);
 
       // Pause playback
       useTimelineStore.getState().setIsPlaying(false);
@@ -281,10 +358,11 @@ export const useStreamingRender = () => {
         phase: 'capturing',
         format,
         isImageExport: false,
+        warnings: [],
       });
 
       try {
-        const audioTracks: { path: string; delay_ms: number }[] = [];
+        const audioTracks: AudioTrackSpec[] = [];
         
         if (includeAudio) {
           // 1. Get audio file from dedicated audio track if available
@@ -295,10 +373,7 @@ export const useStreamingRender = () => {
           if (!isAudioTrackMuted) {
             audioTrack?.clips.forEach(clip => {
               if (clip.data?.mediaUrl) {
-                audioTracks.push({
-                  path: clip.data.mediaUrl,
-                  delay_ms: Math.round(clip.startMs)
-                });
+                audioTracks.push(toAudioTrackSpec(clip, clip.data.mediaUrl, 'music'));
               }
             });
           }
@@ -310,10 +385,7 @@ export const useStreamingRender = () => {
               if (track.muted) return;
               track.clips.forEach((clip) => {
                 if (clip.data?.mediaUrl) {
-                  audioTracks.push({
-                    path: clip.data.mediaUrl,
-                    delay_ms: Math.round(clip.startMs)
-                  });
+                  audioTracks.push(toAudioTrackSpec(clip, clip.data.mediaUrl, 'camera'));
                 }
               });
             });
@@ -323,26 +395,84 @@ export const useStreamingRender = () => {
         // Start the streaming encoder in Rust
         console.log('[StreamRender] Starting streaming encoder...', { audioTracks });
         const { mkdir } = await import('@tauri-apps/plugin-fs');
-        await mkdir(tempFolder, { recursive: true });
-        const encoderId = await invoke<string>('start_streaming_encode', {
-          outputPath: tempOutputPath,
+        await mkdir(exportFolder, { recursive: true });
+        // Mirrors the Rust `OutputSpec`; the first output is the chosen format
+        const primaryOutput = {
+          output_path: outputPath,
+          format,
           width: outputWidth,
           height: outputHeight,
-          fps,
-          totalFrames,
-          format,
-          useHw: true,
-          audio_tracks: audioTracks,
-          inputWidth: captureWidth,
-          inputHeight: captureHeight,
+          alpha: transparentExport,
+          // mov/gif/sequences keep their own codec; the backend rejects other picks there
+          video_codec: ['mp4', 'mkv', 'webm'].includes(format) ? videoCodec : 'auto',
+          animation_options: {
+            lossless: animationLossless,
+            quality: animationQuality,
+            loop_count: animationLoopCount,
+          },
+          gif_options: {
+            fps: gifFps,
+            max_colors: gifColors,
+            dither: gifDither,
+            dither_strength: gifDitherStrength,
+            palette: gifPalette,
+            loop_count: gifLoopCount,
+            max_bytes: gifMaxBytes,
+          },
+          sequence_options: {
+            format: sequenceFormat,
+            pattern: sequencePattern,
+            zip: sequenceZip,
+          },
+        };
+        // Extra formats are encoded from the same frames; each gets its own file next to the main one
+        const extraOutputs = await Promise.all(
+          extraFormats
+            .filter((extra) => extra !== format)
+            .map(async (extra) => ({
+              output_path: await join(
+                exportFolder,
+                getVideoFilename(outputName, outputWidth, outputHeight, getFileExtension(extra))
+              ),
+              format: extra,
+              alpha: transparentExport && ['webm', 'mov', 'gif'].includes(extra),
+              video_codec: extra === 'mp4' ? videoCodec : 'auto',
+            }))
+        );
+        // Encoder writes to a hidden temp sibling and renames into place on finish
+        const encoderId = await invoke<string>('start_streaming_encode', {
+          // Mirrors the Rust `SessionInput` - what every output shares
+          session: {
+            input_width: captureWidth,
+            input_height: captureHeight,
+            fps,
+            total_frames: totalFrames,
+            use_hw: true,
+            audio_tracks: audioTracks,
+            audio_options: {
+              duck_music: duckMusic,
+              loudness_target: loudnessTarget === 'off' ? null : loudnessTarget,
+              codec: audioCodec,
+              bitrate_kbps: audioBitrateKbps,
+              channels: 2,
+              tail_fade_ms: audioTailFadeMs,
+              silent_track:
+                silentAudioTrack === 'on' ||
+                (silentAudioTrack === 'auto' && frameRequiresAudio(canvasWidth, canvasHeight)),
+            },
+            collision_policy: collisionPolicy,
+            color_space: exportColorSpace,
+          },
+          outputs: [primaryOutput, ...extraOutputs],
         });
+        setCaptureTransparent(transparentExport);
+        setCaptureColorSpace(exportColorSpace === 'display_p3' ? 'display-p3' : 'srgb');
         encoderIdRef.current = encoderId;
         console.log('[StreamRender] Encoder started:', encoderId);
 
         // Seek to start BEFORE cloning - ensures initial animation state (opacity=0)
         seekTimeline(0);
         pauseAndSeekAnimations(node, 0);
-        await pauseAndSeekVideos(node, 0);
 
         // CRITICAL: Wait for React to fully re-render with playhead=0
         // This ensures entrance animations are at their START state (invisible)
@@ -357,11 +487,21 @@ export const useStreamingRender = () => {
         const nodeRect = node.getBoundingClientRect();
         await prepareExportContext(node, nodeRect.width, nodeRect.height, captureWidth, captureHeight);
 
+        // Video frames come from the backend decoder (exact frame per index); <video> seeking is
+        // only the fallback when a clip can't be opened there
+        const hasVideos = node.querySelectorAll('video').length > 0;
+        const servesVideos =
+          hasVideos && (await openVideoFrameSources(node, fps, Math.max(captureWidth, captureHeight)));
+        if (servesVideos) {
+          await seekVideoFrames(0);
+        } else if (hasVideos) {
+          await pauseAndSeekVideos(node, 0);
+        }
+
         await waitForRender(16);
 
         // Capture and stream each frame with improved threading
-        const hasVideos = node.querySelectorAll('video').length > 0;
-        console.log(`[StreamRender] Starting frame loop: ${totalFrames} frames, hasVideos: ${hasVideos}`);
+        console.log(`[StreamRender] Starting frame loop: ${totalFrames} frames, hasVideos: ${hasVideos}, served: ${servesVideos}`);
         const loopStart = performance.now();
 
         // OPTIMIZED: Pipeline capture and encode
@@ -387,8 +527,10 @@ export const useStreamingRender = () => {
           seekTimeline(timeMs);
           pauseAndSeekAnimations(node, timeMs);
 
-          // Seek videos if present
-          if (hasVideos) {
+          // Fetch (or seek) video frames if present
+          if (servesVideos) {
+            await seekVideoFrames(timeMs);
+          } else if (hasVideos) {
             await pauseAndSeekVideos(node, timeMs);
           }
 
@@ -423,6 +565,12 @@ export const useStreamingRender = () => {
             const progress = (frameIndex + 1) / totalFrames;
             setState((prev) => ({ ...prev, currentFrame: frameIndex + 1, progress }));
             setRenderStatus({ currentFrame: frameIndex + 1, progress });
+            if (extraOutputs.length > 0) {
+              // Each output keeps its own progress; one failing doesn't stop the rest
+              invoke<OutputStatus[]>('get_streaming_outputs', { encoderId })
+                .then((statuses) => setRenderStatus({ outputs: statuses }))
+                .catch(() => {});
+            }
           }
         }
 
@@ -440,21 +588,25 @@ export const useStreamingRender = () => {
         setState((prev) => ({ ...prev, phase: 'encoding' }));
         setRenderStatus({ phase: 'encoding', format, isImageExport: false });
 
-        await invoke('finish_streaming_encode', { encoderId: encoderIdRef.current });
+        // Final path can differ from outputPath with the 'increment' collision policy
+        const report = await invoke<RenderReport>('finish_streaming_encode', { encoderId: encoderIdRef.current });
+        const finalPath = report.path;
         encoderIdRef.current = null;
+        report.outputs
+          .filter((output) => output.error)
+          .forEach((output) => console.warn(`[StreamRender] ${output.format} output failed:`, output.error));
 
         // Clear export context
         clearExportContext();
+        setCaptureTransparent(false);
+        setCaptureColorSpace('srgb');
 
-        const { copyFile, remove } = await import('@tauri-apps/plugin-fs');
-        await copyFile(tempOutputPath, outputPath);
-        await remove(tempOutputPath);
-
-        console.log('[StreamRender] Export complete:', outputPath);
+        console.log('[StreamRender] Export complete:', finalPath);
         setState((prev) => ({
           ...prev,
           isRendering: false,
           progress: 1,
+          outputPath: finalPath,
           phase: 'done',
         }));
         setRenderStatus({
@@ -463,10 +615,12 @@ export const useStreamingRender = () => {
           phase: 'done',
           format,
           isImageExport: false,
+          report,
+          warnings: report.warnings,
         });
 
         // Reveal exported file in Finder
-        await revealInFileManager(outputPath);
+        await revealInFileManager(finalPath);
       } catch (error) {
         const errorMsg = (error as Error).message || String(error);
         console.error('[StreamRender] Error:', errorMsg);
@@ -481,6 +635,8 @@ export const useStreamingRender = () => {
 
         // Clean up export context
         clearExportContext();
+        setCaptureTransparent(false);
+        setCaptureColorSpace('srgb');
 
         // Cleanup encoder if needed
         if (encoderIdRef.current) {
@@ -491,16 +647,9 @@ export const useStreamingRender = () => {
           }
           encoderIdRef.current = null;
         }
-
-        try {
-          const { remove } = await import('@tauri-apps/plugin-fs');
-          await remove(tempOutputPath);
-        } catch {
-          // Ignore cleanup errors
-        }
       }
     },
-    [resetState, setRenderStatus, canvasWidth, canvasHeight, renderQuality]
+    [resetState, setRenderStatus, canvasWidth, canvasHeight, renderQuality, collisionPolicy, duckMusic, loudnessTarget, audioCodec, audioBitrateKbps, audioTailFadeMs, silentAudioTrack, transparentExport, videoCodec, animationLossless, animationQuality, animationLoopCount, gifFps, gifColors, gifDither, gifDitherStrength, gifPalette, gifLoopCount, gifMaxBytes, sequenceFormat, sequencePattern, sequenceZip, extraFormats, exportColorSpace]
   );
 
   const cancel = useCallback(async () => {
@@ -516,6 +665,8 @@ export const useStreamingRender = () => {
 
     // 3. Clean up export context
     clearExportContext();
+    setCaptureTransparent(false);
+    setCaptureColorSpace('srgb');
 
     // 4. Clean up backend process
     if (encoderIdRef.current) {
@@ -4,7 +4,23 @@ import type { AnimationSpeed } from '../constants/layoutAnimationPresets';
 export type { ImageLayout } from '../constants/layouts';
 import type { ImageLayout } from '../constants/layouts';
 
-export type ExportFormat = 'mp4' | 'webm' | 'mov' | 'png' | 'gif' | 'webp';
+export type ExportFormat =
+  | 'mp4'
+  | 'mkv'
+  | 'webm'
+  | 'mov'
+  | 'png'
+  | 'gif'
+  | 'webp'
+  | 'png_sequence'
+  | 'image_sequence'
+  | 'animated_webp'
+  | 'apng'
+  | 'avif';
+// 'auto' keeps each format's default (H.264, VP9 for webm)
+export type VideoCodec = 'auto' | 'h264' | 'hevc' | 'av1';
+// What the encoder does when the export file already exists
+export type CollisionPolicy = 'overwrite' | 'increment' | 'fail';
 
 type RenderSettings = {
   durationMs: number;
@@ -21,8 +37,98 @@ export type RenderStatus = {
   phase: 'idle' | 'capturing' | 'encoding' | 'done';
   format?: ExportFormat;
   isImageExport?: boolean;
+  report?: RenderReport;
+  outputs?: OutputStatus[]; // Per-output progress while a multi-output export streams
+  warnings?: string[]; // Shown after an export that finished with problems
 };
 
+// Loudness values measured by the first loudnorm pass (LUFS / dBTP / LU)
+export type LoudnessMeasurement = {
+  target_i: number;
+  input_i: number;
+  input_tp: number;
+  input_lra: number;
+  input_thresh: number;
+  target_offset: number;
+};
+
+// Decoded audio length of the export vs. frames / fps
+export type AudioConformance = {
+  expected_ms: number;
+  audio_ms: number;
+  container_ms: number | null;
+  within_tolerance: boolean;
+};
+
+// One encode of the GIF size-budget search
+export type GifAttempt = {
+  fps: number;
+  max_colors: number;
+  scale: number;
+  bytes: number;
+  fits: boolean;
+};
+
+// One output of a streaming export; path is null when it failed
+export type OutputResult = {
+  format: string;
+  path: string | null;
+  error: string | null;
+  frames: number;
+  loudness: LoudnessMeasurement | null;
+  audio_conformance: AudioConformance | null;
+  gif_attempts: GifAttempt[] | null;
+  warnings: string[];
+};
+
+// Returned by get_streaming_outputs while frames stream
+export type OutputStatus = {
+  format: string;
+  path: string;
+  frames: number;
+  progress: number;
+  error: string | null;
+};
+
+// Returned by finish_streaming_encode; top-level fields describe the first output that succeeded
+export type RenderReport = {
+  path: string;
+  frames: number;
+  loudness: LoudnessMeasurement | null;
+  audio_conformance: AudioConformance | null;
+  gif_attempts: GifAttempt[] | null;
+  warnings: string[]; // Every output's warnings, prefixed with the format when there are several
+  outputs: OutputResult[];
+};
+
+// Returned by estimate_export_size; free bytes are null where the OS can't tell
+export type ExportEstimate = {
+  output_bytes: number;
+  temp_bytes: number;
+  output_free_bytes: number | null;
+  temp_free_bytes: number | null;
+  sufficient: boolean;
+  warning: string | null;
+};
+
+// Colour space the canvas is captured in and the export is tagged with
+export type ExportColorSpace = 'srgb' | 'display_p3';
+
+// Frame file type of an image_sequence export
+export type SequenceFormat = 'png' | 'png16' | 'tiff' | 'webp';
+
+export type GifDither = 'bayer' | 'heckbert' | 'floyd_steinberg' | 'sierra2' | 'sierra2_4a' | 'none';
+export type GifPalette = 'global' | 'per_frame';
+
+// Integrated loudness targets: -14 YouTube/social, -16 App Store, -23 EBU broadcast
+export type LoudnessTarget = 'off' | -14 | -16 | -23;
+
+// 'auto' = AAC-LC for mp4/mov, Opus for webm; 'pcm' is mov only
+export type AudioCodec = 'auto' | 'aac' | 'opus' | 'pcm';
+
+// 'auto' adds a silent track only for frame presets whose platform requires audio
+export type SilentAudioTrack = 'auto' | 'on' | 'off';
+
 type StylePreset = 'default' | 'glass-light' | 'glass-dark' | 'neon-glow' | 'cyber' | 'gradient-border' | 'frost' | 'liquid' | 'hologram' | 'inset-dark' | 'outline' | 'border' | 'double-border';
 type ShadowType = 'none' | 'soft' | 'float' | 'dream' | 'glow';
 export type AspectRatio = 'free' | '1:1' | '4:5' | '9:16' | '16:9' | '3:4' | '4:3' | '21:9' | '2:3' | '3:2';
@@ -137,8 +243,33 @@ type RenderStore = RenderSettings & {
   renderStatus: RenderStatus;
   renderQuality: '1080p' | '4k';
   fastExport: boolean;
+  collisionPolicy: CollisionPolicy;
   includeAudio: boolean;
   cameraAudioEnabled: boolean;
+  duckMusic: boolean; // Lower music while camera audio plays
+  loudnessTarget: LoudnessTarget;
+  audioCodec: AudioCodec;
+  audioBitrateKbps: number;
+  audioTailFadeMs: number; // Fade-out at the very end of the export, 0 = off
+  silentAudioTrack: SilentAudioTrack;
+  transparentExport: boolean; // Keep alpha in video exports (webm, mov, gif, PNG sequence)
+  exportColorSpace: ExportColorSpace; // Display P3 for wide-gamut screenshots; GIF/WebP stay sRGB
+  videoCodec: VideoCodec; // Applies to mp4, mkv and webm
+  // Animated WebP / APNG / AVIF
+  animationLossless: boolean; // WebP only
+  animationQuality: number; // 0-100
+  animationLoopCount: number; // 0 = loop forever
+  gifFps: number;
+  gifColors: number; // Palette size, 2-256
+  gifDither: GifDither;
+  gifDitherStrength: number; // 0-5, 0 = no dithering
+  gifPalette: GifPalette;
+  gifLoopCount: number; // 0 = loop forever
+  gifMaxBytes: number | null; // Re-encode smaller until the GIF fits, null = no limit
+  sequenceFormat: SequenceFormat;
+  sequencePattern: string; // File name with one frame counter, e.g. 'frame_%05d'
+  sequenceZip: boolean; // Zip the frames instead of leaving a folder
+  extraFormats: ExportFormat[]; // Also written from the same frames as the chosen format
   // Setters
   setRotationX: (deg: number) => void;
   setRotationY: (deg: number) => void;
@@ -158,8 +289,32 @@ type RenderStore = RenderSettings & {
   setFps: (fps: number) => void;
   setRenderQuality: (quality: '1080p' | '4k') => void;
   setFastExport: (fast: boolean) => void;
+  setCollisionPolicy: (policy: CollisionPolicy) => void;
   setIncludeAudio: (include: boolean) => void;
   setCameraAudioEnabled: (enabled: boolean) => void;
+  setDuckMusic: (duck: boolean) => void;
+  setLoudnessTarget: (target: LoudnessTarget) => void;
+  setAudioCodec: (codec: AudioCodec) => void;
+  setAudioBitrateKbps: (kbps: number) => void;
+  setAudioTailFadeMs: (ms: number) => void;
+  setSilentAudioTrack: (mode: SilentAudioTrack) => void;
+  setTransparentExport: (transparent: boolean) => void;
+  setExportColorSpace: (colorSpace: ExportColorSpace) => void;
+  setVideoCodec: (codec: VideoCodec) => void;
+  setAnimationLossless: (lossless: boolean) => void;
+  setAnimationQuality: (quality: number) => void;
+  setAnimationLoopCount: (count: number) => void;
+  setGifFps: (fps: number) => void;
+  setGifColors: (colors: number) => void;
+  setGifDither: (dither: GifDither) => void;
+  setGifDitherStrength: (strength: number) => void;
+  setGifPalette: (palette: GifPalette) => void;
+  setGifLoopCount: (count: number) => void;
+  setGifMaxBytes: (bytes: number | null) => void;
+  setSequenceFormat: (format: SequenceFormat) => void;
+  setSequencePattern: (pattern: string) => void;
+  setSequenceZip: (zip: boolean) => void;
+  setExtraFormats: (formats: ExportFormat[]) => void;
   setOutputName: (outputName: string) => void;
   setStylePreset: (preset: StylePreset) => void;
   setShadowType: (type: ShadowType) => void;
@@ -243,8 +398,32 @@ export const useRenderStore = create<RenderStore>((set) => ({
   renderStatus: initialRenderStatus,
   renderQuality: '1080p',
   fastExport: false,
+  collisionPolicy: 'increment',
   includeAudio: true,
   cameraAudioEnabled: true,
+  duckMusic: false,
+  loudnessTarget: 'off',
+  audioCodec: 'auto',
+  audioBitrateKbps: 192,
+  audioTailFadeMs: 0,
+  silentAudioTrack: 'auto',
+  transparentExport: false,
+  exportColorSpace: 'srgb',
+  videoCodec: 'auto',
+  animationLossless: false,
+  animationQuality: 80,
+  animationLoopCount: 0,
+  gifFps: 15,
+  gifColors: 256,
+  gifDither: 'floyd_steinberg',
+  gifDitherStrength: 5,
+  gifPalette: 'global',
+  gifLoopCount: 0,
+  gifMaxBytes: null,
+  sequenceFormat: 'png',
+  sequencePattern: 'frame_%05d',
+  sequenceZip: false,
+  extraFormats: [],
   
   frameMode: 'device',
   deviceType: 'iphone',
@@ -304,8 +483,32 @@ export const useRenderStore = create<RenderStore>((set) => ({
   setFps: (fps) => set({ fps }),
   setRenderQuality: (renderQuality) => set({ renderQuality }),
   setFastExport: (fastExport) => set({ fastExport }),
+  setCollisionPolicy: (collisionPolicy) => set({ collisionPolicy }),
   setIncludeAudio: (includeAudio) => set({ includeAudio }),
   setCameraAudioEnabled: (cameraAudioEnabled) => set({ cameraAudioEnabled }),
+  setDuckMusic: (duckMusic) => set({ duckMusic }),
+  setLoudnessTarget: (loudnessTarget) => set({ loudnessTarget }),
+  setAudioCodec: (audioCodec) => set({ audioCodec }),
+  setAudioBitrateKbps: (audioBitrateKbps) => set({ audioBitrateKbps }),
+  setAudioTailFadeMs: (audioTailFadeMs) => set({ audioTailFadeMs }),
+  setSilentAudioTrack: (silentAudioTrack) => set({ silentAudioTrack }),
+  setTransparentExport: (transparentExport) => set({ transparentExport }),
+  setExportColorSpace: (exportColorSpace) => set({ exportColorSpace }),
+  setVideoCodec: (videoCodec) => set({ videoCodec }),
+  setAnimationLossless: (animationLossless) => set({ animationLossless }),
+  setAnimationQuality: (animationQuality) => set({ animationQuality }),
+  setAnimationLoopCount: (animationLoopCount) => set({ animationLoopCount }),
+  setGifFps: (gifFps) => set({ gifFps }),
+  setGifColors: (gifColors) => set({ gifColors }),
+  setGifDither: (gifDither) => set({ gifDither }),
+  setGifDitherStrength: (gifDitherStrength) => set({ gifDitherStrength }),
+  setGifPalette: (gifPalette) => set({ gifPalette }),
+  setGifLoopCount: (gifLoopCount) => set({ gifLoopCount }),
+  setGifMaxBytes: (gifMaxBytes) => set({ gifMaxBytes }),
+  setSequenceFormat: (sequenceFormat) => set({ sequenceFormat }),
+  setSequencePattern: (sequencePattern) => set({ sequencePattern }),
+  setSequenceZip: (sequenceZip) => set({ sequenceZip }),
+  setExtraFormats: (extraFormats) => set({ extraFormats }),
   setOutputName: (outputName) => set({ outputName }),
   setStylePreset: (stylePreset) => set({ stylePreset }),
   setShadowType: (shadowType) => set({ shadowType }),
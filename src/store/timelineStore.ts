@@ -54,6 +54,13 @@ export type TimelineClip = {
     mediaUrl?: string;
     volume?: number;
     slotIndex?: number;
+    sourceInMs?: number; // Offset into the source media where the clip starts
+    sourceDurationMs?: number; // Full length of the source media, when known
+    playbackRate?: number;
+    fadeInMs?: number;
+    fadeOutMs?: number;
+    loop?: boolean; // Repeat audio until the end of the export
+    loopCrossfadeMs?: number;
   };
 };
 
@@ -0,0 +1,98 @@
+import { invoke } from '@tauri-apps/api/core';
+import { type TempWorkspace, writeMediaFile } from './mediaUtils';
+
+type ServedVideo = {
+  clipId: string;
+  canvas: HTMLCanvasElement;
+};
+
+// <video> elements whose export frames come from the backend frame server
+const servedVideos = new Map<HTMLVideoElement, ServedVideo>();
+// Holds the source files the decoders read; released with the clips
+let sourceWorkspace: TempWorkspace | null = null;
+
+// Time inside the clip for a timeline time, clamped to the clip like the preview player
+export const clipTimeMs = (video: HTMLVideoElement, timeMs: number): number => {
+  const clipStartMs = Number(video.dataset.clipStartMs) || 0;
+  const clipDurationMs = Number(video.dataset.clipDurationMs) || 0;
+  const relativeMs = Math.max(0, timeMs - clipStartMs);
+  return clipDurationMs > 0 ? Math.min(relativeMs, clipDurationMs) : relativeMs;
+};
+
+// Served frame to draw instead of the <video> element, if the clip is open
+export const servedVideoFrame = (video: HTMLVideoElement): HTMLCanvasElement | undefined =>
+  servedVideos.get(video)?.canvas;
+
+/**
+ * Open every <video> under `node` in the frame server at the export `fps`.
+ * Each source is copied into a temp workspace first, since uploads only exist as blob: URLs.
+ * Returns false (with nothing left open) if any clip can't be served; callers fall back to seeking.
+ */
+export const openVideoFrameSources = async (node: HTMLElement, fps: number, maxSide: number): Promise<boolean> => {
+  await closeVideoFrameSources();
+  const videos = Array.from(node.querySelectorAll('video'));
+  if (videos.length === 0) return false;
+
+  try {
+    const workspace = await invoke<TempWorkspace>('create_temp_workspace', { label: 'video_sources' });
+    sourceWorkspace = workspace;
+    const written = new Map<string, string>();
+
+    for (const [index, video] of videos.entries()) {
+      const url = video.currentSrc || video.src;
+      if (!url || video.videoWidth === 0 || video.videoHeight === 0) {
+        throw new Error(`Video ${index} has no loaded source`);
+      }
+
+      let path = written.get(url);
+      if (!path) {
+        path = await writeMediaFile(url, workspace.path, `source_${written.size}`);
+        written.set(url, path);
+      }
+
+      const clipId = `${workspace.job_id}_${index}`;
+      await invoke('open_video_clip', { clipId, path, fps });
+
+      // Native size, but no larger than the export frame - bigger frames would only be scaled down
+      const fit = Math.min(1, maxSide / Math.max(video.videoWidth, video.videoHeight));
+      const canvas = document.createElement('canvas');
+      canvas.width = Math.max(1, Math.round(video.videoWidth * fit));
+      canvas.height = Math.max(1, Math.round(video.videoHeight * fit));
+      servedVideos.set(video, { clipId, canvas });
+    }
+    return true;
+  } catch (error) {
+    console.warn('[FrameServer] Falling back to <video> seeking:', error);
+    await closeVideoFrameSources();
+    return false;
+  }
+};
+
+// Fetch the exact frame of every served clip at timeline time `timeMs`
+export const seekVideoFrames = async (timeMs: number): Promise<void> => {
+  await Promise.all(
+    Array.from(servedVideos, async ([video, { clipId, canvas }]) => {
+      const frame = await invoke<ArrayBuffer>('get_video_frame', {
+        clipId,
+        timeMs: clipTimeMs(video, timeMs),
+        width: canvas.width,
+        height: canvas.height,
+      });
+      const pixels = new ImageData(new Uint8ClampedArray(frame), canvas.width, canvas.height);
+      canvas.getContext('2d')!.putImageData(pixels, 0, 0);
+    })
+  );
+};
+
+// Stop the decoders and delete the copied sources
+export const closeVideoFrameSources = async (): Promise<void> => {
+  const clips = Array.from(servedVideos.values());
+  servedVideos.clear();
+  await Promise.all(clips.map(({ clipId }) => invoke('close_video_clip', { clipId }).catch(() => {})));
+
+  if (sourceWorkspace) {
+    const { job_id } = sourceWorkspace;
+    sourceWorkspace = null;
+    await invoke('release_temp_workspace', { jobId: job_id }).catch(() => {});
+  }
+};
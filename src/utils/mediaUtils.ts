@@ -1,3 +1,6 @@
+import { join } from '@tauri-apps/api/path';
+import { writeFile } from '@tauri-apps/plugin-fs';
+
 export const getVideoDuration = (url: string): Promise<number> => {
   return new Promise((resolve) => {
     const video = document.createElement('video');
@@ -10,3 +13,19 @@ export const getVideoDuration = (url: string): Promise<number> => {
     video.src = url;
   });
 };
+
+export type TempWorkspace = { job_id: string; path: string };
+
+const EXTENSIONS: Record<string, string> = {
+  'video/mp4': 'mp4',
+  'video/quicktime': 'mov',
+  'video/webm': 'webm',
+};
+
+// Backend media commands need a file path, but uploads only exist as blob: URLs - write a copy into `dir`
+export const writeMediaFile = async (url: string, dir: string, name: string): Promise<string> => {
+  const blob = await (await fetch(url)).blob();
+  const path = await join(dir, `${name}.${EXTENSIONS[blob.type] ?? 'mp4'}`);
+  await writeFile(path, new Uint8Array(await blob.arrayBuffer()));
+  return path;
+};
@@ -1,5 +1,5 @@
-import { downloadDir, join } from '@tauri-apps/api/path';
-import { mkdir } from '@tauri-apps/plugin-fs';
+import { invoke } from '@tauri-apps/api/core';
+import { clipTimeMs, closeVideoFrameSources, servedVideoFrame } from './frameServer';
 import {
   ANIMATION_SPEED_MULTIPLIERS,
   generateDeviceKeyframes,
@@ -119,12 +119,7 @@ export const pauseAndSeekVideos = async (node: HTMLElement, timeMs: number): Pro
     return new Promise<void>((resolve) => {
       video.pause();
 
-      const clipStartMs = Number(video.dataset.clipStartMs) || 0;
-      const clipDurationMs = Number(video.dataset.clipDurationMs) || 0;
-      const relativeMs = Math.max(0, timeMs - clipStartMs);
-      const limitedMs =
-        clipDurationMs > 0 ? Math.min(relativeMs, clipDurationMs) : relativeMs;
-      const targetSeconds = Math.max(0, limitedMs) / 1000;
+      const targetSeconds = clipTimeMs(video, timeMs) / 1000;
 
       // If already at target time and has data, resolve immediately (more tolerance)
       if (Math.abs(video.currentTime - targetSeconds) < 0.05 && video.readyState >= 2) {
@@ -172,17 +167,8 @@ export const pauseAndSeekVideos = async (node: HTMLElement, timeMs: number): Pro
 export const waitForRender = (ms = 0) =>
   ms > 0 ? new Promise<void>((resolve) => setTimeout(resolve, ms)) : Promise.resolve();
 
-// Get Liike export folder
-export const getExportFolder = async (): Promise<string> => {
-  const downloads = await downloadDir();
-  const liikeFolder = await join(downloads, 'Liike');
-  try {
-    await mkdir(liikeFolder, { recursive: true });
-  } catch {
-    // Folder might already exist
-  }
-  return liikeFolder;
-};
+// Get Liike export folder (created and added to the fs scope by the backend)
+export const getExportFolder = (): Promise<string> => invoke<string>('get_export_folder');
 
 // Generate unique filename
 export const getVideoFilename = (
@@ -192,7 +178,10 @@ export const getVideoFilename = (
   ext: string
 ): string => {
   const timestamp = new Date().toISOString().replace(/[:.]/g, '-').slice(0, 19);
-  return `${baseName}_${timestamp}_${width}x${height}.${ext}`;
+  // No extension for folder outputs (image sequences)
+  return ext
+    ? `${baseName}_${timestamp}_${width}x${height}.${ext}`
+    : `${baseName}_${timestamp}_${width}x${height}`;
 };
 
 export const getFileExtension = (format: ExportFormat): string => {
@@ -201,12 +190,26 @@ export const getFileExtension = (format: ExportFormat): string => {
       return 'webm';
     case 'mov':
       return 'mov';
+    case 'mkv':
+      return 'mkv';
     case 'png':
       return 'png';
     case 'gif':
       return 'gif';
     case 'webp':
       return 'webp';
+    case 'png_sequence':
+      return 'zip';
+    case 'animated_webp':
+      return 'webp';
+    case 'apng':
+      // Browsers and GitHub READMEs play APNG from a plain .png
+      return 'png';
+    case 'avif':
+      return 'avif';
+    case 'image_sequence':
+      // A folder of frames; zipped sequences get '.zip' from the caller
+      return '';
     case 'mp4':
     default:
       return 'mp4';
@@ -1285,6 +1288,8 @@ export const clearExportContext = () => {
   reusableDeviceImg = null;
   captureCanvas = null;
   captureCtx = null;
+  // Stop frame server decoders opened for this export
+  void closeVideoFrameSources();
 };
 
 // Accelerated SVG rendering using Blob URLs and direct style synchronization
@@ -1819,17 +1824,24 @@ const captureVideoFrames = (
   const videos = node.querySelectorAll('video');
 
   for (const video of videos) {
-    // Skip if video not ready or has no dimensions
-    if (video.readyState < 2 || video.videoWidth === 0 || video.videoHeight === 0) {
-      console.warn('[captureFrame] Video not ready:', video.readyState);
-      continue;
-    }
+    // Exact decoded frame from the frame server when the clip is open there
+    const served = servedVideoFrame(video);
+    if (!served) {
+      // Skip if video not ready or has no dimensions
+      if (video.readyState < 2 || video.videoWidth === 0 || video.videoHeight === 0) {
+        console.warn('[captureFrame] Video not ready:', video.readyState);
+        continue;
+      }
 
-    // Skip if video doesn't have crossOrigin set (would taint canvas)
-    if (!video.crossOrigin) {
-      console.warn('[captureFrame] Skipping video without crossOrigin - would taint canvas');
-      continue;
+      // Skip if video doesn't have crossOrigin set (would taint canvas)
+      if (!video.crossOrigin) {
+        console.warn('[captureFrame] Skipping video without crossOrigin - would taint canvas');
+        continue;
+      }
     }
+    const source: CanvasImageSource = served ?? video;
+    const sourceWidth = served?.width ?? video.videoWidth;
+    const sourceHeight = served?.height ?? video.videoHeight;
 
     const videoRect = video.getBoundingClientRect();
 
@@ -1862,27 +1874,27 @@ const captureVideoFrames = (
     // Handle object-fit - wrap in try-catch to prevent canvas taint errors
     try {
       if (objectFit === 'cover') {
-        const videoAspect = video.videoWidth / video.videoHeight;
+        const videoAspect = sourceWidth / sourceHeight;
         const containerAspect = relW / relH;
 
         let srcX = 0,
           srcY = 0,
-          srcW = video.videoWidth,
-          srcH = video.videoHeight;
+          srcW = sourceWidth,
+          srcH = sourceHeight;
 
         if (videoAspect > containerAspect) {
           // Video is wider - crop sides
-          srcW = video.videoHeight * containerAspect;
-          srcX = (video.videoWidth - srcW) / 2;
+          srcW = sourceHeight * containerAspect;
+          srcX = (sourceWidth - srcW) / 2;
         } else {
           // Video is taller - crop top/bottom
-          srcH = video.videoWidth / containerAspect;
-          srcY = (video.videoHeight - srcH) / 2;
+          srcH = sourceWidth / containerAspect;
+          srcY = (sourceHeight - srcH) / 2;
         }
 
-        ctx.drawImage(video, srcX, srcY, srcW, srcH, relX, relY, relW, relH);
+        ctx.drawImage(source, srcX, srcY, srcW, srcH, relX, relY, relW, relH);
       } else if (objectFit === 'contain') {
-        const videoAspect = video.videoWidth / video.videoHeight;
+        const videoAspect = sourceWidth / sourceHeight;
         const containerAspect = relW / relH;
 
         let destX = relX,
@@ -1898,10 +1910,10 @@ const captureVideoFrames = (
           destX = relX + (relW - destW) / 2;
         }
 
-        ctx.drawImage(video, 0, 0, video.videoWidth, video.videoHeight, destX, destY, destW, destH);
+        ctx.drawImage(source, 0, 0, sourceWidth, sourceHeight, destX, destY, destW, destH);
       } else {
         // fill or other - stretch to fit
-        ctx.drawImage(video, relX, relY, relW, relH);
+        ctx.drawImage(source, relX, relY, relW, relH);
       }
     } catch (e) {
       console.warn('[captureFrame] Failed to draw video - possible CORS issue:', e);
@@ -1916,6 +1928,22 @@ let captureCanvas: HTMLCanvasElement | null = null;
 let captureCtx: CanvasRenderingContext2D | null = null;
 let lastCanvasSize = { width: 0, height: 0 };
 
+// Transparent exports skip the background fill and layer so the frame keeps its alpha
+let captureTransparent = false;
+let lastCanvasTransparent = false;
+
+export const setCaptureTransparent = (transparent: boolean) => {
+  captureTransparent = transparent;
+};
+
+// Display P3 captures keep wide-gamut colours; pixels come back in the canvas colour space
+let captureColorSpace: PredefinedColorSpace = 'srgb';
+let lastCanvasColorSpace: PredefinedColorSpace = 'srgb';
+
+export const setCaptureColorSpace = (colorSpace: PredefinedColorSpace) => {
+  captureColorSpace = colorSpace;
+};
+
 // Helper: Capture a single frame from DOM to Canvas
 export const captureFrame = async (
   node: HTMLElement,
@@ -1928,18 +1956,22 @@ export const captureFrame = async (
   if (
     !captureCanvas ||
     lastCanvasSize.width !== outputWidth ||
-    lastCanvasSize.height !== outputHeight
+    lastCanvasSize.height !== outputHeight ||
+    lastCanvasTransparent !== captureTransparent ||
+    lastCanvasColorSpace !== captureColorSpace
   ) {
     captureCanvas = document.createElement('canvas');
     captureCanvas.width = outputWidth;
     captureCanvas.height = outputHeight;
-    // Use alpha: false for better performance and color accuracy in video export
     captureCtx = captureCanvas.getContext('2d', {
       willReadFrequently: true,
-      alpha: false, // Faster compositing, no transparency needed for final output
+      alpha: captureTransparent, // Opaque output composites faster; alpha only when exporting transparency
       desynchronized: true, // Better performance - don't sync with display
+      colorSpace: captureColorSpace,
     })!;
     lastCanvasSize = { width: outputWidth, height: outputHeight };
+    lastCanvasTransparent = captureTransparent;
+    lastCanvasColorSpace = captureColorSpace;
   }
 
   const ctx = captureCtx!;
@@ -1975,8 +2007,10 @@ export const captureFrame = async (
   }
 
   // Fill background first (fallback color)
-  ctx.fillStyle = computedStyle.backgroundColor || '#000000';
-  ctx.fillRect(0, 0, outputWidth, outputHeight);
+  if (!captureTransparent) {
+    ctx.fillStyle = computedStyle.backgroundColor || '#000000';
+    ctx.fillRect(0, 0, outputWidth, outputHeight);
+  }
 
   // Use fast path if export context is prepared and matches size
   if (
@@ -2062,11 +2096,13 @@ export const captureFrame = async (
     context.imageSmoothingQuality = 'high';
 
     // Clear with solid background for better color accuracy
-    context.fillStyle = '#000000';
-    context.fillRect(0, 0, outputWidth, outputHeight);
+    if (!captureTransparent) {
+      context.fillStyle = '#000000';
+      context.fillRect(0, 0, outputWidth, outputHeight);
 
-    // A. Background Layer
-    context.drawImage(bgSource!, 0, 0);
+      // A. Background Layer
+      context.drawImage(bgSource!, 0, 0);
+    }
 
     // B. Video Layer (captured directly from source videos at full quality)
     // Use locked rect to prevent jitter from DOM position changes